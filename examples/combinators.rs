@@ -0,0 +1,65 @@
+extern crate smoke;
+
+use smoke::async::{Task, Stream};
+use std::time::Instant;
+
+//----------------------------------
+// Benchmark helper.
+//----------------------------------
+fn bench<F>(name: &'static str, iterations: u32, func: F) where F: Fn() {
+  let start = Instant::now();
+  for _ in 0..iterations {
+    func();
+  }
+  let elapsed = start.elapsed();
+  println!("{:<24} {:>10?} per iteration", name, elapsed / iterations);
+}
+
+fn increment(value: i32) -> Task<i32> {
+  Task::new(move |sender| sender.send(value + 1))
+}
+
+fn main() {
+  //-------------------------
+  // task combinators
+  //-------------------------
+  bench("task new/wait", 1000, || {
+    increment(0).wait().unwrap();
+  });
+  bench("task map x5", 1000, || {
+    increment(0).map(|n| n.unwrap() + 1)
+                .map(|n| n.unwrap() + 1)
+                .map(|n| n.unwrap() + 1)
+                .map(|n| n.unwrap() + 1)
+                .map(|n| n.unwrap() + 1)
+                .wait()
+                .unwrap();
+  });
+  bench("task then x5", 1000, || {
+    increment(0).then(increment)
+                .then(increment)
+                .then(increment)
+                .then(increment)
+                .then(increment)
+                .wait()
+                .unwrap();
+  });
+  bench("task all x16", 100, || {
+    Task::all(4, (0..16).map(increment).collect()).wait().unwrap();
+  });
+  //-------------------------
+  // stream operators
+  //-------------------------
+  bench("stream range 10k", 10, || {
+    for _ in Stream::range(0, 10000).read() {}
+  });
+  bench("stream map/filter 10k", 10, || {
+    for _ in Stream::range(0, 10000).map(|n| n + 1)
+                                    .filter(|n| n % 2 == 0)
+                                    .map(|n| n * 2)
+                                    .read() {}
+  });
+  bench("stream fold 10k", 10, || {
+    Stream::range(0, 10000).fold(0, |p, c| p + c).wait().unwrap();
+  });
+}
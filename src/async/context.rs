@@ -0,0 +1,93 @@
+/*--------------------------------------------------------------------------
+ smoke-rs
+
+ The MIT License (MIT)
+
+ Copyright (c) 2016 Haydn Paterson (sinclair) <haydn.developer@gmail.com>
+
+ Permission is hereby granted, free of charge, to any person obtaining a copy
+ of this software and associated documentation files (the "Software"), to deal
+ in the Software without restriction, including without limitation the rights
+ to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ copies of the Software, and to permit persons to whom the Software is
+ furnished to do so, subject to the following conditions:
+
+ The above copyright notice and this permission notice shall be included in
+ all copies or substantial portions of the Software.
+
+ THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+ THE SOFTWARE.
+---------------------------------------------------------------------------*/
+
+use std::cell::Cell;
+use std::time::Instant;
+use super::super::error::Error;
+
+thread_local! {
+  /// The deadline of the task running on this thread.
+  static DEADLINE: Cell<Option<Instant>> = Cell::new(None);
+}
+
+/// Returns the deadline of the task running on the current thread, 
+/// if any. Deadlines are set with Task::with_deadline, and are carried
+/// through continuations and onto the schedulers the task runs tasks on.
+///
+/// # Example
+/// ```
+/// use std::time::{Duration, Instant};
+/// use smoke::async::{context, Task};
+///
+/// let deadline = Instant::now() + Duration::from_secs(1);
+/// let task     = Task::new(|sender| sender.send(context::deadline()));
+/// assert_eq!(task.with_deadline(deadline).wait().unwrap(), Some(deadline));
+/// ```
+pub fn deadline() -> Option<Instant> {
+  DEADLINE.with(|deadline| deadline.get())
+}
+
+/// Returns true if the deadline of the task running on the current
+/// thread has passed. Long running tasks can use this to stop early.
+pub fn is_expired() -> bool {
+  match deadline() {
+    Some(deadline) => Instant::now() >= deadline,
+    None           => false
+  }
+}
+
+/// Fails with DeadlineExceeded if the current deadline has passed.
+pub(crate) fn check() -> Result<(), Error> {
+  if is_expired() {
+    Err(Error::DeadlineExceeded)
+  } else {
+    Ok(())
+  }
+}
+
+/// Restores the previous deadline of this thread on drop.
+pub(crate) struct Scope {
+  previous: Option<Instant>
+}
+impl Drop for Scope {
+  fn drop(&mut self) {
+    let previous = self.previous;
+    DEADLINE.with(|deadline| deadline.set(previous));
+  }
+}
+
+/// Enters the given deadline on the current thread until the returned 
+/// scope is dropped. Deadlines only narrow, so if the thread already 
+/// has an earlier deadline, that deadline is kept.
+pub(crate) fn enter(deadline: Option<Instant>) -> Scope {
+  let previous = self::deadline();
+  let current  = match (previous, deadline) {
+    (Some(previous), Some(deadline)) => Some(if deadline < previous { deadline } else { previous }),
+    (previous, deadline)             => previous.or(deadline)
+  };
+  DEADLINE.with(|deadline| deadline.set(current));
+  Scope { previous: previous }
+}
@@ -0,0 +1,213 @@
+/*--------------------------------------------------------------------------
+ smoke-rs
+
+ The MIT License (MIT)
+
+ Copyright (c) 2016 Haydn Paterson (sinclair) <haydn.developer@gmail.com>
+
+ Permission is hereby granted, free of charge, to any person obtaining a copy
+ of this software and associated documentation files (the "Software"), to deal
+ in the Software without restriction, including without limitation the rights
+ to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ copies of the Software, and to permit persons to whom the Software is
+ furnished to do so, subject to the following conditions:
+
+ The above copyright notice and this permission notice shall be included in
+ all copies or substantial portions of the Software.
+
+ THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+ THE SOFTWARE.
+---------------------------------------------------------------------------*/
+
+use std::mem;
+use std::sync::{Arc, Mutex};
+use std::sync::mpsc::{
+  channel,
+  sync_channel,
+  Receiver,
+  Sender,
+  TryRecvError
+};
+use super::scheduling;
+use super::super::error::Error;
+
+/// The completion of a handle watched by select, with the selects 
+/// waiting to be notified of it.
+struct Watch {
+  done:      bool,
+  listeners: Vec<Sender<()>>
+}
+
+/// A waitable handle for scheduled issused by schedulers running tasks.
+///
+/// # Examples
+/// ```
+/// use std::time::Duration;
+/// use smoke::async::Task;
+/// use smoke::async::{Scheduler, ThreadScheduler};
+/// fn hello() -> Task<&'static str> {
+///   Task::delay_for(Duration::from_millis(1)).map(|_| "hello")
+/// }
+///
+/// fn main() {
+///   let scheduler = ThreadScheduler;
+///   let handle    = scheduler.run(hello());
+///   // sometime later...
+///   println!("{:?}", handle.wait());
+/// }
+/// ```
+pub struct TaskHandle<T, E = Error> {
+  receiver: Receiver<Result<T, E>>,
+  watch:    Option<Arc<Mutex<Watch>>>
+}
+impl<T, E> TaskHandle<T, E> where E: From<Error> {
+  
+  /// Creates a new wait handle. Wait handles are created
+  /// by schedulers when running tasks. When the task is
+  /// being run, a sync_channel is created, the sending
+  /// end is passed to the task, the receiving end is passed
+  /// here.
+  pub fn new(receiver: Receiver<Result<T, E>>) -> TaskHandle<T, E> {
+    TaskHandle { receiver: receiver, watch: None }
+  }
+  
+  /// Waits on the handles receiver. This method
+  /// will block the current thread while waiting
+  /// for a result.
+  pub fn wait(self) -> Result<T, E> {
+    match self.receiver.recv() {
+      Ok(result) => result,
+      Err(error) => Err(E::from(Error::from(error)))
+    }
+  }
+  
+  /// Checks the handles receiver without blocking. Returns None
+  /// if the task has not yet completed, otherwise returns the 
+  /// result. As the result can only be received once, later calls
+  /// return a error.
+  ///
+  /// # Example
+  /// ```
+  /// use smoke::async::{Task, ThreadScheduler};
+  ///
+  /// let handle = Task::from_value(10).schedule(ThreadScheduler);
+  /// loop {
+  ///   match handle.try_wait() {
+  ///     None         => { /* do other work */ },
+  ///     Some(result) => {
+  ///       assert_eq!(result.unwrap(), 10);
+  ///       break;
+  ///     }
+  ///   }
+  /// }
+  /// ```
+  pub fn try_wait(&self) -> Option<Result<T, E>> {
+    match self.receiver.try_recv() {
+      Ok(result)                      => Some(result),
+      Err(TryRecvError::Empty)        => None,
+      Err(TryRecvError::Disconnected) => Some(Err(E::from(Error::Recv)))
+    }
+  }
+}
+
+/// Blocks until the first of the given handles is ready. Returns the
+/// index of the ready handle, its result, and the remaining handles
+/// in their original order. While selected, each handle is watched 
+/// from a shared thread that notifies select once the handle is ready,
+/// with the remaining handles kept watched for later selects.
+///
+/// # Panics
+/// Panics if no handles are given.
+///
+/// # Example
+/// ```
+/// use std::time::Duration;
+/// use smoke::async::{handle, Task, ThreadScheduler};
+///
+/// let handles = vec![
+///   Task::delay_for(Duration::from_millis(100)).map(|_| 1).schedule(ThreadScheduler),
+///   Task::delay_for(Duration::from_millis(1)).map(|_| 2).schedule(ThreadScheduler)
+/// ];
+/// let (index, result, remaining) = handle::select(handles);
+/// assert_eq!(index, 1);
+/// assert_eq!(result.unwrap(), 2);
+/// assert_eq!(remaining.len(), 1);
+/// ```
+pub fn select<T, E>(mut handles: Vec<TaskHandle<T, E>>) -> (usize, Result<T, E>, Vec<TaskHandle<T, E>>) 
+  where T: Send + 'static, E: From<Error> + Send + 'static {
+  assert!(handles.len() > 0, "select: no handles given");
+  let (sender, receiver) = channel();
+  for handle in handles.iter_mut() {
+    let watch = watch(handle);
+    let mut watch = watch.lock().unwrap();
+    if !watch.done {
+      watch.listeners.push(sender.clone());
+    }
+  }
+  // handles completed before being listened to are found on
+  // the first pass, later handles notify the receiver.
+  loop {
+    for index in 0..handles.len() {
+      if let Some(result) = handles[index].try_wait() {
+        handles.remove(index);
+        return (index, result, handles);
+      }
+    }
+    let _ = receiver.recv();
+  }
+}
+
+/// Returns the watch of the given handle, watching the handle if not 
+/// yet watched. The watching thread receives the result in place of 
+/// the handle, passing it back to the handle before notifying the 
+/// listeners of the watch.
+fn watch<T, E>(handle: &mut TaskHandle<T, E>) -> Arc<Mutex<Watch>> where 
+  T: Send + 'static, 
+  E: From<Error> + Send + 'static {
+  if let Some(ref watch) = handle.watch {
+    return watch.clone();
+  }
+  let (sender, receiver) = sync_channel(1);
+  let upstream = mem::replace(&mut handle.receiver, receiver);
+  let watch    = Arc::new(Mutex::new(Watch { done: false, listeners: Vec::new() }));
+  let notify   = watch.clone();
+  scheduling::spawn(move || {
+    let result = match upstream.recv() {
+      Ok(result) => result,
+      Err(error) => Err(E::from(Error::from(error)))
+    };
+    let _ = sender.send(result);
+    let mut watch = notify.lock().unwrap();
+    watch.done = true;
+    for listener in watch.listeners.drain(..) {
+      let _ = listener.send(());
+    }
+  });
+  handle.watch = Some(watch.clone());
+  watch
+}
+
+/// Blocks until all of the given handles are ready, returning their
+/// results in order.
+///
+/// # Example
+/// ```
+/// use smoke::async::{handle, Task, ThreadScheduler};
+///
+/// let handles = vec![
+///   Task::from_value(1).schedule(ThreadScheduler),
+///   Task::from_value(2).schedule(ThreadScheduler)
+/// ];
+/// let results = handle::wait_all(handles);
+/// assert_eq!(results.len(), 2);
+/// ```
+pub fn wait_all<T, E>(handles: Vec<TaskHandle<T, E>>) -> Vec<Result<T, E>> where E: From<Error> {
+  handles.into_iter()
+         .map(|handle| handle.wait())
+         .collect()
+}
@@ -0,0 +1,180 @@
+/*--------------------------------------------------------------------------
+ smoke-rs
+
+ The MIT License (MIT)
+
+ Copyright (c) 2016 Haydn Paterson (sinclair) <haydn.developer@gmail.com>
+
+ Permission is hereby granted, free of charge, to any person obtaining a copy
+ of this software and associated documentation files (the "Software"), to deal
+ in the Software without restriction, including without limitation the rights
+ to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ copies of the Software, and to permit persons to whom the Software is
+ furnished to do so, subject to the following conditions:
+
+ The above copyright notice and this permission notice shall be included in
+ all copies or substantial portions of the Software.
+
+ THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+ THE SOFTWARE.
+---------------------------------------------------------------------------*/
+
+use std::sync::mpsc::{
+  sync_channel,
+  SendError
+};
+use std::panic::{self, AssertUnwindSafe};
+
+use super::task::{
+  Task,
+  TaskSender,
+  TaskFunc
+};
+use super::handle::TaskHandle;
+use super::scheduling::SyncScheduler;
+use super::super::error::Error;
+
+/// A task without Send bounds. Local tasks are run on the current
+/// thread only, allowing non Send types, such as Rc, to be used in
+/// purely synchronous pipelines.
+///
+/// # Example
+/// ```
+/// use std::rc::Rc;
+/// use smoke::async::LocalTask;
+///
+/// let task = LocalTask::new(|sender| sender.send(Rc::new(10)))
+///                      .map(|value| *value.unwrap() + 1);
+/// assert_eq!(task.wait().unwrap(), 11);
+/// ```
+pub struct LocalTask<T, E = Error> {
+  /// The closure to resolve this task.
+  pub func: Box<TaskFunc<TaskSender<T, E>, Result<(), SendError<T>>> + 'static>
+}
+impl<T> LocalTask<T> where T: 'static {
+  
+  /// Creates a new local task.
+  /// # Example
+  /// ```
+  /// use smoke::async::LocalTask;
+  ///
+  /// let task = LocalTask::new(|sender| sender.send("hello"));
+  /// ```
+  pub fn new<F>(func: F) -> LocalTask<T>
+    where F: FnOnce(TaskSender<T>) -> Result<(), SendError<T>> + 'static {
+      LocalTask { func: Box::new(func) }
+  }
+  
+  /// Creates a new local task that resolves immediately with the given value.
+  /// # Example
+  /// ```
+  /// use smoke::async::LocalTask;
+  ///
+  /// assert_eq!(LocalTask::from_value(10).wait().unwrap(), 10);
+  /// ```
+  pub fn from_value(value: T) -> LocalTask<T> {
+    LocalTask::new(move |sender| sender.send(value))
+  }
+}
+impl<T, E> LocalTask<T, E> where T: 'static, E: From<Error> + 'static {
+  
+  /// Creates a new local task with a custom error type.
+  pub fn fallible<F>(func: F) -> LocalTask<T, E>
+    where F: FnOnce(TaskSender<T, E>) -> Result<(), SendError<T>> + 'static {
+      LocalTask { func: Box::new(func) }
+  }
+  
+  /// Maps this task into another value.
+  /// # Example
+  /// ```
+  /// use smoke::async::LocalTask;
+  ///
+  /// let task = LocalTask::from_value(1).map(|n| n.unwrap() + 1);
+  /// assert_eq!(task.wait().unwrap(), 2);
+  /// ```
+  pub fn map<U, F>(self, func: F) -> LocalTask<U, E> where
+      U: 'static,
+      F: FnOnce(Result<T, E>) -> U + 'static {
+        LocalTask::<U, E>::fallible(move |sender| sender.send(func(self.wait())))
+  }
+  
+  /// Creates a new local task that runs this task followed by the next.
+  /// # Example
+  /// ```
+  /// use smoke::async::LocalTask;
+  ///
+  /// fn increment(value: i32) -> LocalTask<i32> {
+  ///   LocalTask::from_value(value + 1)
+  /// }
+  /// assert_eq!(increment(0).then(increment).wait().unwrap(), 2);
+  /// ```
+  pub fn then<F>(self, func: F) -> LocalTask<T, E> where
+      F: FnOnce(T) -> LocalTask<T, E> + 'static {
+        LocalTask::fallible(move |sender| {
+          match self.wait() {
+            Err(error) => sender.fail(error),
+            Ok(value)  => match func(value).wait() {
+              Err(error) => sender.fail(error),
+              Ok(value)  => sender.send(value)
+            }
+          }
+        })
+  }
+  
+  /// Waits synchronously for this task to complete. Panics raised 
+  /// while running the task are resolved as errors.
+  /// # Example
+  /// ```
+  /// use smoke::async::LocalTask;
+  ///
+  /// let task = LocalTask::new(|sender| sender.send(10));
+  /// assert_eq!(task.wait().unwrap(), 10);
+  /// ```
+  pub fn wait(self) -> Result<T, E> {
+    let (sender, receiver) = sync_channel(1);
+    let handle = TaskHandle::new(receiver);
+    let func   = self.func;
+    match panic::catch_unwind(AssertUnwindSafe(move || func.call(TaskSender::new(sender)))) {
+      Err(payload) => Err(E::from(Error::from_panic(payload))),
+      Ok(_)        => handle.wait()
+    }
+  }
+}
+impl<T, E> From<Task<T, E>> for LocalTask<T, E> where T: Send + 'static, E: From<Error> + Send + 'static {
+  /// Converts a task into a local task.
+  fn from(task: Task<T, E>) -> LocalTask<T, E> {
+    LocalTask { func: task.into_func() }
+  }
+}
+
+impl SyncScheduler {
+  /// Runs a local task on the current thread. Unlike Scheduler::run,
+  /// this does not require the task's value or error to be Send. As
+  /// with wait, panics raised while running the task are resolved as
+  /// errors on the returned handle.
+  /// # Example
+  /// ```
+  /// use std::rc::Rc;
+  /// use smoke::async::{LocalTask, SyncScheduler};
+  ///
+  /// let handle = SyncScheduler.run_local(LocalTask::new(|sender| sender.send(Rc::new(10))));
+  /// assert_eq!(*handle.wait().unwrap(), 10);
+  /// ```
+  pub fn run_local<T, E>(&self, task: LocalTask<T, E>) -> TaskHandle<T, E> where 
+      T: 'static, 
+      E: From<Error> + 'static {
+    let (sender, receiver) = sync_channel(1);
+    let handle  = TaskHandle::new(receiver);
+    let failure = sender.clone();
+    let func    = task.func;
+    if let Err(payload) = panic::catch_unwind(AssertUnwindSafe(move || func.call(TaskSender::new(sender)))) {
+      let _ = failure.try_send(Err(E::from(Error::from_panic(payload))));
+    }
+    handle
+  }
+}
@@ -27,16 +27,53 @@
 pub mod task;
 pub mod stream;
 pub mod scheduling;
+pub mod handle;
+pub mod retry;
+pub mod shared;
+pub mod local;
+pub mod observer;
+pub mod context;
+pub mod threadpool;
+pub mod scope;
 
-pub use self::scheduling::TaskHandle;
+pub use self::handle::TaskHandle;
 pub use self::scheduling::Scheduler;
+pub use self::scheduling::RecurringHandle;
 pub use self::scheduling::SyncScheduler;
 pub use self::scheduling::ThreadScheduler;
 pub use self::scheduling::ThreadPoolScheduler;
+pub use self::scheduling::ThreadPoolBuilder;
+pub use self::scheduling::QueuePolicy;
+pub use self::scheduling::PriorityScheduler;
+pub use self::scheduling::FairScheduler;
+pub use self::scheduling::FairGroup;
+pub use self::scheduling::DualPoolScheduler;
+pub use self::scheduling::EventLoopScheduler;
+pub use self::scheduling::AffinityScheduler;
+pub use self::scheduling::AffinityWorker;
+pub use self::scheduling::DefaultScheduler;
+pub use self::scheduling::set_default_scheduler;
 
 pub use self::task::Task;
+pub use self::task::TaskError;
+pub use self::task::Priority;
+pub use self::shared::SharedTask;
+pub use self::local::LocalTask;
+pub use self::scope::TaskScope;
+pub use self::scope::SchedulerScope;
+
+pub use self::observer::TaskObserver;
+pub use self::observer::TaskInfo;
+pub use self::observer::TaskSpan;
+
+pub use self::retry::Backoff;
+pub use self::retry::RetryPolicy;
 
 pub use self::stream::Stream;
+pub use self::stream::Backpressure;
 pub use self::stream::StreamSender;
 pub use self::stream::StreamReceiver;
+pub use self::stream::Publisher;
+pub use self::stream::Recording;
+pub use self::stream::StreamControl;
 pub use self::stream::ToStream;
\ No newline at end of file
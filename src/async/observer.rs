@@ -0,0 +1,209 @@
+/*--------------------------------------------------------------------------
+ smoke-rs
+
+ The MIT License (MIT)
+
+ Copyright (c) 2016 Haydn Paterson (sinclair) <haydn.developer@gmail.com>
+
+ Permission is hereby granted, free of charge, to any person obtaining a copy
+ of this software and associated documentation files (the "Software"), to deal
+ in the Software without restriction, including without limitation the rights
+ to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ copies of the Software, and to permit persons to whom the Software is
+ furnished to do so, subject to the following conditions:
+
+ The above copyright notice and this permission notice shall be included in
+ all copies or substantial portions of the Software.
+
+ THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+ THE SOFTWARE.
+---------------------------------------------------------------------------*/
+
+use std::fmt;
+use std::thread::{self, ThreadId};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+use super::context;
+use super::task::{Task, Priority};
+use super::super::error::Error;
+
+/// Information about a task passed to a TaskObserver.
+#[derive(Debug, Clone)]
+pub struct TaskInfo {
+  /// The name of the scheduler running the task.
+  pub scheduler: &'static str,
+  /// The unique id of the task.
+  pub id:        usize,
+  /// The name of the task, if named.
+  pub name:      Option<String>,
+  /// The priority the task was scheduled with.
+  pub priority:  Priority,
+  /// The deadline the task was scheduled with, if any.
+  pub deadline:  Option<Instant>,
+  /// True if the task is marked as blocking.
+  pub blocking:  bool,
+  /// The time the task was submitted to the scheduler.
+  pub enqueued:  Instant
+}
+impl TaskInfo {
+  pub(crate) fn new<T, E>(scheduler: &'static str, task: &Task<T, E>) -> TaskInfo where 
+      T: Send + 'static, 
+      E: From<Error> + Send + 'static {
+    TaskInfo {
+      scheduler: scheduler,
+      id:        task.id(),
+      name:      task.name().map(|name| name.to_string()),
+      priority:  task.priority(),
+      deadline:  context::deadline(),
+      blocking:  task.is_blocking(),
+      enqueued:  Instant::now()
+    }
+  }
+}
+impl fmt::Display for TaskInfo {
+  /// Formats the task as its id, followed by its name if named.
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self.name {
+      Some(ref name) => write!(f, "#{} '{}'", self.id, name),
+      None           => write!(f, "#{}", self.id)
+    }
+  }
+}
+
+/// A record of a task run on one of the built in schedulers, passed to
+/// TaskObserver::on_span once the task has finished, and logged at the 
+/// trace level when built with the `log` feature.
+#[derive(Debug, Clone)]
+pub struct TaskSpan {
+  /// The name of the scheduler that ran the task.
+  pub scheduler:   &'static str,
+  /// The unique id of the task.
+  pub id:          usize,
+  /// The name of the task, if named.
+  pub name:        Option<String>,
+  /// The time the task was submitted to the scheduler.
+  pub enqueued:    Instant,
+  /// The time the task began running.
+  pub started:     Instant,
+  /// The time the task finished running.
+  pub finished:    Instant,
+  /// The id of the thread that ran the task.
+  pub worker:      ThreadId,
+  /// The name of the thread that ran the task, if named.
+  pub worker_name: Option<String>
+}
+impl TaskSpan {
+  /// Returns the time the task waited between being submitted and running.
+  pub fn queued(&self) -> Duration {
+    self.started.duration_since(self.enqueued)
+  }
+  
+  /// Returns the time taken to run the task.
+  pub fn duration(&self) -> Duration {
+    self.finished.duration_since(self.started)
+  }
+}
+
+/// Observes the lifecycle of tasks run on the built in schedulers. 
+/// Observers are installed globally with set_observer(), and are 
+/// called from the threads scheduling and running each task, so 
+/// implementations should return quickly.
+///
+/// # Example
+/// ```
+/// use std::time::Duration;
+/// use smoke::async::{Task, TaskObserver, TaskInfo};
+/// use smoke::async::observer;
+///
+/// struct Latency;
+/// impl TaskObserver for Latency {
+///   fn on_completed(&self, info: &TaskInfo, duration: Duration) {
+///     println!("{} task took {:?}", info.scheduler, duration);
+///   }
+/// }
+///
+/// observer::set_observer(Latency);
+/// Task::from_value(1).wait().unwrap();
+/// observer::clear_observer();
+/// ```
+pub trait TaskObserver: Send + Sync {
+  
+  /// Called when a task is submitted to a scheduler.
+  fn on_scheduled(&self, _info: &TaskInfo) { }
+  
+  /// Called when a task begins running.
+  fn on_started(&self, _info: &TaskInfo) { }
+  
+  /// Called when a task has finished running, with the time taken to run it. 
+  fn on_completed(&self, _info: &TaskInfo, _duration: Duration) { }
+  
+  /// Called after on_completed with the span of the task run.
+  fn on_span(&self, _span: &TaskSpan) { }
+}
+
+static OBSERVER: RwLock<Option<Arc<TaskObserver>>> = RwLock::new(None);
+
+/// Installs the global task observer, replacing any existing observer.
+pub fn set_observer<O>(observer: O) where O: TaskObserver + 'static {
+  *OBSERVER.write().unwrap() = Some(Arc::new(observer));
+}
+
+/// Removes the global task observer.
+pub fn clear_observer() {
+  *OBSERVER.write().unwrap() = None;
+}
+
+// the observer is cloned out of the lock before being called, 
+// allowing observers to replace themselves without deadlocking.
+fn current() -> Option<Arc<TaskObserver>> {
+  match OBSERVER.read() {
+    Ok(observer) => observer.clone(),
+    Err(_)       => None
+  }
+}
+
+pub(crate) fn scheduled(info: &TaskInfo) {
+  if let Some(observer) = current() {
+    observer.on_scheduled(info);
+  }
+}
+
+pub(crate) fn started(info: &TaskInfo) {
+  if let Some(observer) = current() {
+    observer.on_started(info);
+  }
+}
+
+pub(crate) fn completed(info: &TaskInfo, duration: Duration) {
+  if let Some(observer) = current() {
+    observer.on_completed(info, duration);
+  }
+}
+
+pub(crate) fn traced(info: &TaskInfo, started: Instant, finished: Instant) {
+  let observer = current();
+  if observer.is_none() && !cfg!(feature = "log") {
+    return;
+  }
+  let worker = thread::current();
+  let span   = TaskSpan {
+    scheduler:   info.scheduler,
+    id:          info.id,
+    name:        info.name.clone(),
+    enqueued:    info.enqueued,
+    started:     started,
+    finished:    finished,
+    worker:      worker.id(),
+    worker_name: worker.name().map(|name| name.to_string())
+  };
+  log_trace!("{}: task {} span queued={:?} duration={:?} worker={:?}", 
+    span.scheduler, info, span.queued(), span.duration(), span.worker);
+  if let Some(observer) = observer {
+    observer.on_span(&span);
+  }
+}
@@ -0,0 +1,95 @@
+/*--------------------------------------------------------------------------
+ smoke-rs
+
+ The MIT License (MIT)
+
+ Copyright (c) 2016 Haydn Paterson (sinclair) <haydn.developer@gmail.com>
+
+ Permission is hereby granted, free of charge, to any person obtaining a copy
+ of this software and associated documentation files (the "Software"), to deal
+ in the Software without restriction, including without limitation the rights
+ to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ copies of the Software, and to permit persons to whom the Software is
+ furnished to do so, subject to the following conditions:
+
+ The above copyright notice and this permission notice shall be included in
+ all copies or substantial portions of the Software.
+
+ THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+ THE SOFTWARE.
+---------------------------------------------------------------------------*/
+
+use std::time::Duration;
+
+/// The backoff applied between retry attempts.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Backoff {
+  /// Retry immediately.
+  None,
+  /// Wait a fixed duration between attempts.
+  Fixed(Duration),
+  /// Wait an initial duration, doubling on each attempt up to the given maximum.
+  Exponential(Duration, Duration)
+}
+
+/// Describes how many times, and how often, a failed operation should be retried.
+///
+/// # Example
+/// ```
+/// use std::time::Duration;
+/// use smoke::async::RetryPolicy;
+///
+/// // retry 3 times, waiting 10, 20 then 40 milliseconds.
+/// let policy = RetryPolicy::exponential(3, Duration::from_millis(10), 
+///                                          Duration::from_secs(1));
+/// assert_eq!(policy.delay(3), Duration::from_millis(40));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RetryPolicy {
+  /// The number of retries to attempt after the first failure.
+  pub retries: usize,
+  /// The backoff applied between attempts.
+  pub backoff: Backoff
+}
+impl RetryPolicy {
+  
+  /// Creates a policy that retries immediately.
+  pub fn immediate(retries: usize) -> RetryPolicy {
+    RetryPolicy { retries: retries, backoff: Backoff::None }
+  }
+  
+  /// Creates a policy that waits a fixed duration between retries.
+  pub fn fixed(retries: usize, delay: Duration) -> RetryPolicy {
+    RetryPolicy { retries: retries, backoff: Backoff::Fixed(delay) }
+  }
+  
+  /// Creates a policy that doubles the delay between each retry, 
+  /// starting at initial and capped at max.
+  pub fn exponential(retries: usize, initial: Duration, max: Duration) -> RetryPolicy {
+    RetryPolicy { retries: retries, backoff: Backoff::Exponential(initial, max) }
+  }
+  
+  /// Returns the delay to wait before the given retry. Retries are
+  /// numbered from 1.
+  pub fn delay(&self, retry: usize) -> Duration {
+    match self.backoff {
+      Backoff::None => Duration::from_millis(0),
+      Backoff::Fixed(delay) => delay,
+      Backoff::Exponential(initial, max) => {
+        let shift = if retry > 0 { retry - 1 } else { 0 };
+        if shift >= 32 { 
+          return max 
+        }
+        match initial.checked_mul(1 << shift) {
+          Some(delay) if delay < max => delay,
+          _ => max
+        }
+      }
+    }
+  }
+}
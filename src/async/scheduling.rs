@@ -27,62 +27,181 @@
 extern crate threadpool;
 
 use self::threadpool::ThreadPool;
+use std::any::Any;
+use std::cell::Cell;
+use std::cmp;
 use std::thread;
-use std::sync::mpsc::{
-  sync_channel,
-  Receiver,
-  RecvError
-};
+use std::collections::{HashMap, VecDeque, BinaryHeap};
+use std::sync::{Arc, Mutex, RwLock, Condvar};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{channel, sync_channel, Sender, SyncSender};
+use std::time::{Duration, Instant};
+use std::panic::{self, AssertUnwindSafe};
+pub use super::handle::TaskHandle;
+use super::observer::{self, TaskInfo};
+use super::context;
+use super::scope::{self, SchedulerScope};
+use super::stream::Stream;
+use super::threadpool::ThreadPool as BlockingPool;
 use super::task::{
   Task,
-  TaskSender
+  TaskSender,
+  Priority
 };
+use super::super::metrics;
+use super::super::error::Error;
 
+/// Common scheduler trait implemented by all schedulers.
+pub trait Scheduler {
+  
+  /// Schedules a task. Failures of the task, including panics, are 
+  /// resolved on the returned handle and never panic the thread the
+  /// task was run on.
+  fn run<T, E>(&self, task: Task<T, E>) -> TaskHandle<T, E> where T: Send + 'static, E: From<Error> + Send + 'static;
+  
+  /// Schedules a task to run once the given delay has elapsed. Delayed
+  /// tasks wait on a single timer thread shared by all schedulers, and
+  /// do not hold a thread of their own while waiting. Once due, tasks
+  /// are handed to the scheduler from a shared thread, so schedulers 
+  /// that run tasks in place do not hold up other timers.
+  ///
+  /// # Example
+  /// ```
+  /// use std::time::Duration;
+  /// use smoke::async::{Task, Scheduler, ThreadPoolScheduler};
+  ///
+  /// let scheduler = ThreadPoolScheduler::new(4);
+  /// let handle    = scheduler.run_after(Duration::from_millis(10), Task::from_value(10));
+  /// assert_eq!(handle.wait().unwrap(), 10);
+  /// ```
+  fn run_after<T, E>(&self, delay: Duration, task: Task<T, E>) -> TaskHandle<T, E> where 
+      Self: Clone + Send + 'static, 
+      T: Send + 'static, 
+      E: From<Error> + Send + 'static {
+    let (task, handle) = task.erase();
+    let scheduler      = self.clone();
+    timer().set_timeout(delay, move || {
+      spawn(move || { scheduler.run(task); });
+    });
+    handle
+  }
+  
+  /// Schedules a task created by the given factory to run once every 
+  /// period, starting one period from now, until cancelled with the 
+  /// returned handle. Runs are timed from the start of the schedule, 
+  /// so do not drift, and may overlap should a run take longer than 
+  /// the period. As with run_after, runs wait on the shared timer 
+  /// thread, and results of each run are discarded.
+  ///
+  /// # Example
+  /// ```
+  /// use std::time::Duration;
+  /// use smoke::async::{Task, Scheduler, ThreadPoolScheduler};
+  ///
+  /// let scheduler = ThreadPoolScheduler::new(4);
+  /// let recurring = scheduler.run_at_fixed_rate(Duration::from_secs(60), || {
+  ///   Task::new(|sender| {
+  ///     // maintenance ...
+  ///     sender.send(())
+  ///   })
+  /// });
+  /// recurring.cancel();
+  /// ```
+  fn run_at_fixed_rate<T, E, F>(&self, period: Duration, factory: F) -> RecurringHandle where 
+      Self: Clone + Send + 'static, 
+      T: Send + 'static, 
+      E: From<Error> + Send + 'static,
+      F: Fn() -> Task<T, E> + Send + 'static {
+    let handle = RecurringHandle { 
+      cancelled: Arc::new(AtomicBool::new(false)), 
+      runs:      Arc::new(AtomicUsize::new(0)) 
+    };
+    recur(self.clone(), factory, period, Instant::now() + period, handle.clone());
+    handle
+  }
+  
+  /// Runs closures that may borrow from the caller on this scheduler,
+  /// returning once all closures run on the scope have completed. 
+  ///
+  /// # Example
+  /// ```
+  /// use smoke::async::{Scheduler, ThreadPoolScheduler};
+  ///
+  /// let scheduler = ThreadPoolScheduler::new(4);
+  /// let numbers   = vec![1, 2, 3, 4, 5, 6, 7, 8];
+  /// let sum = scheduler.scope(|scope| {
+  ///   let handles = numbers.chunks(2).map(|chunk| {
+  ///     scope.run(move || chunk.iter().sum::<i32>())
+  ///   }).collect::<Vec<_>>();
+  ///   handles.into_iter().map(|handle| handle.wait().unwrap()).sum::<i32>()
+  /// });
+  /// assert_eq!(sum, 36);
+  /// ```
+  fn scope<'env, F, R>(&'env self, func: F) -> R where 
+      Self: Sized,
+      F: FnOnce(&SchedulerScope<'env, Self>) -> R {
+    scope::scope(self, func)
+  }
+}
 
-/// A waitable handle for scheduled issused by schedulers running tasks.
-///
-/// # Examples
-/// ```
-/// use smoke::async::Task;
-/// use smoke::async::{Scheduler, ThreadScheduler};
-/// fn hello() -> Task<&'static str> {
-///   Task::delay(1).map(|_| "hello")
-/// }
-///
-/// fn main() {
-///   let scheduler = ThreadScheduler;
-///   let handle    = scheduler.run(hello());
-///   // sometime later...
-///   println!("{:?}", handle.wait());
-/// }
-/// ```
-pub struct TaskHandle<T> {
-  receiver: Receiver<T>
+/// A handle to a recurring task scheduled with Scheduler::run_at_fixed_rate().
+/// Dropping the handle does not cancel the recurring task.
+#[derive(Clone)]
+pub struct RecurringHandle {
+  cancelled: Arc<AtomicBool>,
+  runs:      Arc<AtomicUsize>
 }
-impl<T> TaskHandle<T> where T: Send + 'static {
+impl RecurringHandle {
+  
+  /// Cancels the recurring task. Runs already started are not interrupted.
+  pub fn cancel(&self) {
+    self.cancelled.store(true, Ordering::SeqCst);
+  }
   
-  /// Creates a new wait handle. Wait handles are created
-  /// by schedulers when running tasks. When the task is
-  /// being run, a sync_channel is created, the sending
-  /// end is passed to the task, the receiving end is passed
-  /// here.
-  pub fn new(receiver: Receiver<T>) -> TaskHandle<T> {
-    TaskHandle { receiver: receiver }
+  /// Returns true if the recurring task has been cancelled.
+  pub fn is_cancelled(&self) -> bool {
+    self.cancelled.load(Ordering::SeqCst)
   }
   
-  /// Waits on the handles receiver. This method
-  /// will block the current thread while waiting
-  /// for a result.
-  pub fn wait(self) -> Result<T, RecvError> {
-    self.receiver.recv()
+  /// Returns the number of runs started.
+  pub fn runs(&self) -> usize {
+    self.runs.load(Ordering::SeqCst)
   }
 }
 
-/// Common scheduler trait implemented by all schedulers.
-pub trait Scheduler {
-  
-  /// Schedules a task.
-  fn run<T>(&self, task: Task<T>) -> TaskHandle<T> where T: Send + 'static;
+/// Sets a timer to run the next task of a recurring schedule when due,
+/// setting the timer for the run after.
+fn recur<S, T, E, F>(scheduler: S, factory: F, period: Duration, due: Instant, handle: RecurringHandle) where
+    S: Scheduler + Clone + Send + 'static,
+    T: Send + 'static, 
+    E: From<Error> + Send + 'static,
+    F: Fn() -> Task<T, E> + Send + 'static {
+  let delay = due.saturating_duration_since(Instant::now());
+  timer().set_timeout(delay, move || {
+    if handle.is_cancelled() { 
+      return; 
+    }
+    handle.runs.fetch_add(1, Ordering::SeqCst);
+    let (task, runner) = (factory(), scheduler.clone());
+    spawn(move || { runner.run(task); });
+    recur(scheduler, factory, period, due + period, handle);
+  });
+}
+
+impl<'a, S> Scheduler for &'a S where S: Scheduler {
+  /// Schedules a task on the referenced scheduler, allowing
+  /// schedulers to be shared across calls.
+  fn run<T, E>(&self, task: Task<T, E>) -> TaskHandle<T, E> where T: Send + 'static, E: From<Error> + Send + 'static {
+    (**self).run(task)
+  }
+}
+
+impl<S> Scheduler for Arc<S> where S: Scheduler {
+  /// Schedules a task on the shared scheduler, allowing schedulers
+  /// to be moved into tasks and shared across threads.
+  fn run<T, E>(&self, task: Task<T, E>) -> TaskHandle<T, E> where T: Send + 'static, E: From<Error> + Send + 'static {
+    (**self).run(task)
+  }
 }
 
 /// A synchronous scheduler. Tasks scheduled on this scheduler
@@ -91,11 +210,12 @@ pub trait Scheduler {
 ///
 /// # Examples
 /// ```
+/// use std::time::Duration;
 /// use smoke::async::Task;
 /// use smoke::async::SyncScheduler;
 ///
 /// fn hello() -> Task<&'static str> {
-///   Task::delay(1).map(|_| "hello")
+///   Task::delay_for(Duration::from_millis(1)).map(|_| "hello")
 /// }
 ///
 /// fn main() {
@@ -105,15 +225,15 @@ pub trait Scheduler {
 ///   println!("{:?}", handle.wait());
 /// }
 /// ```
+#[derive(Clone)]
 pub struct SyncScheduler;
 impl Scheduler for SyncScheduler {
-  fn run<T>(&self, task: Task<T>) -> TaskHandle<T> where T: Send + 'static {
+  fn run<T, E>(&self, task: Task<T, E>) -> TaskHandle<T, E> where T: Send + 'static, E: From<Error> + Send + 'static {
     let (sender, receiver) = sync_channel(1);
     let handle = TaskHandle::new(receiver);
-    match task.func.call(TaskSender::new(sender)) {
-      Err(error) => panic!(format!("Scheduler: Error processing task: {}", error)),
-      Ok (_)     => { /* ... */ }
-    };
+    let info   = TaskInfo::new("SyncScheduler", &task);
+    scheduled(&info);
+    execute(&info, task, sender);
     handle
   }
 }
@@ -125,11 +245,12 @@ impl Scheduler for SyncScheduler {
 ///
 /// # Examples
 /// ```
+/// use std::time::Duration;
 /// use smoke::async::Task;
 /// use smoke::async::ThreadScheduler;
 ///
 /// fn hello() -> Task<&'static str> {
-///   Task::delay(1).map(|_| "hello")
+///   Task::delay_for(Duration::from_millis(1)).map(|_| "hello")
 /// }
 ///
 /// fn main() {
@@ -139,6 +260,7 @@ impl Scheduler for SyncScheduler {
 ///   println!("{:?}", handle.wait());
 /// }
 /// ```
+#[derive(Clone)]
 pub struct ThreadScheduler;
 impl ThreadScheduler {
   /// Creates a new thread scheduler.
@@ -148,28 +270,29 @@ impl ThreadScheduler {
 }
 impl Scheduler for ThreadScheduler {
   /// Schedules a task.
-  fn run<T>(&self, task: Task<T>) -> TaskHandle<T> where T: Send + 'static {
+  fn run<T, E>(&self, task: Task<T, E>) -> TaskHandle<T, E> where T: Send + 'static, E: From<Error> + Send + 'static {
     let (sender, receiver) = sync_channel(1);
     let handle = TaskHandle::new(receiver);
-    thread::spawn(move || {
-      match task.func.call(TaskSender::new(sender)) {
-        Err(error) => panic!(format!("Scheduler: Error processing task: {}", error)),
-        Ok (_)     => { /* ... */ }
-      }
-    }); handle
+    let info   = TaskInfo::new("ThreadScheduler", &task);
+    scheduled(&info);
+    thread::spawn(move || execute(&info, task, sender));
+    handle
   }
 }
 
 /// A asynchronous scheduler. Tasks scheduled here are executed
-/// within a threadpool of the given size.
+/// within a threadpool of the given size. When all threads are
+/// busy, waiting tasks are run in order of their priority. Tasks 
+/// marked as blocking are run on a separate elastic threadpool.
 ///
 /// # Examples
 /// ```
+/// use std::time::Duration;
 /// use smoke::async::Task;
 /// use smoke::async::ThreadPoolScheduler;
 ///
 /// fn hello() -> Task<&'static str> {
-///   Task::delay(1).map(|_| "hello")
+///   Task::delay_for(Duration::from_millis(1)).map(|_| "hello")
 /// }
 ///
 /// fn main() {
@@ -179,28 +302,1431 @@ impl Scheduler for ThreadScheduler {
 ///   println!("{:?}", handle.wait());
 /// }
 /// ```
+#[derive(Clone)]
 pub struct ThreadPoolScheduler {
-  threadpool: ThreadPool
+  threadpool: ThreadPool,
+  queue:      Arc<(Mutex<PriorityQueue>, Condvar)>,
+  bound:      Option<(usize, QueuePolicy)>,
+  hooks:      Arc<RwLock<Hooks>>,
+  blocking:   BlockingPool
+}
+
+/// What a threadpool scheduler with a bounded queue does with tasks 
+/// scheduled while its queue is full.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QueuePolicy {
+  /// Blocks the thread scheduling the task until the queue has room.
+  /// Tasks scheduled from a thread of the scheduler itself run on that
+  /// thread instead, as threads blocked on their own queue could leave
+  /// no thread to make room.
+  Block,
+  /// Resolves the task with a Rejected error without running it.
+  Reject,
+  /// Resolves the longest waiting task with a Rejected error, and
+  /// queues the task in its place.
+  DropOldest
+}
+
+/// The most threads started for blocking tasks per scheduler.
+const BLOCKING_THREADS: usize = 512;
+
+thread_local! {
+  /// The queue of the threadpool scheduler this thread is a worker of.
+  static WORKER: Cell<usize> = Cell::new(0);
+}
+
+/// Creates the pool running the blocking tasks of a scheduler. Bursts
+/// of blocking tasks may start many threads, so threads idle for the
+/// shared keep alive exit.
+fn blocking_pool(bound: usize, name: Option<String>, stack_size: Option<usize>) -> BlockingPool {
+  let pool = BlockingPool::with_threads(bound, name, stack_size);
+  pool.keep_alive(Duration::from_millis(SHARED_KEEP_ALIVE));
+  pool
 }
 impl ThreadPoolScheduler {
   
   /// Creates a new threadpool scheduler with the given number of threads.
   pub fn new(threads: usize) -> ThreadPoolScheduler {
-    let threadpool = ThreadPool::new(threads);
+    ThreadPoolScheduler::builder().threads(threads).build()
+  }
+  
+  /// Sets the number of threads running tasks. Growing starts threads
+  /// immediately, running tasks waiting at the time. Shrinking ends 
+  /// excess threads once their current task completes. A threadpool 
+  /// keeps at least one thread.
+  ///
+  /// # Example
+  /// ```
+  /// use smoke::async::ThreadPoolScheduler;
+  ///
+  /// let scheduler = ThreadPoolScheduler::new(2);
+  /// scheduler.set_threads(8);
+  /// assert_eq!(scheduler.threads(), 8);
+  /// ```
+  pub fn set_threads(&self, threads: usize) {
+    let threads  = cmp::max(threads, 1);
+    let previous = {
+      let (ref lock, _) = *self.queue;
+      let mut queue  = lock.lock().unwrap();
+      let previous   = queue.limit;
+      queue.limit    = threads;
+      previous
+    };
+    // clones of a threadpool share its threads.
+    self.threadpool.clone().set_num_threads(threads);
+    // tasks left waiting while at the previous limit are dequeued
+    // on the threads added.
+    for _ in previous..threads {
+      self.dequeue();
+    }
+  }
+  
+  /// Returns the number of threads running tasks.
+  pub fn threads(&self) -> usize {
+    let (ref lock, _) = *self.queue;
+    lock.lock().unwrap().limit
+  }
+  
+  /// Returns a builder to configure the threads of a threadpool scheduler.
+  ///
+  /// # Example
+  /// ```
+  /// use smoke::async::{Task, ThreadPoolScheduler};
+  ///
+  /// let scheduler = ThreadPoolScheduler::builder()
+  ///                   .threads(4)
+  ///                   .name_prefix("smoke-io")
+  ///                   .stack_size(8 * 1024 * 1024)
+  ///                   .build();
+  /// let handle = Task::from_value(10).schedule(scheduler);
+  /// assert_eq!(handle.wait().unwrap(), 10);
+  /// ```
+  pub fn builder() -> ThreadPoolBuilder {
+    ThreadPoolBuilder {
+      threads:     thread::available_parallelism().map(|n| n.get()).unwrap_or(1),
+      name_prefix: None,
+      stack_size:  None,
+      queue_bound: None
+    }
+  }
+}
+
+/// Configures and creates a threadpool scheduler. Created with 
+/// ThreadPoolScheduler::builder().
+#[derive(Debug, Clone)]
+pub struct ThreadPoolBuilder {
+  threads:     usize,
+  name_prefix: Option<String>,
+  stack_size:  Option<usize>,
+  queue_bound: Option<(usize, QueuePolicy)>
+}
+impl ThreadPoolBuilder {
+  
+  /// Sets the number of threads running tasks. Defaults to the 
+  /// available parallelism of the machine.
+  pub fn threads(mut self, threads: usize) -> ThreadPoolBuilder {
+    self.threads = threads;
+    self
+  }
+  
+  /// Sets the prefix of the names of threads started by the scheduler.
+  /// Threads running tasks are named prefix-worker, and threads running
+  /// blocking tasks are named prefix-blocking.
+  pub fn name_prefix(mut self, prefix: &str) -> ThreadPoolBuilder {
+    self.name_prefix = Some(prefix.to_string());
+    self
+  }
+  
+  /// Sets the stack size in bytes of threads started by the scheduler.
+  pub fn stack_size(mut self, size: usize) -> ThreadPoolBuilder {
+    self.stack_size = Some(size);
+    self
+  }
+  
+  /// Bounds the number of tasks waiting for a thread, applying the 
+  /// policy to tasks scheduled while the queue is full. A bounded 
+  /// queue holds at least one task. Tasks marked as blocking are not
+  /// counted. Unbounded by default.
+  ///
+  /// # Example
+  /// ```
+  /// use smoke::async::{Task, ThreadPoolScheduler, QueuePolicy};
+  ///
+  /// let scheduler = ThreadPoolScheduler::builder()
+  ///                   .threads(1)
+  ///                   .queue_bound(1024, QueuePolicy::Reject)
+  ///                   .build();
+  /// let handle = Task::from_value(10).schedule(scheduler);
+  /// assert_eq!(handle.wait().unwrap(), 10);
+  /// ```
+  pub fn queue_bound(mut self, bound: usize, policy: QueuePolicy) -> ThreadPoolBuilder {
+    self.queue_bound = Some((cmp::max(bound, 1), policy));
+    self
+  }
+  
+  /// Creates the scheduler.
+  pub fn build(self) -> ThreadPoolScheduler {
+    self.build_with(PriorityQueue::new())
+  }
+  
+  /// Creates the scheduler, queueing tasks on the given queue.
+  fn build_with(self, mut queue: PriorityQueue) -> ThreadPoolScheduler {
+    queue.limit = self.threads;
+    let mut builder = threadpool::Builder::new().num_threads(self.threads);
+    if let Some(ref prefix) = self.name_prefix {
+      builder = builder.thread_name(format!("{}-worker", prefix));
+    }
+    if let Some(stack_size) = self.stack_size {
+      builder = builder.thread_stack_size(stack_size);
+    }
+    let blocking = self.name_prefix.map(|prefix| format!("{}-blocking", prefix));
     ThreadPoolScheduler {
-      threadpool: threadpool 
+      threadpool: builder.build(),
+      queue:      Arc::new((Mutex::new(queue), Condvar::new())),
+      bound:      self.queue_bound,
+      hooks:      Arc::new(RwLock::new(Hooks::default())),
+      blocking:   blocking_pool(BLOCKING_THREADS, blocking, self.stack_size)
     }
   }
 }
 impl Scheduler for ThreadPoolScheduler {
-  fn run<T>(&self, task: Task<T>) -> TaskHandle<T> where T: Send + 'static {
+  fn run<T, E>(&self, task: Task<T, E>) -> TaskHandle<T, E> where T: Send + 'static, E: From<Error> + Send + 'static {
+    let (sender, receiver) = sync_channel(1);
+    let handle   = TaskHandle::new(receiver);
+    let priority = task.priority();
+    let info     = TaskInfo::new("ThreadPoolScheduler", &task);
+    let hooks    = self.hooks.clone();
+    if task.is_blocking() {
+      metrics::TASKS_QUEUED.increment();
+      scheduled(&info);
+      self.blocking.spawn(move || {
+        metrics::TASKS_QUEUED.decrement();
+        execute_with(&info, task, sender, hooks.read().unwrap().panicked.clone());
+      });
+      return handle;
+    }
+    metrics::TASKS_QUEUED.increment();
+    scheduled(&info);
+    let pending = Box::new(Waiting { info: info, task: task, sender: sender, hooks: hooks });
+    let saturated = {
+      let (ref lock, ref condvar) = *self.queue;
+      let mut queue = lock.lock().unwrap();
+      loop {
+        if queue.shutdown {
+          log_debug!("ThreadPoolScheduler: shut down, task rejected");
+          pending.reject();
+          return handle;
+        }
+        match self.bound {
+          Some((bound, policy)) if queue.len() >= bound => match policy {
+            QueuePolicy::Block if self.is_worker() => {
+              log_debug!("ThreadPoolScheduler: queue full, task run by the scheduling worker");
+              drop(queue);
+              pending.run();
+              return handle;
+            },
+            QueuePolicy::Block      => queue = condvar.wait(queue).unwrap(),
+            QueuePolicy::Reject     => {
+              log_debug!("ThreadPoolScheduler: queue full, task rejected");
+              pending.reject();
+              return handle;
+            },
+            QueuePolicy::DropOldest => {
+              log_debug!("ThreadPoolScheduler: queue full, oldest task rejected");
+              if let Some(oldest) = queue.pop_oldest() {
+                oldest.reject();
+              }
+            }
+          },
+          _ => break
+        }
+      }
+      queue.push(priority, pending);
+      if queue.running >= queue.limit { Some(queue.len()) } else { None }
+    };
+    if let Some(queued) = saturated {
+      log_debug!("ThreadPoolScheduler: saturated, {} tasks queued", queued);
+      let hook = self.hooks.read().unwrap().saturated.clone();
+      if let Some(hook) = hook {
+        hook(queued);
+      }
+    }
+    self.dequeue(); handle
+  }
+}
+impl ThreadPoolScheduler {
+  
+  /// Returns true if the current thread is a worker of this scheduler.
+  fn is_worker(&self) -> bool {
+    WORKER.with(|worker| worker.get() == &*self.queue as *const _ as usize)
+  }
+  
+  /// Returns true if no tasks are waiting or running on this scheduler.
+  /// Checked under the locks tasks are handed to threads under, so a 
+  /// task being handed off is never missed.
+  fn is_idle(&self) -> bool {
+    let queue = (self.queue.0).lock().unwrap();
+    queue.running == 0 && queue.len() == 0 && self.blocking.active() == 0 && self.blocking.queued() == 0
+  }
+  
+  /// Submits a job to the threadpool that runs the highest priority 
+  /// task waiting at the time a thread becomes free, and then each
+  /// task waiting after it, while within the thread limit.
+  fn dequeue(&self) {
+    let queue = self.queue.clone();
+    let hooks = self.hooks.clone();
+    self.threadpool.execute(move || {
+      // the threads of the threadpool only run jobs of this scheduler.
+      WORKER.with(|worker| worker.set(&*queue as *const _ as usize));
+      let (ref lock, ref condvar) = *queue;
+      loop {
+        let job = lock.lock().unwrap().take();
+        condvar.notify_all();
+        match job {
+          None      => break,
+          Some(job) => {
+            job.run();
+            let idle = {
+              let mut queue = lock.lock().unwrap();
+              queue.running -= 1;
+              queue.running == 0 && queue.len() == 0
+            };
+            // wakes schedulers waiting on a graceful shutdown.
+            condvar.notify_all();
+            if idle {
+              let hook = hooks.read().unwrap().idle.clone();
+              if let Some(hook) = hook {
+                hook();
+              }
+            }
+          }
+        }
+      }
+    });
+  }
+  
+  /// Shuts down this scheduler, returning the tasks waiting on a thread
+  /// without running them. Tasks already running are left to complete,
+  /// and tasks marked as blocking are run as usual. The tasks returned 
+  /// resolve the handles of the original tasks when run, and may be run
+  /// elsewhere. Tasks scheduled once shut down are resolved with a 
+  /// Rejected error. Dropping a scheduler without shutting it down runs
+  /// each waiting task to completion.
+  ///
+  /// # Example
+  /// ```
+  /// use smoke::async::{Task, ThreadPoolScheduler, SyncScheduler};
+  ///
+  /// let scheduler = ThreadPoolScheduler::new(1);
+  /// let handle    = Task::from_value(10).schedule(&scheduler);
+  /// // the task either ran, or is returned to run elsewhere.
+  /// for task in scheduler.shutdown_now() {
+  ///   task.schedule(SyncScheduler).wait().unwrap();
+  /// }
+  /// assert_eq!(handle.wait().unwrap(), 10);
+  /// ```
+  pub fn shutdown_now(&self) -> Vec<Task<()>> {
+    let (ref lock, ref condvar) = *self.queue;
+    let unstarted = {
+      let mut queue  = lock.lock().unwrap();
+      queue.shutdown = true;
+      let mut unstarted = Vec::new();
+      while let Some(pending) = queue.pop() {
+        unstarted.push(pending.into_task());
+      }
+      unstarted
+    };
+    // wakes schedulers blocked on a full queue.
+    condvar.notify_all();
+    unstarted
+  }
+  
+  /// Shuts down this scheduler once the tasks waiting and running have
+  /// completed, waiting at most the given timeout for them to do so. 
+  /// Returns the tasks still waiting on a thread at the timeout, as with
+  /// shutdown_now, or an empty vector if all tasks completed. Tasks 
+  /// scheduled once shutting down are resolved with a Rejected error.
+  ///
+  /// # Example
+  /// ```
+  /// use std::time::Duration;
+  /// use smoke::async::{Task, ThreadPoolScheduler};
+  ///
+  /// let scheduler = ThreadPoolScheduler::new(1);
+  /// let handle    = Task::from_value(10).schedule(&scheduler);
+  /// assert_eq!(scheduler.shutdown_graceful(Duration::from_secs(1)).len(), 0);
+  /// assert_eq!(handle.wait().unwrap(), 10);
+  /// ```
+  pub fn shutdown_graceful(&self, timeout: Duration) -> Vec<Task<()>> {
+    let deadline = Instant::now() + timeout;
+    {
+      let (ref lock, ref condvar) = *self.queue;
+      let mut queue  = lock.lock().unwrap();
+      queue.shutdown = true;
+      condvar.notify_all();
+      while queue.running > 0 || queue.len() > 0 {
+        let now = Instant::now();
+        if now >= deadline {
+          log_debug!("ThreadPoolScheduler: shutdown timed out with {} tasks waiting", queue.len());
+          break;
+        }
+        queue = condvar.wait_timeout(queue, deadline - now).unwrap().0;
+      }
+    }
+    self.shutdown_now()
+  }
+  
+  /// Sets a callback run each time this scheduler becomes idle, with 
+  /// no tasks running or waiting. Blocking tasks are not counted. The
+  /// callback is run on the thread of the last task to complete.
+  ///
+  /// # Example
+  /// ```
+  /// use smoke::async::ThreadPoolScheduler;
+  ///
+  /// let scheduler = ThreadPoolScheduler::new(4);
+  /// scheduler.on_idle(|| println!("idle"));
+  /// ```
+  pub fn on_idle<F>(&self, func: F) where F: Fn() + Send + Sync + 'static {
+    self.hooks.write().unwrap().idle = Some(Arc::new(func));
+  }
+  
+  /// Sets a callback run each time a task is scheduled while all threads
+  /// of this scheduler are busy, with the number of tasks waiting. Allows
+  /// applications to grow the threadpool or shed load. The callback is 
+  /// run on the thread scheduling the task.
+  ///
+  /// # Example
+  /// ```
+  /// use smoke::async::ThreadPoolScheduler;
+  ///
+  /// let scheduler = ThreadPoolScheduler::new(4);
+  /// scheduler.on_saturated(|queued| println!("{} tasks waiting", queued));
+  /// ```
+  pub fn on_saturated<F>(&self, func: F) where F: Fn(usize) + Send + Sync + 'static {
+    self.hooks.write().unwrap().saturated = Some(Arc::new(func));
+  }
+  
+  /// Sets a callback run each time a task run by this scheduler panics,
+  /// with the task's name, if named, and the panic payload. The task 
+  /// resolves with Panicked as usual, and the thread is kept. The callback
+  /// is run on the thread of the task.
+  ///
+  /// # Example
+  /// ```
+  /// use smoke::async::ThreadPoolScheduler;
+  ///
+  /// let scheduler = ThreadPoolScheduler::new(4);
+  /// scheduler.on_panic(|name, _payload| {
+  ///   println!("task {} panicked", name.unwrap_or("<unnamed>"));
+  /// });
+  /// ```
+  pub fn on_panic<F>(&self, func: F) where F: Fn(Option<&str>, &(Any + Send)) + Send + Sync + 'static {
+    self.hooks.write().unwrap().panicked = Some(Arc::new(func));
+  }
+}
+
+/// A callback run with the name and panic payload of a task that panicked.
+type PanicHook = Arc<Fn(Option<&str>, &(Any + Send)) + Send + Sync>;
+
+/// The callbacks set on a threadpool scheduler.
+#[derive(Default)]
+struct Hooks {
+  idle:      Option<Arc<Fn() + Send + Sync>>,
+  saturated: Option<Arc<Fn(usize) + Send + Sync>>,
+  panicked:  Option<PanicHook>
+}
+
+/// A threadpool scheduler with a choice of dequeue order for tasks
+/// waiting on a thread. Strict order always runs waiting tasks of a 
+/// higher priority first, as with the ThreadPoolScheduler. Weighted
+/// order runs up to the given number of waiting tasks of each priority
+/// in turn, so lower priority tasks are not starved by a steady supply 
+/// of higher priority tasks.
+///
+/// # Examples
+/// ```
+/// use smoke::async::{Task, Priority, PriorityScheduler};
+///
+/// // run 8 high, 4 normal then 1 low priority task, in turn.
+/// let scheduler = PriorityScheduler::weighted(4, 8, 4, 1);
+/// let handle    = Task::from_value(10).with_priority(Priority::Low).schedule(scheduler);
+/// assert_eq!(handle.wait().unwrap(), 10);
+/// ```
+#[derive(Clone)]
+pub struct PriorityScheduler {
+  scheduler: ThreadPoolScheduler
+}
+impl PriorityScheduler {
+  
+  /// Creates a new scheduler with the given number of threads that runs
+  /// waiting tasks strictly in order of priority.
+  pub fn strict(threads: usize) -> PriorityScheduler {
+    PriorityScheduler { 
+      scheduler: ThreadPoolScheduler::builder().threads(threads).build() 
+    }
+  }
+  
+  /// Creates a new scheduler with the given number of threads that runs
+  /// waiting tasks by the given weights of each priority. Weights of zero
+  /// are treated as one.
+  pub fn weighted(threads: usize, high: usize, normal: usize, low: usize) -> PriorityScheduler {
+    PriorityScheduler { 
+      scheduler: ThreadPoolScheduler::builder().threads(threads)
+                   .build_with(PriorityQueue::weighted(high, normal, low))
+    }
+  }
+}
+impl Scheduler for PriorityScheduler {
+  fn run<T, E>(&self, task: Task<T, E>) -> TaskHandle<T, E> where T: Send + 'static, E: From<Error> + Send + 'static {
+    self.scheduler.run(task)
+  }
+}
+
+/// A threadpool scheduler that shares its threads fairly among groups
+/// of tasks, such as the tasks of each tenant of a service. Waiting tasks
+/// are run from each group in turn, in submission order within a group, 
+/// so a group submitting many tasks cannot hold back the tasks of other 
+/// groups. Tasks are submitted to a group through the scheduler returned
+/// from group(). Tasks run on the FairScheduler itself are submitted to 
+/// the "default" group. Tasks marked as blocking are run on a separate
+/// elastic threadpool.
+///
+/// # Examples
+/// ```
+/// use smoke::async::{Task, FairScheduler};
+///
+/// let scheduler = FairScheduler::new(4);
+/// let tenant_a  = scheduler.group("tenant-a");
+/// let tenant_b  = scheduler.group("tenant-b");
+/// let a = (0..100).map(|n| Task::from_value(n).schedule(&tenant_a)).collect::<Vec<_>>();
+/// let b = Task::from_value(10).schedule(&tenant_b);
+/// // b runs before most of the tasks of tenant-a.
+/// assert_eq!(b.wait().unwrap(), 10);
+/// for handle in a {
+///   handle.wait().unwrap();
+/// }
+/// ```
+#[derive(Clone)]
+pub struct FairScheduler {
+  threadpool: ThreadPool,
+  queue:      Arc<Mutex<FairQueue>>,
+  blocking:   BlockingPool
+}
+impl FairScheduler {
+  
+  /// Creates a new fair scheduler with the given number of threads.
+  pub fn new(threads: usize) -> FairScheduler {
+    FairScheduler {
+      threadpool: ThreadPool::new(threads),
+      queue:      Arc::new(Mutex::new(FairQueue::new())),
+      blocking:   blocking_pool(BLOCKING_THREADS, None, None)
+    }
+  }
+  
+  /// Returns a scheduler submitting tasks to the given group of this 
+  /// scheduler. Groups are created as tasks are submitted to them.
+  pub fn group<N: Into<String>>(&self, name: N) -> FairGroup {
+    FairGroup { scheduler: self.clone(), name: name.into() }
+  }
+  
+  /// Submits the task to the given group.
+  fn submit<T, E>(&self, group: &str, task: Task<T, E>) -> TaskHandle<T, E> where T: Send + 'static, E: From<Error> + Send + 'static {
     let (sender, receiver) = sync_channel(1);
     let handle = TaskHandle::new(receiver);
+    let info   = TaskInfo::new("FairScheduler", &task);
+    metrics::TASKS_QUEUED.increment();
+    scheduled(&info);
+    if task.is_blocking() {
+      self.blocking.spawn(move || {
+        metrics::TASKS_QUEUED.decrement();
+        execute(&info, task, sender);
+      });
+      return handle;
+    }
+    self.queue.lock().unwrap().push(group, Box::new(move || {
+      metrics::TASKS_QUEUED.decrement();
+      execute(&info, task, sender);
+    }));
+    // as with the ThreadPoolScheduler, each job submitted to the 
+    // threadpool dequeues the next task waiting at the time.
+    let queue = self.queue.clone();
     self.threadpool.execute(move || {
-      match task.func.call(TaskSender::new(sender)) {
-        Err(error) => panic!(format!("Scheduler: Error processing task: {}", error)),
-        Ok (_)     => { /* ... */ }
+      let job = queue.lock().unwrap().pop();
+      if let Some(job) = job {
+        job()
       }
     }); handle
   }
-}
\ No newline at end of file
+}
+impl Scheduler for FairScheduler {
+  fn run<T, E>(&self, task: Task<T, E>) -> TaskHandle<T, E> where T: Send + 'static, E: From<Error> + Send + 'static {
+    self.submit("default", task)
+  }
+}
+
+/// A group of tasks sharing the threads of a fair scheduler. Created 
+/// with FairScheduler::group().
+#[derive(Clone)]
+pub struct FairGroup {
+  scheduler: FairScheduler,
+  name:      String
+}
+impl FairGroup {
+  
+  /// Returns the name of this group.
+  pub fn name(&self) -> &str {
+    &self.name
+  }
+}
+impl Scheduler for FairGroup {
+  fn run<T, E>(&self, task: Task<T, E>) -> TaskHandle<T, E> where T: Send + 'static, E: From<Error> + Send + 'static {
+    self.scheduler.submit(&self.name, task)
+  }
+}
+
+/// A scheduler routing tasks between a fixed size pool for compute
+/// tasks and an elastic pool for tasks marked as blocking. Threads 
+/// for blocking tasks are started as needed up to the given bound, 
+/// so tasks waiting on io neither hold the compute threads, nor 
+/// oversubscribe the machine when compute bound.
+///
+/// # Examples
+/// ```
+/// use std::time::Duration;
+/// use smoke::async::{Task, DualPoolScheduler};
+///
+/// let scheduler = DualPoolScheduler::new(4, 64);
+/// let io        = Task::delay_for(Duration::from_millis(10)).blocking().schedule(&scheduler);
+/// let compute   = Task::from_value(10).schedule(&scheduler);
+/// assert_eq!(compute.wait().unwrap(), 10);
+/// io.wait().unwrap();
+/// ```
+#[derive(Clone)]
+pub struct DualPoolScheduler {
+  compute: ThreadPoolScheduler,
+  io:      BlockingPool
+}
+impl DualPoolScheduler {
+  
+  /// Creates a new scheduler with the given number of compute threads,
+  /// and at most the given number of threads for blocking tasks.
+  pub fn new(compute_threads: usize, io_threads: usize) -> DualPoolScheduler {
+    DualPoolScheduler {
+      compute: ThreadPoolScheduler::new(compute_threads),
+      io:      blocking_pool(io_threads, None, None)
+    }
+  }
+}
+impl Scheduler for DualPoolScheduler {
+  fn run<T, E>(&self, task: Task<T, E>) -> TaskHandle<T, E> where T: Send + 'static, E: From<Error> + Send + 'static {
+    if !task.is_blocking() {
+      return self.compute.run(task);
+    }
+    let (sender, receiver) = sync_channel(1);
+    let handle = TaskHandle::new(receiver);
+    let info   = TaskInfo::new("DualPoolScheduler", &task);
+    if self.io.active() >= self.io.bound() {
+      log_debug!("DualPoolScheduler: io saturated, {} tasks queued", self.io.queued() + 1);
+    }
+    metrics::TASKS_QUEUED.increment();
+    scheduled(&info);
+    self.io.spawn(move || {
+      metrics::TASKS_QUEUED.decrement();
+      execute(&info, task, sender);
+    }); handle
+  }
+}
+
+/// A single threaded scheduler. Tasks, timers and stream callbacks
+/// are run one at a time on a dedicated thread, in the order they 
+/// become ready, giving deterministic execution without locking 
+/// between them. As a cooperative scheduler, a task that blocks holds
+/// back all others, and tasks must not wait on tasks scheduled on the
+/// same event loop. The event loop runs until stopped, or until its 
+/// scheduler is dropped and no work remains.
+///
+/// # Examples
+/// ```
+/// use std::time::Duration;
+/// use smoke::async::{Task, EventLoopScheduler};
+///
+/// let event_loop = EventLoopScheduler::new();
+/// event_loop.set_timeout(Duration::from_millis(10), || println!("later"));
+/// let handle = Task::from_value(10).schedule(&event_loop);
+/// assert_eq!(handle.wait().unwrap(), 10);
+/// event_loop.stop();
+/// ```
+pub struct EventLoopScheduler {
+  inner: Arc<(Mutex<Events>, Condvar)>
+}
+impl EventLoopScheduler {
+  
+  /// Creates a new event loop, running on its own thread.
+  pub fn new() -> EventLoopScheduler {
+    EventLoopScheduler::named("smoke-event-loop")
+  }
+  
+  /// Creates a new event loop, running on a thread of the given name.
+  fn named(name: &str) -> EventLoopScheduler {
+    let inner = Arc::new((Mutex::new(Events {
+      queue:   VecDeque::new(),
+      timers:  BinaryHeap::new(),
+      next:    0,
+      handles: 1,
+      stopped: false
+    }), Condvar::new()));
+    let events = inner.clone();
+    thread::Builder::new().name(name.to_string())
+                          .spawn(move || run_events(&events))
+                          .expect("EventLoopScheduler: unable to start thread");
+    EventLoopScheduler { inner: inner }
+  }
+  
+  /// Runs the given closure on the event loop once the delay has elapsed.
+  /// Timers that are due run before queued tasks.
+  pub fn set_timeout<F>(&self, delay: Duration, func: F) where F: FnOnce() + Send + 'static {
+    let (ref lock, ref condvar) = *self.inner;
+    let mut events = lock.lock().unwrap();
+    if events.stopped { return; }
+    let sequence = events.next;
+    events.next += 1;
+    events.timers.push(Timer { due: Instant::now() + delay, sequence: sequence, job: Box::new(func) });
+    condvar.notify_all();
+  }
+  
+  /// Reads the given stream, calling the closure with each element on
+  /// the event loop. The next element is read once the closure returns,
+  /// holding back the stream. The returned handle resolves once the 
+  /// stream ends, or with an error if the stream fails or the event loop
+  /// is stopped.
+  ///
+  /// # Example
+  /// ```
+  /// use smoke::async::{Stream, EventLoopScheduler};
+  ///
+  /// let event_loop = EventLoopScheduler::new();
+  /// let handle     = event_loop.for_each(Stream::range(0, 4), |n| println!("{}", n));
+  /// handle.wait().unwrap();
+  /// ```
+  pub fn for_each<T, F>(&self, stream: Stream<T>, func: F) -> TaskHandle<()> where 
+      T: Send + 'static, 
+      F: FnMut(T) + Send + 'static {
+    let (sender, receiver) = sync_channel(1);
+    let event_loop = self.clone();
+    let func       = Arc::new(Mutex::new(func));
+    thread::spawn(move || {
+      let elements = stream.read();
+      for n in elements.iter() {
+        let (done, ack) = sync_channel(1);
+        let func = func.clone();
+        event_loop.post(Box::new(move || {
+          (*func.lock().unwrap())(n);
+          let _ = done.send(());
+        }));
+        // the job is dropped without running if the loop was stopped.
+        if ack.recv().is_err() {
+          let _ = sender.send(Err(Error::Cancelled));
+          return;
+        }
+      }
+      let _ = sender.send(match elements.error() {
+        Some(error) => Err(error),
+        None        => Ok(())
+      });
+    });
+    TaskHandle::new(receiver)
+  }
+  
+  /// Stops the event loop once the current task or callback returns. 
+  /// Waiting tasks and timers are dropped, resolving the handles of 
+  /// waiting tasks with an error.
+  pub fn stop(&self) {
+    let (ref lock, ref condvar) = *self.inner;
+    let mut events = lock.lock().unwrap();
+    events.stopped = true;
+    events.queue.clear();
+    events.timers.clear();
+    condvar.notify_all();
+  }
+  
+  /// Returns true if the event loop has been stopped.
+  pub fn is_stopped(&self) -> bool {
+    let (ref lock, _) = *self.inner;
+    lock.lock().unwrap().stopped
+  }
+  
+  /// Queues the job to run on the event loop, dropping it if stopped.
+  fn post(&self, job: Job) {
+    let (ref lock, ref condvar) = *self.inner;
+    let mut events = lock.lock().unwrap();
+    if !events.stopped {
+      events.queue.push_back(job);
+      condvar.notify_all();
+    }
+  }
+}
+impl Clone for EventLoopScheduler {
+  fn clone(&self) -> EventLoopScheduler {
+    let (ref lock, _) = *self.inner;
+    lock.lock().unwrap().handles += 1;
+    EventLoopScheduler { inner: self.inner.clone() }
+  }
+}
+impl Drop for EventLoopScheduler {
+  /// Releases this handle to the event loop, which ends once 
+  /// no handles remain and no work is waiting.
+  fn drop(&mut self) {
+    let (ref lock, ref condvar) = *self.inner;
+    lock.lock().unwrap().handles -= 1;
+    condvar.notify_all();
+  }
+}
+impl Scheduler for EventLoopScheduler {
+  fn run<T, E>(&self, task: Task<T, E>) -> TaskHandle<T, E> where T: Send + 'static, E: From<Error> + Send + 'static {
+    let (sender, receiver) = sync_channel(1);
+    let handle = TaskHandle::new(receiver);
+    let info   = TaskInfo::new("EventLoopScheduler", &task);
+    metrics::TASKS_QUEUED.increment();
+    scheduled(&info);
+    self.post(Box::new(move || {
+      metrics::TASKS_QUEUED.decrement();
+      execute(&info, task, sender);
+    })); handle
+  }
+}
+
+/// A scheduler with a fixed set of workers, each running the tasks 
+/// sent to it in order on its own thread. With the affinity feature 
+/// on Linux, each worker is pinned to a core, keeping the data of tasks
+/// sent to a worker in that core's cache. Tasks are sent to a specific
+/// worker through the scheduler returned from worker(), and tasks run
+/// on the AffinityScheduler itself are sent to each worker in turn.
+///
+/// # Examples
+/// ```
+/// use smoke::async::{Task, AffinityScheduler};
+///
+/// let scheduler = AffinityScheduler::new(2);
+/// let worker    = scheduler.worker(1);
+/// let handle    = Task::from_value(10).schedule(&worker);
+/// assert_eq!(handle.wait().unwrap(), 10);
+/// ```
+#[derive(Clone)]
+pub struct AffinityScheduler {
+  workers: Arc<Vec<AffinityWorker>>,
+  next:    Arc<AtomicUsize>
+}
+impl AffinityScheduler {
+  
+  /// Creates a new scheduler with the given number of workers. Worker
+  /// n is pinned to core n, wrapping around the available cores. A 
+  /// count of zero is raised to one.
+  pub fn new(threads: usize) -> AffinityScheduler {
+    // a scheduler without workers would have nowhere to run a task.
+    let threads = cmp::max(threads, 1);
+    let cores   = thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+    let workers = (0..threads).map(|index| {
+      let (sender, receiver) = channel::<Job>();
+      let pinned = Arc::new(RwLock::new(None));
+      let result = pinned.clone();
+      thread::Builder::new().name(format!("smoke-affinity-{}", index)).spawn(move || {
+        *result.write().unwrap() = Some(pin(index % cores));
+        for job in receiver {
+          job()
+        }
+      }).expect("AffinityScheduler: unable to start thread");
+      AffinityWorker { index: index, sender: sender, pinned: pinned }
+    }).collect();
+    AffinityScheduler { workers: Arc::new(workers), next: Arc::new(AtomicUsize::new(0)) }
+  }
+  
+  /// Returns a scheduler sending tasks to the given worker. Worker 
+  /// indices wrap around the number of workers.
+  pub fn worker(&self, index: usize) -> AffinityWorker {
+    self.workers[index % self.workers.len()].clone()
+  }
+  
+  /// Returns the number of workers.
+  pub fn threads(&self) -> usize {
+    self.workers.len()
+  }
+}
+impl Scheduler for AffinityScheduler {
+  fn run<T, E>(&self, task: Task<T, E>) -> TaskHandle<T, E> where T: Send + 'static, E: From<Error> + Send + 'static {
+    let next = self.next.fetch_add(1, Ordering::SeqCst);
+    self.workers[next % self.workers.len()].run(task)
+  }
+}
+
+/// A worker of an affinity scheduler. Created with AffinityScheduler::worker().
+#[derive(Clone)]
+pub struct AffinityWorker {
+  index:  usize,
+  sender: Sender<Job>,
+  pinned: Arc<RwLock<Option<bool>>>
+}
+impl AffinityWorker {
+  
+  /// Returns the index of this worker.
+  pub fn index(&self) -> usize {
+    self.index
+  }
+  
+  /// Returns true if this worker's thread was pinned to a core, or 
+  /// None if the thread has not yet started.
+  pub fn is_pinned(&self) -> Option<bool> {
+    *self.pinned.read().unwrap()
+  }
+}
+impl Scheduler for AffinityWorker {
+  fn run<T, E>(&self, task: Task<T, E>) -> TaskHandle<T, E> where T: Send + 'static, E: From<Error> + Send + 'static {
+    let (sender, receiver) = sync_channel(1);
+    let handle = TaskHandle::new(receiver);
+    let info   = TaskInfo::new("AffinityScheduler", &task);
+    metrics::TASKS_QUEUED.increment();
+    scheduled(&info);
+    let _ = self.sender.send(Box::new(move || {
+      metrics::TASKS_QUEUED.decrement();
+      execute(&info, task, sender);
+    })); handle
+  }
+}
+
+/// Pins the current thread to the given core, returning true if pinned.
+#[cfg(all(feature = "affinity", target_os = "linux"))]
+fn pin(core: usize) -> bool {
+  use std::mem;
+  use libc;
+  unsafe {
+    let mut set: libc::cpu_set_t = mem::zeroed();
+    libc::CPU_SET(core, &mut set);
+    libc::sched_setaffinity(0, mem::size_of::<libc::cpu_set_t>(), &set) == 0
+  }
+}
+
+/// Pinning threads is unsupported without the affinity feature on Linux.
+#[cfg(not(all(feature = "affinity", target_os = "linux")))]
+fn pin(_core: usize) -> bool {
+  false
+}
+
+/// The event loop running the timers of delayed tasks.
+static TIMER: Mutex<Option<EventLoopScheduler>> = Mutex::new(None);
+
+/// Returns the event loop running the timers of delayed tasks, 
+/// starting it if not started.
+fn timer() -> EventLoopScheduler {
+  let mut timer = TIMER.lock().unwrap();
+  if timer.is_none() {
+    *timer = Some(EventLoopScheduler::named("smoke-timer"));
+  }
+  timer.as_ref().unwrap().clone()
+}
+
+/// A type erased scheduler for unit tasks.
+pub(crate) type Runner = Arc<Fn(Task<()>) -> TaskHandle<()> + Send + Sync>;
+
+/// The scheduler set with set_default_scheduler.
+static DEFAULT: RwLock<Option<Runner>> = RwLock::new(None);
+
+/// Sets the scheduler used by DefaultScheduler, and so by Task::spawn, 
+/// Task::async and Stream::spawn. Allows applications to configure the 
+/// scheduler once for libraries built on smoke. Until set, tasks are
+/// run on the shared threads also used by the task combinators.
+///
+/// # Example
+/// ```
+/// use smoke::async::{Task, ThreadPoolScheduler};
+/// use smoke::async::set_default_scheduler;
+///
+/// set_default_scheduler(ThreadPoolScheduler::new(8));
+/// assert_eq!(Task::from_value(10).spawn().wait().unwrap(), 10);
+/// ```
+pub fn set_default_scheduler<S>(scheduler: S) where S: Scheduler + Send + Sync + 'static {
+  let runner: Runner = Arc::new(move |task| scheduler.run(task));
+  *DEFAULT.write().unwrap() = Some(runner);
+}
+
+/// A scheduler running tasks on the scheduler set with 
+/// set_default_scheduler, or on the shared threads if unset.
+///
+/// # Examples
+/// ```
+/// use smoke::async::{Task, DefaultScheduler};
+///
+/// let handle = Task::from_value(10).schedule(DefaultScheduler);
+/// assert_eq!(handle.wait().unwrap(), 10);
+/// ```
+#[derive(Clone)]
+pub struct DefaultScheduler;
+impl Scheduler for DefaultScheduler {
+  fn run<T, E>(&self, task: Task<T, E>) -> TaskHandle<T, E> where T: Send + 'static, E: From<Error> + Send + 'static {
+    // cloned out of the lock, so tasks run synchronously 
+    // may set the default scheduler without deadlocking.
+    let runner = DEFAULT.read().unwrap().clone();
+    match runner {
+      None         => SharedScheduler.run(task),
+      Some(runner) => {
+        let (task, handle) = task.erase();
+        runner(task);
+        handle
+      }
+    }
+  }
+}
+
+/// How long an idle shared thread waits for a job before exiting.
+const SHARED_KEEP_ALIVE: u64 = 5000;
+
+/// The jobs and idle threads of the shared threads.
+struct Shared {
+  queue: VecDeque<Job>,
+  idle:  usize
+}
+
+/// The process wide threads used by the task combinators and stream 
+/// stages, created as needed and kept for reuse while idle.
+static SHARED: (Mutex<Shared>, Condvar) = (Mutex::new(Shared { queue: VecDeque::new(), idle: 0 }), Condvar::new());
+
+/// Spawns the given closure on the shared threads. The closure is run on
+/// an idle thread if any, otherwise a new thread is started, so closures
+/// waiting on each other never deadlock. Threads idle for longer than 
+/// the keep alive exit.
+pub(crate) fn spawn<F>(func: F) where F: FnOnce() + Send + 'static {
+  let (ref lock, ref condvar) = SHARED;
+  let mut shared = lock.lock().unwrap();
+  shared.queue.push_back(Box::new(func));
+  if shared.idle >= shared.queue.len() {
+    condvar.notify_one();
+    return;
+  }
+  let started = thread::Builder::new()
+    .name("smoke-shared".to_string())
+    .spawn(work_shared);
+  if let Err(_error) = started {
+    log_debug!("SharedScheduler: unable to start thread: {}", _error);
+    let job = shared.queue.pop_back().unwrap();
+    drop(shared);
+    job();
+  }
+}
+
+/// Runs jobs of the shared threads until idle for the keep alive.
+fn work_shared() {
+  let (ref lock, ref condvar) = SHARED;
+  let mut shared = lock.lock().unwrap();
+  loop {
+    match shared.queue.pop_front() {
+      Some(job) => {
+        drop(shared);
+        // panics are resolved by the tasks run, the thread is kept.
+        let _ = panic::catch_unwind(AssertUnwindSafe(move || job()));
+        shared = lock.lock().unwrap();
+      },
+      None => {
+        shared.idle += 1;
+        let (guard, waited) = condvar.wait_timeout(shared, Duration::from_millis(SHARED_KEEP_ALIVE)).unwrap();
+        shared = guard;
+        shared.idle -= 1;
+        if waited.timed_out() && shared.queue.is_empty() {
+          return;
+        }
+      }
+    }
+  }
+}
+
+/// A scheduler running tasks on the shared threads. Used in place of
+/// the ThreadScheduler by the task combinators, avoiding a new thread
+/// per task.
+#[derive(Clone)]
+pub(crate) struct SharedScheduler;
+impl Scheduler for SharedScheduler {
+  fn run<T, E>(&self, task: Task<T, E>) -> TaskHandle<T, E> where T: Send + 'static, E: From<Error> + Send + 'static {
+    let (sender, receiver) = sync_channel(1);
+    let handle = TaskHandle::new(receiver);
+    let info   = TaskInfo::new("SharedScheduler", &task);
+    scheduled(&info);
+    spawn(move || execute(&info, task, sender));
+    handle
+  }
+}
+
+/// The most idle threadpools kept for reuse.
+const MAX_POOLS: usize = 8;
+
+/// Idle threadpools kept for reuse by pooled schedulers, with the time
+/// each was returned.
+static POOLS: Mutex<Vec<(Instant, ThreadPoolScheduler)>> = Mutex::new(Vec::new());
+
+/// A threadpool scheduler checked out from a process wide cache of
+/// idle threadpools, used by Task::all and its variants to avoid 
+/// creating a new threadpool per call. Each pooled scheduler has 
+/// exclusive use of its threadpool, which is returned to the cache
+/// on drop if idle. Threadpools not checked out again within the 
+/// shared keep alive are dropped from the cache.
+pub(crate) struct PooledScheduler {
+  scheduler: Option<ThreadPoolScheduler>
+}
+impl PooledScheduler {
+  /// Checks out a scheduler with the given number of threads, 
+  /// creating one if none are idle.
+  pub(crate) fn acquire(threads: usize) -> PooledScheduler {
+    let mut pools = POOLS.lock().unwrap();
+    let scheduler = match pools.iter().position(|&(_, ref pool)| pool.threadpool.max_count() == threads) {
+      Some(index) => pools.swap_remove(index).1,
+      None        => ThreadPoolScheduler::new(threads)
+    };
+    PooledScheduler { scheduler: Some(scheduler) }
+  }
+}
+impl Scheduler for PooledScheduler {
+  fn run<T, E>(&self, task: Task<T, E>) -> TaskHandle<T, E> where T: Send + 'static, E: From<Error> + Send + 'static {
+    self.scheduler.as_ref().unwrap().run(task)
+  }
+}
+impl Drop for PooledScheduler {
+  /// Returns the threadpool to the cache. Threadpools with tasks 
+  /// still running, such as when Task::all fails early, are dropped.
+  fn drop(&mut self) {
+    if let Some(scheduler) = self.scheduler.take() {
+      if scheduler.is_idle() {
+        let mut pools = POOLS.lock().unwrap();
+        if pools.len() < MAX_POOLS {
+          pools.push((Instant::now(), scheduler));
+          drop(pools);
+          timer().set_timeout(Duration::from_millis(SHARED_KEEP_ALIVE), trim_pools);
+        }
+      }
+    }
+  }
+}
+
+/// Drops the threadpools cached for longer than the shared keep alive.
+fn trim_pools() {
+  let keep_alive = Duration::from_millis(SHARED_KEEP_ALIVE);
+  let expired    = {
+    let mut pools = POOLS.lock().unwrap();
+    let (kept, expired) = pools.drain(..).partition::<Vec<_>, _>(|&(returned, _)| returned.elapsed() < keep_alive);
+    *pools = kept;
+    expired
+  };
+  // threadpools are dropped outside the lock of the cache.
+  drop(expired);
+}
+
+/// Records a task as scheduled.
+fn scheduled(info: &TaskInfo) {
+  metrics::TASKS_SCHEDULED.increment();
+  observer::scheduled(info);
+  log_debug!("{}: task {} scheduled", info.scheduler, info);
+}
+
+/// Runs a task on the current thread, resolving its result on
+/// the given sender. Called by each scheduler once the task 
+/// has been given a thread to run on. Tasks scheduled with a 
+/// deadline that has since passed are failed without running.
+fn execute<T, E>(info: &TaskInfo, task: Task<T, E>, sender: SyncSender<Result<T, E>>) where 
+    T: Send + 'static,
+    E: From<Error> + Send + 'static {
+  execute_with(info, task, sender, None)
+}
+
+/// Runs a task as with execute, calling the given hook should the task panic.
+fn execute_with<T, E>(info: &TaskInfo, task: Task<T, E>, sender: SyncSender<Result<T, E>>, hook: Option<PanicHook>) where 
+    T: Send + 'static,
+    E: From<Error> + Send + 'static {
+  let _context = context::enter(info.deadline);
+  log_debug!("{}: task {} started", info.scheduler, info);
+  observer::started(info);
+  let started = Instant::now();
+  match context::check() {
+    Err(error) => { let _ = sender.send(Err(E::from(error))); },
+    Ok(_)      => call(info, task, sender, hook)
+  }
+  metrics::TASKS_EXECUTED.increment();
+  let finished = Instant::now();
+  observer::completed(info, finished.duration_since(started));
+  observer::traced(info, started, finished);
+  log_debug!("{}: task {} finished", info.scheduler, info);
+}
+
+/// Calls the task's closure. Panics raised by the task are caught 
+/// and resolved as errors, leaving the thread intact. Tasks returning
+/// an error, such as when their handle was dropped before the result
+/// was sent, resolve with a Send error if not already resolved.
+fn call<T, E>(info: &TaskInfo, task: Task<T, E>, sender: SyncSender<Result<T, E>>, hook: Option<PanicHook>) where 
+    T: Send + 'static,
+    E: From<Error> + Send + 'static {
+  let fallback = sender.clone();
+  let func     = task.into_func();
+  let sender   = TaskSender::named(sender, info.name.clone());
+  match panic::catch_unwind(AssertUnwindSafe(move || func.call(sender))) {
+    Err(payload)    => {
+      log_debug!("{}: task {} panicked", info.scheduler, info);
+      if let Some(hook) = hook {
+        let name = info.name.as_ref().map(|name| name.as_str());
+        if panic::catch_unwind(AssertUnwindSafe(|| hook(name, &*payload))).is_err() {
+          log_debug!("{}: panic hook panicked", info.scheduler);
+        }
+      }
+      // the task may have resolved before panicking, in which
+      // case the channel is full and the result is kept.
+      let _ = fallback.try_send(Err(E::from(Error::from_panic(payload))));
+    },
+    Ok(Err(_error)) => {
+      log_debug!("{}: task {} failed to send result: {}", info.scheduler, info, _error);
+      let _ = fallback.try_send(Err(E::from(Error::Send)));
+    },
+    Ok(Ok(_))       => { /* ... */ }
+  }
+}
+
+/// A type erased unit of work queued on a scheduler.
+type Job = Box<FnOnce() + Send + 'static>;
+
+/// A task waiting in the queue of a threadpool scheduler.
+trait Pending: Send {
+  /// Runs the task on the current thread.
+  fn run(self: Box<Self>);
+  /// Resolves the task with a Rejected error without running it.
+  fn reject(self: Box<Self>);
+  /// Converts the task into a unit task resolving the task's handle.
+  fn into_task(self: Box<Self>) -> Task<()>;
+}
+
+/// A task waiting on a threadpool scheduler, with the sender of its handle.
+struct Waiting<T, E> {
+  info:   TaskInfo,
+  task:   Task<T, E>,
+  sender: SyncSender<Result<T, E>>,
+  hooks:  Arc<RwLock<Hooks>>
+}
+impl<T, E> Pending for Waiting<T, E> where T: Send + 'static, E: From<Error> + Send + 'static {
+  fn run(self: Box<Self>) {
+    metrics::TASKS_QUEUED.decrement();
+    let waiting = *self;
+    let hook    = waiting.hooks.read().unwrap().panicked.clone();
+    execute_with(&waiting.info, waiting.task, waiting.sender, hook);
+  }
+  fn reject(self: Box<Self>) {
+    metrics::TASKS_QUEUED.decrement();
+    let _ = self.sender.try_send(Err(E::from(Error::Rejected)));
+  }
+  fn into_task(self: Box<Self>) -> Task<()> {
+    metrics::TASKS_QUEUED.decrement();
+    let waiting = *self;
+    waiting.task.erase_into(waiting.sender)
+  }
+}
+
+/// A task waiting in a priority queue.
+struct Queued {
+  pending:  Box<Pending>,
+  sequence: usize
+}
+
+/// A queue of jobs, dequeued High before Normal before Low, 
+/// and in submission order within a priority. Weighted queues
+/// dequeue up to the weight of each priority in turn, starting
+/// a new round once each waiting priority has had its share or 
+/// the queue is empty. Also counts the jobs running from the 
+/// queue, limiting them to the threads of the threadpool.
+struct PriorityQueue {
+  queues:   [VecDeque<Queued>; 3],
+  weights:  Option<[usize; 3]>,
+  credits:  [usize; 3],
+  next:     usize,
+  running:  usize,
+  limit:    usize,
+  shutdown: bool
+}
+impl PriorityQueue {
+  fn new() -> PriorityQueue {
+    PriorityQueue {
+      queues:   [VecDeque::new(), VecDeque::new(), VecDeque::new()],
+      weights:  None,
+      credits:  [0; 3],
+      next:     0,
+      running:  0,
+      limit:    usize::MAX,
+      shutdown: false
+    }
+  }
+  fn weighted(high: usize, normal: usize, low: usize) -> PriorityQueue {
+    // a priority with no weight would never be dequeued.
+    let weights = [cmp::max(high, 1), cmp::max(normal, 1), cmp::max(low, 1)];
+    PriorityQueue {
+      queues:   [VecDeque::new(), VecDeque::new(), VecDeque::new()],
+      weights:  Some(weights),
+      credits:  weights,
+      next:     0,
+      running:  0,
+      limit:    usize::MAX,
+      shutdown: false
+    }
+  }
+  fn len(&self) -> usize {
+    self.queues.iter().map(|queue| queue.len()).sum()
+  }
+  fn push(&mut self, priority: Priority, pending: Box<Pending>) {
+    let index = match priority {
+      Priority::High   => 0,
+      Priority::Normal => 1,
+      Priority::Low    => 2
+    };
+    let sequence = self.next;
+    self.next += 1;
+    self.queues[index].push_back(Queued { pending: pending, sequence: sequence });
+  }
+  fn pop(&mut self) -> Option<Box<Pending>> {
+    let index = match self.weights {
+      None          => self.queues.iter().position(|queue| !queue.is_empty()),
+      Some(weights) => {
+        if self.waiting().is_none() {
+          self.credits = weights;
+        }
+        self.waiting()
+      }
+    };
+    let job = index.and_then(|index| {
+      self.credits[index] = self.credits[index].saturating_sub(1);
+      self.queues[index].pop_front()
+    });
+    if let Some(weights) = self.weights {
+      if self.queues.iter().all(|queue| queue.is_empty()) {
+        self.credits = weights;
+      }
+    }
+    job.map(|queued| queued.pending)
+  }
+  /// Dequeues the next job to run if below the limit of running jobs.
+  fn take(&mut self) -> Option<Box<Pending>> {
+    if self.running >= self.limit {
+      return None;
+    }
+    let job = self.pop();
+    if job.is_some() {
+      self.running += 1;
+    }
+    job
+  }
+  /// Removes the longest waiting task.
+  fn pop_oldest(&mut self) -> Option<Box<Pending>> {
+    let index = (0..3).filter(|&index| !self.queues[index].is_empty())
+                      .min_by_key(|&index| self.queues[index][0].sequence);
+    index.and_then(|index| self.queues[index].pop_front())
+         .map(|queued| queued.pending)
+  }
+  /// Returns the highest priority with jobs waiting and credit remaining.
+  fn waiting(&self) -> Option<usize> {
+    (0..3).find(|&index| self.credits[index] > 0 && !self.queues[index].is_empty())
+  }
+}
+
+/// A queue of jobs in groups, dequeued from each group with jobs 
+/// waiting in turn.
+struct FairQueue {
+  groups: HashMap<String, VecDeque<Job>>,
+  turns:  VecDeque<String>
+}
+impl FairQueue {
+  fn new() -> FairQueue {
+    FairQueue {
+      groups: HashMap::new(),
+      turns:  VecDeque::new()
+    }
+  }
+  fn push(&mut self, group: &str, job: Job) {
+    let turns = &mut self.turns;
+    let queue = self.groups.entry(group.to_string()).or_insert_with(|| {
+      turns.push_back(group.to_string());
+      VecDeque::new()
+    });
+    queue.push_back(job);
+  }
+  fn pop(&mut self) -> Option<Job> {
+    let group = match self.turns.pop_front() {
+      Some(group) => group,
+      None        => return None
+    };
+    let (job, waiting) = {
+      let queue = self.groups.get_mut(&group).unwrap();
+      (queue.pop_front(), !queue.is_empty())
+    };
+    if waiting {
+      self.turns.push_back(group);
+    } else {
+      self.groups.remove(&group);
+    }
+    job
+  }
+}
+
+/// A closure run on an event loop once due. Ordered so the 
+/// earliest timer is the greatest, and so first out of a heap.
+struct Timer {
+  due:      Instant,
+  sequence: usize,
+  job:      Job
+}
+impl PartialEq for Timer {
+  fn eq(&self, other: &Timer) -> bool {
+    self.due == other.due && self.sequence == other.sequence
+  }
+}
+impl Eq for Timer {}
+impl PartialOrd for Timer {
+  fn partial_cmp(&self, other: &Timer) -> Option<cmp::Ordering> {
+    Some(self.cmp(other))
+  }
+}
+impl Ord for Timer {
+  fn cmp(&self, other: &Timer) -> cmp::Ordering {
+    (other.due, other.sequence).cmp(&(self.due, self.sequence))
+  }
+}
+
+/// The work waiting on an event loop.
+struct Events {
+  queue:   VecDeque<Job>,
+  timers:  BinaryHeap<Timer>,
+  next:    usize,
+  handles: usize,
+  stopped: bool
+}
+
+/// Runs the jobs of an event loop as they become ready, returning 
+/// once stopped, or once no handles remain and no work is waiting.
+/// Panics raised by timers and callbacks are caught, leaving the 
+/// event loop running.
+fn run_events(inner: &Arc<(Mutex<Events>, Condvar)>) {
+  let (ref lock, ref condvar) = **inner;
+  loop {
+    let job = {
+      let mut events = lock.lock().unwrap();
+      loop {
+        if events.stopped { 
+          return; 
+        }
+        let now = Instant::now();
+        if events.timers.peek().map_or(false, |timer| timer.due <= now) {
+          break events.timers.pop().unwrap().job;
+        }
+        if let Some(job) = events.queue.pop_front() {
+          break job;
+        }
+        let due = events.timers.peek().map(|timer| timer.due);
+        events = match due {
+          Some(due) => condvar.wait_timeout(events, due - now).unwrap().0,
+          None if events.handles == 0 => return,
+          None      => condvar.wait(events).unwrap()
+        };
+      }
+    };
+    if let Err(_) = panic::catch_unwind(AssertUnwindSafe(job)) {
+      log_debug!("EventLoopScheduler: callback panicked");
+    }
+  }
+}
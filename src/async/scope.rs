@@ -0,0 +1,256 @@
+/*--------------------------------------------------------------------------
+ smoke-rs
+
+ The MIT License (MIT)
+
+ Copyright (c) 2016 Haydn Paterson (sinclair) <haydn.developer@gmail.com>
+
+ Permission is hereby granted, free of charge, to any person obtaining a copy
+ of this software and associated documentation files (the "Software"), to deal
+ in the Software without restriction, including without limitation the rights
+ to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ copies of the Software, and to permit persons to whom the Software is
+ furnished to do so, subject to the following conditions:
+
+ The above copyright notice and this permission notice shall be included in
+ all copies or substantial portions of the Software.
+
+ THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+ THE SOFTWARE.
+---------------------------------------------------------------------------*/
+
+use std::mem;
+use std::marker::PhantomData;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Arc, Mutex, Condvar};
+use std::sync::mpsc::sync_channel;
+use super::task::{self, Task};
+use super::handle::TaskHandle;
+use super::scheduling::{Scheduler, ThreadScheduler};
+use super::super::error::Error;
+
+struct State {
+  cancelled: bool,
+  running:   usize
+}
+
+/// A scope for child tasks. Tasks run on a scope can be cancelled 
+/// and joined together, and when created with Task::scope, are 
+/// cancelled and joined when the parent task completes. Cancellation
+/// is cooperative; tasks run on a scope after it is cancelled fail 
+/// with Cancelled without running, and tasks already running can 
+/// observe cancellation with is_cancelled().
+///
+/// # Example
+/// ```
+/// use std::time::Duration;
+/// use smoke::async::Task;
+///
+/// let task = Task::scope(|scope| {
+///   let child = scope.run(Task::delay_for(Duration::from_millis(10)).map(|_| 10));
+///   Task::new(move |sender| sender.send(child.wait().unwrap() + 1))
+/// });
+/// assert_eq!(task.wait().unwrap(), 11);
+/// ```
+#[derive(Clone)]
+pub struct TaskScope {
+  inner: Arc<(Mutex<State>, Condvar)>
+}
+impl TaskScope {
+  
+  /// Creates a new task scope.
+  pub fn new() -> TaskScope {
+    TaskScope { 
+      inner: Arc::new((Mutex::new(State { cancelled: false, running: 0 }), Condvar::new())) 
+    }
+  }
+  
+  /// Runs the given task on this scope on its own thread.
+  pub fn run<T, E>(&self, task: Task<T, E>) -> TaskHandle<T, E> where 
+      T: Send + 'static, 
+      E: From<Error> + Send + 'static {
+    self.run_on(ThreadScheduler, task)
+  }
+  
+  /// Runs the given task on this scope with the given scheduler.
+  pub fn run_on<S, T, E>(&self, scheduler: S, task: Task<T, E>) -> TaskHandle<T, E> where 
+      S: Scheduler,
+      T: Send + 'static, 
+      E: From<Error> + Send + 'static {
+    {
+      let mut state = self.inner.0.lock().unwrap();
+      if state.cancelled {
+        let (sender, receiver) = sync_channel(1);
+        let _ = sender.send(Err(E::from(Error::Cancelled)));
+        return TaskHandle::new(receiver);
+      }
+      state.running += 1;
+    }
+    let child = Child(self.clone());
+    scheduler.run(Task::fallible(move |sender| {
+      let _child = child;
+      // child handles may be dropped without waiting, 
+      // leaving the child's value undelivered.
+      let _ = match task::run_inline(task) {
+        Ok(value)  => sender.send(value),
+        Err(error) => sender.fail(error)
+      };
+      Ok(())
+    }))
+  }
+  
+  /// Cancels this scope. Tasks run on this scope from now on will 
+  /// fail with Cancelled.
+  pub fn cancel(&self) {
+    self.inner.0.lock().unwrap().cancelled = true;
+  }
+  
+  /// Returns true if this scope has been cancelled.
+  pub fn is_cancelled(&self) -> bool {
+    self.inner.0.lock().unwrap().cancelled
+  }
+  
+  /// Blocks until all tasks run on this scope have completed.
+  pub fn join(&self) {
+    let &(ref state, ref condvar) = &*self.inner;
+    let mut state = state.lock().unwrap();
+    while state.running > 0 {
+      state = condvar.wait(state).unwrap();
+    }
+  }
+}
+
+/// Marks a child task as completed on drop, including when 
+/// the child panics or is never run.
+struct Child(TaskScope);
+impl Drop for Child {
+  fn drop(&mut self) {
+    let &(ref state, ref condvar) = &*(self.0).inner;
+    state.lock().unwrap().running -= 1;
+    condvar.notify_all();
+  }
+}
+
+/// Cancels and joins a scope on drop.
+struct Guard(TaskScope);
+impl Drop for Guard {
+  fn drop(&mut self) {
+    self.0.cancel();
+    self.0.join();
+  }
+}
+
+impl<T, E> Task<T, E> where T: Send + 'static, E: From<Error> + Send + 'static {
+  
+  /// Creates a new task from the task given by the closure, with a scope
+  /// to run child tasks on. When the task completes or fails, the scope 
+  /// is cancelled and its child tasks are joined before this task resolves.
+  /// # Example
+  /// ```
+  /// use std::time::Duration;
+  /// use smoke::async::Task;
+  ///
+  /// let task = Task::scope(|scope| {
+  ///   let scope = scope.clone();
+  ///   Task::new(move |sender| {
+  ///     // never waited on, but will be joined.
+  ///     scope.run(Task::delay_for(Duration::from_millis(10)));
+  ///     sender.send(10)
+  ///   })
+  /// });
+  /// assert_eq!(task.wait().unwrap(), 10);
+  /// ```
+  pub fn scope<F>(func: F) -> Task<T, E> where 
+      F: FnOnce(&TaskScope) -> Task<T, E> + Send + 'static {
+    Task::fallible(move |sender| {
+      let scope  = TaskScope::new();
+      let result = {
+        let _guard = Guard(scope.clone());
+        task::run_inline(func(&scope))
+      };
+      match result {
+        Ok(value)  => sender.send(value),
+        Err(error) => sender.fail(error)
+      }
+    })
+  }
+}
+
+/// A scope for closures run on a scheduler that may borrow from the 
+/// stack of the caller. Created with Scheduler::scope(), which returns
+/// once all closures run on the scope have completed.
+pub struct SchedulerScope<'env, S: 'env> {
+  scheduler: &'env S,
+  scope:     TaskScope,
+  // invariant over 'env, so borrows cannot be shortened.
+  _env:      PhantomData<&'env mut &'env ()>
+}
+impl<'env, S> SchedulerScope<'env, S> where S: Scheduler {
+  
+  /// Runs the given closure on the scheduler of this scope, returning
+  /// a handle to its result. Closures that panic resolve with Panicked.
+  pub fn run<F, T>(&self, func: F) -> TaskHandle<T> where 
+      F: FnOnce() -> T + Send + 'env, 
+      T: Send + 'env {
+    let (sender, receiver) = sync_channel(1);
+    let job: Box<FnOnce() + Send + 'env> = Box::new(move || {
+      let result = panic::catch_unwind(AssertUnwindSafe(func));
+      let _ = sender.send(result.map_err(Error::from_panic));
+    });
+    // the scope joins the job before 'env ends, including if the job
+    // is dropped by the scheduler without running, so the job may be
+    // treated as 'static.
+    let job: Box<FnOnce() + Send + 'static> = unsafe { mem::transmute(job) };
+    self.scope.inner.0.lock().unwrap().running += 1;
+    let scoped = Scoped { job: Some(job), _child: Child(self.scope.clone()) };
+    self.scheduler.run(Task::new(move |sender| {
+      let mut scoped = scoped;
+      if let Some(job) = scoped.job.take() {
+        job();
+      }
+      drop(scoped);
+      sender.send(())
+    }));
+    TaskHandle::new(receiver)
+  }
+}
+
+/// A closure run on a scheduler scope. Drops the closure before 
+/// marking it as completed.
+struct Scoped {
+  job:    Option<Box<FnOnce() + Send + 'static>>,
+  _child: Child
+}
+impl Drop for Scoped {
+  fn drop(&mut self) {
+    self.job.take();
+  }
+}
+
+/// Creates a scope for closures that may borrow from the caller run on 
+/// the given scheduler, returning once all closures run on the scope 
+/// have completed, including if the given closure panics.
+pub(crate) fn scope<'env, S, F, R>(scheduler: &'env S, func: F) -> R where 
+    S: Scheduler,
+    F: FnOnce(&SchedulerScope<'env, S>) -> R {
+  let scope = SchedulerScope { 
+    scheduler: scheduler, 
+    scope:     TaskScope::new(), 
+    _env:      PhantomData 
+  };
+  let _join = Join(scope.scope.clone());
+  func(&scope)
+}
+
+/// Joins a scope on drop.
+struct Join(TaskScope);
+impl Drop for Join {
+  fn drop(&mut self) {
+    self.0.join();
+  }
+}
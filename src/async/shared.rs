@@ -0,0 +1,109 @@
+/*--------------------------------------------------------------------------
+ smoke-rs
+
+ The MIT License (MIT)
+
+ Copyright (c) 2016 Haydn Paterson (sinclair) <haydn.developer@gmail.com>
+
+ Permission is hereby granted, free of charge, to any person obtaining a copy
+ of this software and associated documentation files (the "Software"), to deal
+ in the Software without restriction, including without limitation the rights
+ to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ copies of the Software, and to permit persons to whom the Software is
+ furnished to do so, subject to the following conditions:
+
+ The above copyright notice and this permission notice shall be included in
+ all copies or substantial portions of the Software.
+
+ THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+ THE SOFTWARE.
+---------------------------------------------------------------------------*/
+
+use std::mem;
+use std::sync::{Arc, Mutex, Condvar};
+
+use super::task::{Task, run_inline};
+use super::super::error::Error;
+
+/// The execution state of a shared task.
+enum State<T, E> {
+  Pending(Task<T, E>),
+  Running,
+  Complete(Result<T, E>)
+}
+
+/// A task that is run at most once, with its result shared among
+/// all clones of this handle. The task is run on the first call to 
+/// wait(). Subsequent (or concurrent) calls block until the result 
+/// is available and receive a clone of it.
+///
+/// # Example
+/// ```
+/// use std::time::Duration;
+/// use smoke::async::Task;
+///
+/// let shared = Task::delay_for(Duration::from_millis(1)).map(|_| 10).shared();
+/// let other  = shared.clone();
+/// assert_eq!(shared.wait().unwrap(), 10);
+/// assert_eq!(other.wait().unwrap(),  10);
+/// ```
+pub struct SharedTask<T, E = Error> {
+  inner: Arc<(Mutex<State<T, E>>, Condvar)>
+}
+impl<T, E> Clone for SharedTask<T, E> {
+  fn clone(&self) -> SharedTask<T, E> {
+    SharedTask { inner: self.inner.clone() }
+  }
+}
+impl<T, E> SharedTask<T, E> where T: Clone + Send + 'static, 
+                                  E: Clone + From<Error> + Send + 'static {
+  
+  /// Creates a new shared task from the given task.
+  pub fn new(task: Task<T, E>) -> SharedTask<T, E> {
+    SharedTask { inner: Arc::new((Mutex::new(State::Pending(task)), Condvar::new())) }
+  }
+  
+  /// Waits for the result of the shared task, running it if
+  /// it has not been run already.
+  pub fn wait(&self) -> Result<T, E> {
+    let (ref lock, ref condvar) = *self.inner;
+    let mut state = lock.lock().unwrap();
+    let pending   = match *state {
+      State::Pending(_) => true,
+      _                 => false
+    };
+    if pending {
+      let task = match mem::replace(&mut *state, State::Running) {
+        State::Pending(task) => task,
+        _                    => unreachable!()
+      };
+      drop(state);
+      let result    = run_inline(task);
+      let mut state = lock.lock().unwrap();
+      *state = State::Complete(result.clone());
+      condvar.notify_all();
+      return result;
+    }
+    loop {
+      match *state {
+        State::Complete(ref result) => return result.clone(),
+        _ => state = condvar.wait(state).unwrap()
+      }
+    }
+  }
+  
+  /// Creates a new task that resolves with a clone of the shared
+  /// result, allowing the shared task to be composed with other tasks.
+  pub fn task(&self) -> Task<T, E> {
+    let shared = self.clone();
+    Task::fallible(move |sender| match shared.wait() {
+      Ok(value)  => sender.send(value),
+      Err(error) => sender.fail(error)
+    })
+  }
+}
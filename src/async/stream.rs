@@ -24,16 +24,34 @@
  THE SOFTWARE.
 ---------------------------------------------------------------------------*/
 
+use std::mem;
+use std::rc::Rc;
 use std::thread;
+use std::cell::RefCell;
+use std::panic::{self, AssertUnwindSafe};
+use std::hash::Hash;
+use std::cmp::Ordering;
+use std::ops::Range;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::time::{Duration, Instant};
+use std::sync::{Arc, Mutex, Condvar};
+use std::sync::atomic::{self, AtomicBool, AtomicUsize};
 use std::sync::mpsc::{
+   channel,
    sync_channel, 
    SyncSender,
    SendError,
    Receiver,
-   RecvError
+   RecvError,
+   TryRecvError,
+   RecvTimeoutError
 };
 
-use super::task::Task;
+use super::task::{self, Task, TaskSender};
+use super::retry::RetryPolicy;
+use super::scheduling::{self, Scheduler, DefaultScheduler, PooledScheduler, Runner};
+use super::super::metrics;
+use super::super::error::Error;
 
 /// Specialized boxed FnOnce() closure type for streams.
 trait Func<T, TResult> {
@@ -45,16 +63,380 @@ impl<T, TResult, F> Func<T, TResult> for F where F: FnOnce(T) -> TResult {
     }
 }
 
-/// Wraps a mpsc SyncSender<T>
-pub type StreamSender<T> = SyncSender<T>;
+/// The boxed closure type of stream stages.
+type BoxedFunc<T> = Box<Func<StreamSender<T>, Result<(), SendError<T>>> + Send + 'static>;
 
-/// Wraps a mpsc Receiver<T>
-pub type StreamReceiver<T> = Receiver<T>;
+/// The elements of fused stages, pulled through on the thread of the
+/// stage reading them, with the error of the stream they were read from
+/// once the elements have ended.
+struct Pulled<T> {
+  elements: Box<Iterator<Item = T>>,
+  error:    Box<Fn() -> Option<Error>>
+}
+
+/// A stage fused into the stage reading it, rather than run on a stage
+/// of its own. Used for the per element operators, avoiding a thread and
+/// channel per operator.
+trait Fused<T>: Send {
+  fn pull(self: Box<Self>) -> Pulled<T>;
+}
+
+/// The operation of a stream.
+enum Body<T> {
+  Func(BoxedFunc<T>),
+  Fused(Box<Fused<T>>)
+}
+
+/// Iterator over a stream receiver shared with the error of a pull.
+struct Shared<T> {
+  receiver: Rc<StreamReceiver<T>>
+}
+impl<T> Iterator for Shared<T> {
+  type Item = T;
+  fn next(&mut self) -> Option<T> {
+    self.receiver.recv().ok()
+  }
+}
+
+/// A stream mapped through a closure.
+struct Map<T, F> {
+  stream: Stream<T>,
+  func:   F
+}
+impl<T, U, F> Fused<U> for Map<T, F> where T: Send + 'static, F: Fn(T) -> U + Send + 'static {
+  fn pull(self: Box<Self>) -> Pulled<U> {
+    let pulled = self.stream.pull();
+    Pulled { elements: Box::new(pulled.elements.map(self.func)), error: pulled.error }
+  }
+}
+
+/// A stream filtered by a predicate.
+struct Filter<T, F> {
+  stream: Stream<T>,
+  func:   F
+}
+impl<T, F> Fused<T> for Filter<T, F> where T: Send + 'static, F: Fn(&T) -> bool + Send + 'static {
+  fn pull(self: Box<Self>) -> Pulled<T> {
+    let pulled = self.stream.pull();
+    Pulled { elements: Box::new(pulled.elements.filter(self.func)), error: pulled.error }
+  }
+}
+
+/// A stream whose elements are passed to a closure as they are read.
+struct Inspect<T, F> {
+  stream: Stream<T>,
+  func:   F
+}
+impl<T, F> Fused<T> for Inspect<T, F> where T: Send + 'static, F: Fn(&T) + Send + 'static {
+  fn pull(self: Box<Self>) -> Pulled<T> {
+    let pulled = self.stream.pull();
+    Pulled { elements: Box::new(pulled.elements.inspect(self.func)), error: pulled.error }
+  }
+}
+
+thread_local! {
+  /// The scheduler the stage on this thread is bound to.
+  static RUNNER: RefCell<Option<Runner>> = RefCell::new(None);
+}
+
+/// Restores the previous scheduler bound to this thread on drop, so
+/// a stage that unwinds does not leave its scheduler on the thread.
+struct Bound {
+  previous: Option<Runner>
+}
+impl Drop for Bound {
+  fn drop(&mut self) {
+    let previous = self.previous.take();
+    RUNNER.with(|current| *current.borrow_mut() = previous);
+  }
+}
+
+/// Binds the thread to the given scheduler until the returned guard 
+/// is dropped.
+fn bind(runner: Runner) -> Bound {
+  Bound { previous: RUNNER.with(|current| mem::replace(&mut *current.borrow_mut(), Some(runner))) }
+}
+
+/// Spawns a stream stage on the given scheduler, or the scheduler of 
+/// the current stage, binding the stage to the scheduler. Stages not
+/// bound to a scheduler are spawned on a new thread.
+fn spawn_stage<F>(runner: Option<Runner>, func: F) where F: FnOnce() + Send + 'static {
+  match runner.or_else(|| RUNNER.with(|current| current.borrow().clone())) {
+    None         => scheduling::spawn(func),
+    Some(runner) => {
+      let bound = runner.clone();
+      let _ = runner(Task::new(move |sender| {
+        let scope = bind(bound);
+        func();
+        drop(scope);
+        // the handle of the stage is not kept.
+        let _ = sender.send(());
+        Ok(())
+      }));
+    }
+  }
+}
+
+/// Creates a stream sender and receiver pair with the given bound.
+fn stream_channel<T>(bound: usize) -> (StreamSender<T>, StreamReceiver<T>) {
+  let (sender, receiver) = sync_channel(bound);
+  (StreamSender   { sender: sender, failed: Arc::new(AtomicBool::new(false)) },
+   StreamReceiver { receiver: receiver, error: RefCell::new(None) })
+}
+
+/// Forwards the elements of the receiver to the sender, failing the 
+/// sender if the receiver's stream failed.
+fn forward<T>(receiver: StreamReceiver<T>, sender: &StreamSender<T>) -> Result<(), SendError<T>> {
+  for n in receiver.iter() {
+    try!( sender.send(n) );
+  }
+  end(&receiver, sender)
+}
+
+/// Ends the sender's stream once the receiver's stream has ended, 
+/// failing it with the error of the receiver's stream if it failed.
+/// Stages call this once done reading, as errors are not forwarded
+/// otherwise.
+fn end<T, U>(receiver: &StreamReceiver<T>, sender: &StreamSender<U>) -> Result<(), SendError<U>> {
+  match receiver.error() {
+    Some(error) => sender.fail(error),
+    None        => Ok(())
+  }
+}
+
+/// An element of either of two joined streams.
+enum Side<A, B> {
+  Left(A),
+  Right(B)
+}
+
+/// An event of a switched stream. Inner stream events carry the
+/// generation of the inner stream they were read from.
+enum Switched<T> {
+  Inner(Stream<T>),
+  Next(usize, T),
+  End(usize, Option<Error>),
+  Ended(Option<Error>)
+}
+
+/// Discards elements held for longer than the window.
+fn expire<K, V>(pending: &mut HashMap<K, VecDeque<(Instant, V)>>, now: Instant, window: Duration) where K: Eq + Hash {
+  pending.retain(|_, elements| {
+    while elements.front().map_or(false, |&(received, _)| now.duration_since(received) > window) {
+      elements.pop_front();
+    }
+    !elements.is_empty()
+  });
+}
+
+/// Returns the number of intervals in the given duration.
+fn duration_ratio(duration: Duration, interval: Duration) -> f64 {
+  if interval == Duration::from_secs(0) {
+    return ::std::f64::INFINITY;
+  }
+  duration.as_secs_f64() / interval.as_secs_f64()
+}
+
+/// Resolves the task sender with the value, or with the error of 
+/// the receiver's stream if it failed.
+fn resolve<T, U>(receiver: &StreamReceiver<T>, sender: TaskSender<U>, value: U) -> Result<(), SendError<U>> {
+  match receiver.error() {
+    Some(error) => sender.fail(error),
+    None        => sender.send(value)
+  }
+}
+
+/// Sends elements on a stream. Wraps a mpsc SyncSender.
+pub struct StreamSender<T> {
+  sender: SyncSender<Result<T, Error>>,
+  failed: Arc<AtomicBool>
+}
+impl<T> Clone for StreamSender<T> {
+  fn clone(&self) -> StreamSender<T> {
+    StreamSender { sender: self.sender.clone(), failed: self.failed.clone() }
+  }
+}
+impl<T> StreamSender<T> {
+  
+  /// Sends an element on the stream, returning an error if the stream
+  /// is no longer read or has failed.
+  pub fn send(&self, value: T) -> Result<(), SendError<T>> {
+    if self.failed.load(atomic::Ordering::SeqCst) {
+      return Err(SendError(value));
+    }
+    match self.sender.send(Ok(value)) {
+      Err(SendError(Ok(value))) => Err(SendError(value)),
+      _ => Ok(())
+    }
+  }
+  
+  /// Fails the stream with the given error, ending the stream for its 
+  /// reader after the elements already sent. Elements sent after are
+  /// discarded. If the stream is no longer read, the error is discarded.
+  /// # Example
+  ///
+  /// ```
+  /// use smoke::Error;
+  /// use smoke::async::Stream;
+  ///
+  /// let numbers = Stream::output(|sender| {
+  ///   try!(sender.send(1));
+  ///   sender.fail(Error::Timeout)
+  /// });
+  /// let receiver = numbers.read();
+  /// assert_eq!(receiver.iter().collect::<Vec<_>>(), vec![1]);
+  /// assert!(receiver.error().is_some());
+  /// ```
+  pub fn fail(&self, error: Error) -> Result<(), SendError<T>> {
+    if !self.failed.swap(true, atomic::Ordering::SeqCst) {
+      let _ = self.sender.send(Err(error));
+    } Ok(())
+  }
+}
+
+/// Receives elements from a stream. Wraps a mpsc Receiver.
+///
+/// A stream ends either when its source ends, or when its source fails,
+/// with the error available from error().
+pub struct StreamReceiver<T> {
+  receiver: Receiver<Result<T, Error>>,
+  error:    RefCell<Option<Error>>
+}
+impl<T> StreamReceiver<T> {
+  
+  /// Receives the next element, blocking until one is available. 
+  /// Returns an error once the stream has ended or failed.
+  pub fn recv(&self) -> Result<T, RecvError> {
+    if self.error.borrow().is_some() {
+      return Err(RecvError);
+    }
+    match self.receiver.recv() {
+      Ok(Ok(value))  => Ok(value),
+      Ok(Err(error)) => { 
+        *self.error.borrow_mut() = Some(error);
+        Err(RecvError)
+      },
+      Err(error)     => Err(error)
+    }
+  }
+  
+  /// Receives the next element if one is available without blocking.
+  pub fn try_recv(&self) -> Result<T, TryRecvError> {
+    if self.error.borrow().is_some() {
+      return Err(TryRecvError::Disconnected);
+    }
+    match self.receiver.try_recv() {
+      Ok(Ok(value))  => Ok(value),
+      Ok(Err(error)) => { 
+        *self.error.borrow_mut() = Some(error);
+        Err(TryRecvError::Disconnected)
+      },
+      Err(error)     => Err(error)
+    }
+  }
+  
+  /// Receives the next element, blocking for at most the given duration.
+  pub fn recv_timeout(&self, timeout: Duration) -> Result<T, RecvTimeoutError> {
+    if self.error.borrow().is_some() {
+      return Err(RecvTimeoutError::Disconnected);
+    }
+    match self.receiver.recv_timeout(timeout) {
+      Ok(Ok(value))  => Ok(value),
+      Ok(Err(error)) => { 
+        *self.error.borrow_mut() = Some(error);
+        Err(RecvTimeoutError::Disconnected)
+      },
+      Err(error)     => Err(error)
+    }
+  }
+  
+  /// Returns an iterator over the elements of the stream, ending when 
+  /// the stream ends or fails.
+  pub fn iter(&self) -> Iter<T> {
+    Iter { receiver: self }
+  }
+  
+  /// Returns the error the stream failed with, if the stream has failed.
+  /// # Example
+  ///
+  /// ```
+  /// use smoke::io::Read;
+  ///
+  /// let receiver = std::io::empty().to_stream(16384).read();
+  /// for bytes in receiver.iter() {
+  ///   // ...
+  /// }
+  /// assert!(receiver.error().is_none());
+  /// ```
+  pub fn error(&self) -> Option<Error> {
+    self.error.borrow().clone()
+  }
+}
+/// Iterator over the elements of a stream receiver.
+pub struct Iter<'a, T: 'a> {
+  receiver: &'a StreamReceiver<T>
+}
+impl<'a, T> Iterator for Iter<'a, T> {
+  type Item = T;
+  fn next(&mut self) -> Option<T> {
+    self.receiver.recv().ok()
+  }
+}
+
+/// Owning iterator over the elements of a stream receiver.
+pub struct IntoIter<T> {
+  receiver: StreamReceiver<T>
+}
+impl<T> Iterator for IntoIter<T> {
+  type Item = T;
+  fn next(&mut self) -> Option<T> {
+    self.receiver.recv().ok()
+  }
+}
+impl<'a, T> IntoIterator for &'a StreamReceiver<T> {
+  type Item     = T;
+  type IntoIter = Iter<'a, T>;
+  fn into_iter(self) -> Iter<'a, T> {
+    self.iter()
+  }
+}
+impl<T> IntoIterator for StreamReceiver<T> {
+  type Item     = T;
+  type IntoIter = IntoIter<T>;
+  fn into_iter(self) -> IntoIter<T> {
+    IntoIter { receiver: self }
+  }
+}
+
+/// Strategies for streams whose reader is slower than the source.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Backpressure {
+  /// The source is held back until the reader is ready. (default)
+  Block,
+  /// Buffers up to the given number of elements, dropping the 
+  /// oldest buffered element when full.
+  DropOldest(usize),
+  /// Buffers up to the given number of elements, dropping new 
+  /// elements when full.
+  DropNewest(usize),
+  /// Keeps only the latest element, replacing it when not yet read.
+  Latest
+}
+
+/// Elements buffered between a source stream and a slower reader.
+struct Buffer<T> {
+  queue:  VecDeque<T>,
+  error:  Option<Error>,
+  ended:  bool,
+  closed: bool
+}
 
 /// Provides functionality to generate asynchronous sequences.
 pub struct Stream<T>  {
-  /// The closure used to emit elements on this stream.
-  func: Box<Func<SyncSender<T>, Result<(), SendError<T>>> + Send + 'static>
+  /// The operation used to emit elements on this stream.
+  body: Body<T>,
+  /// The scheduler this stream is bound to, if any.
+  runner: Option<Runner>
 }
 
 impl<T> Stream<T> where T: Send + 'static {
@@ -76,8 +458,8 @@ impl<T> Stream<T> where T: Send + 'static {
   /// }
   /// ```
   pub fn output<F>(func:F) -> Stream<T>  where
-      F: FnOnce(SyncSender<T>) -> Result<(), SendError<T>> + Send + 'static {
-      Stream { func: Box::new(func) }
+      F: FnOnce(StreamSender<T>) -> Result<(), SendError<T>> + Send + 'static {
+      Stream { body: Body::Func(Box::new(func)), runner: None }
   }
   
   /// Creates a input stream which externally receives values.
@@ -97,12 +479,194 @@ impl<T> Stream<T> where T: Send + 'static {
   /// }
   /// ```
   pub fn input<F>(func:F) -> StreamSender<T>  
-      where F: FnOnce(Receiver<T>) + Send + 'static {
-      let (tx, rx) = sync_channel(1);
-      let _ = thread::spawn(move || func(rx));
+      where F: FnOnce(StreamReceiver<T>) + Send + 'static {
+      let (tx, rx) = stream_channel(1);
+      scheduling::spawn(move || func(rx));
       tx
   }
   
+  /// Creates a stream which emits no elements.
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// use smoke::async::Stream;
+  ///
+  /// assert_eq!(Stream::<i32>::empty().read().iter().count(), 0);
+  /// ```
+  pub fn empty() -> Stream<T> {
+      Stream::output(|_| Ok(()))
+  }
+  
+  /// Creates a stream which emits the given element once.
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// use smoke::async::Stream;
+  ///
+  /// assert_eq!(Stream::once(10).read().iter().collect::<Vec<_>>(), vec![10]);
+  /// ```
+  pub fn once(value: T) -> Stream<T> {
+      Stream::output(move |sender| sender.send(value))
+  }
+  
+  /// Creates a stream which emits the given element repeatedly, ending
+  /// only when the stream is no longer read.
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// use smoke::async::Stream;
+  ///
+  /// let values = Stream::repeat("a").take(3);
+  /// assert_eq!(values.read().iter().collect::<Vec<_>>(), vec!["a", "a", "a"]);
+  /// ```
+  pub fn repeat(value: T) -> Stream<T> where T: Clone {
+      Stream::output(move |sender| {
+        loop {
+          try!( sender.send(value.clone()) );
+        }
+      })
+  }
+  
+  /// Creates a stream emitting each element of the vector resolved
+  /// by the given task. The task is run when the stream is read. If
+  /// the task fails, the stream fails with the task's error.
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// use smoke::async::{Stream, Task};
+  ///
+  /// let stream = Stream::from_task(Task::from_value(vec![1, 2, 3]));
+  /// assert_eq!(stream.read().iter().collect::<Vec<_>>(), vec![1, 2, 3]);
+  /// ```
+  pub fn from_task<E>(task: Task<Vec<T>, E>) -> Stream<T> where 
+      E: From<Error> + Into<Error> + Send + 'static {
+      Stream::output(move |sender| {
+        match task::run_inline(task) {
+          Ok(values) => {
+            for value in values {
+              try!( sender.send(value) );
+            } Ok(())
+          },
+          Err(error) => sender.fail(error.into())
+        }
+      })
+  }
+  
+  /// Creates a stream emitting the elements of the stream given by the
+  /// factory, creating the stream again up to the given number of 
+  /// retries if it fails. Elements emitted before a failure are not
+  /// emitted again, so the stream resumes with the elements of the new
+  /// stream. If every attempt fails, the stream fails with the last 
+  /// error.
+  /// # Example
+  ///
+  /// ```
+  /// use smoke::io::Read;
+  /// use smoke::async::Stream;
+  ///
+  /// let lines = Stream::retry(3, || std::io::empty().to_line_stream());
+  /// assert_eq!(lines.read().iter().count(), 0);
+  /// ```
+  pub fn retry<F>(retries: usize, factory: F) -> Stream<T>
+      where F: Fn() -> Stream<T> + Send + 'static {
+      Stream::retry_with(RetryPolicy::immediate(retries), factory)
+  }
+  
+  /// Creates a stream emitting the elements of the stream given by the
+  /// factory, creating the stream again if it fails. The number of 
+  /// retries and the delay between them is given by the policy. Retries
+  /// are counted from the last attempt to emit an element, so long lived
+  /// streams may fail any number of times if they make progress between.
+  /// # Example
+  ///
+  /// ```
+  /// use std::time::Duration;
+  /// use smoke::async::{Stream, RetryPolicy};
+  ///
+  /// let policy  = RetryPolicy::fixed(3, Duration::from_millis(10));
+  /// let numbers = Stream::retry_with(policy, || Stream::range(0, 4));
+  /// assert_eq!(numbers.read().iter().count(), 4);
+  /// ```
+  pub fn retry_with<F>(policy: RetryPolicy, factory: F) -> Stream<T>
+      where F: Fn() -> Stream<T> + Send + 'static {
+      Stream::output(move |sender| {
+        let mut retry = 0;
+        loop {
+          let receiver = factory().read();
+          for n in receiver.iter() {
+            retry = 0;
+            try!( sender.send(n) );
+          }
+          match receiver.error() {
+            None        => return Ok(()),
+            Some(error) => {
+              if retry >= policy.retries {
+                return sender.fail(error)
+              }
+              retry += 1;
+              log_debug!("stream: retrying failed stream: {}", error);
+              thread::sleep(policy.delay(retry));
+            }
+          }
+        }
+      })
+  }
+  
+  /// Creates a stream as retry_with, passing the factory the last element
+  /// emitted before the failure, allowing the new stream to resume from 
+  /// a checkpoint. The factory is passed None for the first attempt, or
+  /// if no element has been emitted.
+  /// # Example
+  ///
+  /// ```
+  /// use smoke::Error;
+  /// use smoke::async::{Stream, RetryPolicy};
+  ///
+  /// // a source failing after every two elements.
+  /// fn source(from: i32) -> Stream<i32> {
+  ///   Stream::output(move |sender| {
+  ///     try!(sender.send(from));
+  ///     try!(sender.send(from + 1));
+  ///     if from + 2 < 6 { sender.fail(Error::Timeout) } else { Ok(()) }
+  ///   })
+  /// }
+  /// let numbers = Stream::retry_from(RetryPolicy::immediate(1), |last: Option<&i32>| {
+  ///   source(last.map(|n| n + 1).unwrap_or(0))
+  /// });
+  /// assert_eq!(numbers.read().iter().collect::<Vec<_>>(), vec![0, 1, 2, 3, 4, 5]);
+  /// ```
+  pub fn retry_from<F>(policy: RetryPolicy, factory: F) -> Stream<T>
+      where T: Clone, 
+            F: Fn(Option<&T>) -> Stream<T> + Send + 'static {
+      Stream::output(move |sender| {
+        let mut retry = 0;
+        let mut last  = None;
+        loop {
+          let receiver = factory(last.as_ref()).read();
+          for n in receiver.iter() {
+            retry = 0;
+            last  = Some(n.clone());
+            try!( sender.send(n) );
+          }
+          match receiver.error() {
+            None        => return Ok(()),
+            Some(error) => {
+              if retry >= policy.retries {
+                return sender.fail(error)
+              }
+              retry += 1;
+              log_debug!("stream: retrying failed stream: {}", error);
+              thread::sleep(policy.delay(retry));
+            }
+          }
+        }
+      })
+  }
+  
   /// Reads elements from the stream.
   /// # Example
   ///
@@ -113,8 +677,78 @@ impl<T> Stream<T> where T: Send + 'static {
   ///     // 0, 1, 2, 3
   /// } 
   pub fn read(self) -> StreamReceiver<T> {
-      let (tx, rx) = sync_channel(1);
-      let _ = thread::spawn(move || self.func.call(tx));
+      self.read_bounded(1)
+  }
+  
+  /// Runs this stream as a stage emitting on the given sender. Stages
+  /// that panic fail the stream with the panic.
+  fn run(self, sender: StreamSender<T>) {
+      metrics::STREAMS_STARTED.increment();
+      log_trace!("stream: stage started");
+      let failure = sender.clone();
+      let func    = self.into_func();
+      if let Err(payload) = panic::catch_unwind(AssertUnwindSafe(move || func.call(sender))) {
+        let _ = failure.fail(Error::from_panic(payload));
+      }
+      metrics::STREAMS_COMPLETED.increment();
+      log_trace!("stream: stage ended");
+  }
+  
+  /// Converts this stream into the closure of its stage. Fused stages
+  /// are pulled through on the thread of the stage.
+  fn into_func(self) -> BoxedFunc<T> {
+      match self.body {
+        Body::Func(func)   => func,
+        Body::Fused(fused) => Box::new(move |sender: StreamSender<T>| {
+          let pulled = fused.pull();
+          for n in pulled.elements {
+            try!( sender.send(n) );
+          }
+          match (pulled.error)() {
+            Some(error) => sender.fail(error),
+            None        => Ok(())
+          }
+        })
+      }
+  }
+  
+  /// Pulls the elements of this stream on the current thread. Streams
+  /// that are not fused are read from a stage of their own.
+  fn pull(self) -> Pulled<T> {
+      match self.body {
+        Body::Fused(fused) => fused.pull(),
+        Body::Func(_)      => {
+          let receiver = Rc::new(self.read());
+          let shared   = receiver.clone();
+          Pulled {
+            elements: Box::new(Shared { receiver: shared }),
+            error:    Box::new(move || receiver.error())
+          }
+        }
+      }
+  }
+  
+  /// Reads elements from the stream, running the stream on the default
+  /// scheduler, as set with set_default_scheduler, rather than its own 
+  /// thread. Each stream stage holds a thread of the scheduler until 
+  /// the stream ends, so schedulers should have enough threads for the
+  /// stages of the streams spawned on them.
+  /// # Example
+  ///
+  /// ```
+  /// use smoke::async::Stream;
+  ///
+  /// let values = Stream::range(0, 4).spawn().iter().collect::<Vec<_>>();
+  /// assert_eq!(values, vec![0, 1, 2, 3]);
+  /// ```
+  pub fn spawn(self) -> StreamReceiver<T> {
+      let (tx, rx) = stream_channel(1);
+      let _ = DefaultScheduler.run(Task::new(move |sender| {
+        self.run(tx);
+        // the handle of the stage is not kept.
+        let _ = sender.send(());
+        Ok(())
+      }));
       rx
   }
   
@@ -128,11 +762,66 @@ impl<T> Stream<T> where T: Send + 'static {
   ///     // 0, 1, 2, 3
   /// } 
   pub fn read_bounded(self, bound: usize) -> StreamReceiver<T> {
-      let (tx, rx) = sync_channel(bound);
-      let _ = thread::spawn(move || self.func.call(tx));
+      let (tx, rx) = stream_channel(bound);
+      let runner   = self.runner.clone();
+      spawn_stage(runner, move || self.run(tx));
       rx
   }
   
+  /// Reads elements from the stream with a handle to pause, resume or
+  /// stop reading. While paused, the source stream is not read, holding
+  /// it back. Stopping ends the stream, taking effect immediately if 
+  /// paused, or once the next element is received.
+  /// # Example
+  ///
+  /// ```
+  /// use smoke::async::Stream;
+  ///
+  /// let (receiver, control) = Stream::range(0, 100).read_controlled();
+  /// assert_eq!(receiver.recv().unwrap(), 0);
+  /// control.pause();
+  /// // .. rebalance
+  /// control.resume();
+  /// assert_eq!(receiver.recv().unwrap(), 1);
+  /// control.stop();
+  /// ```
+  pub fn read_controlled(self) -> (StreamReceiver<T>, StreamControl) {
+      let control = StreamControl::new();
+      let gate    = control.clone();
+      let gated   = Stream::output(move |sender| {
+        let receiver = self.read();
+        while gate.wait() {
+          match receiver.recv() {
+            Ok(n)  => try!( sender.send(n) ),
+            Err(_) => return end(&receiver, &sender)
+          }
+        } Ok(())
+      });
+      (gated.read(), control)
+  }
+  
+  /// Binds this stream to the given scheduler. The stage of this stream,
+  /// and the stages of the streams it reads, are run on the scheduler
+  /// rather than on threads of their own, allowing pipelines to share a
+  /// bounded threadpool. Stages hold a thread of the scheduler until 
+  /// their stream ends, so the scheduler should have a thread for each
+  /// stage of the pipelines running on it.
+  /// # Example
+  ///
+  /// ```
+  /// use smoke::async::{Stream, ThreadPoolScheduler};
+  ///
+  /// let scheduler = ThreadPoolScheduler::new(4);
+  /// let numbers   = Stream::range(0, 4).map(|n| n * 2)
+  ///                                    .filter(|n| *n > 2)
+  ///                                    .via(scheduler);
+  /// assert_eq!(numbers.read().iter().collect::<Vec<_>>(), vec![4, 6]);
+  /// ```
+  pub fn via<S>(self, scheduler: S) -> Stream<T> where S: Scheduler + Send + Sync + 'static {
+      let runner: Runner = Arc::new(move |task| scheduler.run(task));
+      Stream { body: self.body, runner: Some(runner) }
+  }
+  
   /// Will merge multiple streams into a single stream. 
   /// # Example
   ///
@@ -148,111 +837,1918 @@ impl<T> Stream<T> where T: Send + 'static {
   /// ```
   pub fn merge(streams: Vec<Stream<T>>) -> Stream<T> {
       Stream::output(move |sender| {
-        let handles = streams.into_iter()
-                .map(move |stream| {
-                  let sender = sender.clone();
-                  thread::spawn(move ||
-                      stream.read().into_iter()
-                                   .map(|n| sender.send(n))
-                                   .last()
-                                   .unwrap())
-                      }).collect::<Vec<_>>()
-                        .into_iter()
-                        .map(|handle| handle.join())
-                        .collect::<Result<Vec<_>, _>>();
-          match handles {
-              Err(error) => panic!(error),
-              Ok(send_results) => 
-                  match send_results.into_iter()
-                                    .collect::<Result<Vec<_>, _>>() {
-                                        Err(err) => Err(err),
-                                        Ok(_)    => Ok (())
-                                    }
-          }
+        let (tx, rx) = channel();
+        for stream in streams {
+          let sender = sender.clone();
+          let tx     = tx.clone();
+          spawn_stage(None, move || {
+            let _ = tx.send(forward(stream.read(), &sender));
+          });
+        }
+        drop(tx);
+        rx.iter().collect::<Vec<_>>()
+                 .into_iter()
+                 .collect::<Result<Vec<_>, _>>()
+                 .map(|_| ())
       })
   }
   
-  /// Will filter elements from the source stream.
+  /// Will concatenate multiple streams into a single stream, reading
+  /// each stream to its end before the next stream is started.
   /// # Example
   ///
   /// ```
   /// use smoke::async::Stream;
   ///
-  /// let numbers = Stream::range(0, 100);
-  /// let evens   = numbers.filter(|n| n % 2 == 0);
-  /// for n in evens.read() {
-  ///     // only even numbers
-  /// }
+  /// let a = Stream::range(0, 2);
+  /// let b = Stream::range(2, 4);
+  /// let c = Stream::concat(vec![a, b]);
+  /// assert_eq!(c.read().iter().collect::<Vec<_>>(), vec![0, 1, 2, 3]);
   /// ```
-  pub fn filter<F>(self, func:F) -> Stream<T> 
-      where F: Fn(&T) -> bool + Send + 'static {
-      Stream::output(move |sender|
-        self.read().into_iter()
-                   .filter(|n| func(n))
-                   .map(|n| sender.send(n))
-                   .last()
-                   .unwrap())
+  pub fn concat(streams: Vec<Stream<T>>) -> Stream<T> {
+      Stream::output(move |sender| {
+        for stream in streams {
+          let receiver = stream.read();
+          for n in receiver.iter() {
+            try!( sender.send(n) );
+          }
+          if let Some(error) = receiver.error() {
+            return sender.fail(error);
+          }
+        } Ok(())
+      })
   }
   
-  /// Will map the source stream into a new stream.
+  /// Will interleave multiple streams into a single stream, taking one
+  /// element from each stream in turn. Streams that end are removed 
+  /// from the rotation. Unlike merge, a fast stream cannot starve the
+  /// others, though a slow stream holds back the rotation.
   /// # Example
   ///
   /// ```
   /// use smoke::async::Stream;
   ///
-  /// let numbers = Stream::range(0, 100);
-  /// let strings = numbers.map(|n| format!("number {}", n));
-  /// for n in strings.read() {
-  ///     // strings
-  /// }
-  /// ```    
-  pub fn map<F, U>(self, func:F) -> Stream<U>
-     where U: Send + 'static,
-           F: Fn(T) -> U + Send + 'static {
-      Stream::output(move |sender| 
-        self.read().into_iter()
-                   .map(|n| sender.send(func(n)))
-                   .last()
-                   .unwrap())
+  /// let a = Stream::range(0, 3);
+  /// let b = Stream::range(10, 12);
+  /// let c = Stream::interleave(vec![a, b]);
+  /// assert_eq!(c.read().iter().collect::<Vec<_>>(), vec![0, 10, 1, 11, 2]);
+  /// ```
+  pub fn interleave(streams: Vec<Stream<T>>) -> Stream<T> {
+      Stream::output(move |sender| {
+        let mut receivers = streams.into_iter()
+                                   .map(|stream| stream.read())
+                                   .collect::<Vec<_>>();
+        while receivers.len() > 0 {
+          let mut index = 0;
+          while index < receivers.len() {
+            match receivers[index].recv() {
+              Ok(n)  => { 
+                try!( sender.send(n) );
+                index += 1;
+              },
+              Err(_) => { 
+                let receiver = receivers.remove(index);
+                if let Some(error) = receiver.error() {
+                  return sender.fail(error);
+                }
+              }
+            }
+          }
+        } Ok(())
+      })
   }
   
-  /// Reduces elements in the source stream and returns a task
-  /// to obtain the result.
+  /// Will merge multiple sorted streams into a single sorted stream,
+  /// taking the least of the next element of each stream in turn. The 
+  /// source streams are expected to be sorted.
   /// # Example
   ///
   /// ```
-  /// use smoke::async::Stream;
+  /// use smoke::async::{Stream, ToStream};
   ///
-  /// let numbers  = Stream::range(0, 100);
-  /// let task     = numbers.fold(0, |p, c| p + c);
-  /// let result   = task.wait().unwrap();
-  /// ```  
-  pub fn fold<F>(self, init: T, func:F) -> Task<T> 
-    where F: FnMut(T, T) -> T + Send + 'static {
-      Task::new(move |sender|  sender.send(self.read()
-                                     .into_iter()
-                                     .fold(init, func)))
+  /// let a = vec![1, 4, 7].into_iter().to_stream();
+  /// let b = vec![2, 3, 8].into_iter().to_stream();
+  /// let c = Stream::merge_sorted(vec![a, b]);
+  /// assert_eq!(c.read().iter().collect::<Vec<_>>(), vec![1, 2, 3, 4, 7, 8]);
+  /// ```
+  pub fn merge_sorted(streams: Vec<Stream<T>>) -> Stream<T> where T: Ord {
+      Stream::merge_sorted_by(streams, |a, b| a.cmp(b))
   }
-}
-impl Stream<i32>  {
   
-  /// Creates a linear sequence of i32 values from the
-  /// given start and end range.
+  /// Will merge multiple sorted streams into a single sorted stream,
+  /// as merge_sorted, ordering elements with the given comparator.
   /// # Example
   ///
   /// ```
-  /// use smoke::async::Stream;
+  /// use smoke::async::{Stream, ToStream};
   ///
-  /// let numbers = Stream::range(0, 100);
-  /// for n in numbers.read() {
+  /// let a = vec![7, 4, 1].into_iter().to_stream();
+  /// let b = vec![8, 3, 2].into_iter().to_stream();
+  /// let c = Stream::merge_sorted_by(vec![a, b], |a, b| b.cmp(a));
+  /// assert_eq!(c.read().iter().collect::<Vec<_>>(), vec![8, 7, 4, 3, 2, 1]);
+  /// ```
+  pub fn merge_sorted_by<F>(streams: Vec<Stream<T>>, compare: F) -> Stream<T>
+      where F: Fn(&T, &T) -> Ordering + Send + 'static {
+      Stream::output(move |sender| {
+        let receivers = streams.into_iter()
+                               .map(|stream| stream.read())
+                               .collect::<Vec<_>>();
+        let mut heads = Vec::with_capacity(receivers.len());
+        for receiver in &receivers {
+          let head = receiver.recv().ok();
+          if head.is_none() {
+            if let Some(error) = receiver.error() {
+              return sender.fail(error);
+            }
+          }
+          heads.push(head);
+        }
+        loop {
+          let mut least: Option<usize> = None;
+          for (index, head) in heads.iter().enumerate() {
+            if let Some(ref value) = *head {
+              least = match least {
+                Some(current) if compare(heads[current].as_ref().unwrap(), value) != Ordering::Greater => Some(current),
+                _ => Some(index)
+              };
+            }
+          }
+          match least {
+            None        => break,
+            Some(index) => {
+              let value = mem::replace(&mut heads[index], receivers[index].recv().ok());
+              try!( sender.send(value.unwrap()) );
+              if heads[index].is_none() {
+                if let Some(error) = receivers[index].error() {
+                  return sender.fail(error);
+                }
+              }
+            }
+          }
+        } Ok(())
+      })
+  }
+  
+  /// Will join this stream with another by key, emitting pairs of 
+  /// elements whose keys match. Each element is paired with the earliest
+  /// element of the other stream with the same key received within the
+  /// given window, or is held for the window awaiting a match. Elements
+  /// not matched within the window are discarded. Both streams are read
+  /// concurrently, and the joined stream fails if either stream fails.
+  /// # Example
+  ///
+  /// ```
+  /// use std::time::Duration;
+  /// use smoke::async::ToStream;
+  ///
+  /// let requests  = vec![(1, "get"), (2, "put")].into_iter().to_stream();
+  /// let responses = vec![(2, 201), (1, 200)].into_iter().to_stream();
+  /// let joined    = requests.join_by_key(responses, |r| r.0, |r| r.0, Duration::from_secs(1));
+  /// let mut pairs = joined.read().iter().map(|(req, res)| (req.1, res.1)).collect::<Vec<_>>();
+  /// pairs.sort();
+  /// assert_eq!(pairs, vec![("get", 200), ("put", 201)]);
+  /// ```
+  pub fn join_by_key<U, K, F, G>(self, other: Stream<U>, key: F, other_key: G, window: Duration) -> Stream<(T, U)>
+     where U: Send + 'static,
+           K: Eq + Hash + Send + 'static,
+           F: Fn(&T) -> K + Send + 'static,
+           G: Fn(&U) -> K + Send + 'static {
+      Stream::output(move |sender| {
+        let (tx, rx) = sync_channel(1);
+        let left     = tx.clone();
+        spawn_stage(None, move || {
+          let receiver = self.read();
+          for n in receiver.iter() {
+            if left.send(Ok(Side::Left(n))).is_err() { return; }
+          }
+          if let Some(error) = receiver.error() {
+            let _ = left.send(Err(error));
+          }
+        });
+        let right = tx;
+        spawn_stage(None, move || {
+          let receiver = other.read();
+          for n in receiver.iter() {
+            if right.send(Ok(Side::Right(n))).is_err() { return; }
+          }
+          if let Some(error) = receiver.error() {
+            let _ = right.send(Err(error));
+          }
+        });
+        let mut lefts  = HashMap::new();
+        let mut rights = HashMap::new();
+        for next in rx {
+          let now = Instant::now();
+          expire(&mut lefts,  now, window);
+          expire(&mut rights, now, window);
+          match next {
+            Err(error) => return sender.fail(error),
+            Ok(Side::Left(a)) => {
+              let k = key(&a);
+              match rights.get_mut(&k).and_then(|pending: &mut VecDeque<(Instant, U)>| pending.pop_front()) {
+                Some((_, b)) => try!( sender.send((a, b)) ),
+                None         => lefts.entry(k).or_insert_with(VecDeque::new).push_back((now, a))
+              }
+            },
+            Ok(Side::Right(b)) => {
+              let k = other_key(&b);
+              match lefts.get_mut(&k).and_then(|pending: &mut VecDeque<(Instant, T)>| pending.pop_front()) {
+                Some((_, a)) => try!( sender.send((a, b)) ),
+                None         => rights.entry(k).or_insert_with(VecDeque::new).push_back((now, b))
+              }
+            }
+          }
+        } Ok(())
+      })
+  }
+  
+  /// Will split this stream into the given number of branches, with
+  /// each element of this stream emitted on every branch. This stream 
+  /// is read once the first branch is read. As streams are bounded, 
+  /// branches are read in lockstep, so all branches should be read 
+  /// concurrently. Branches that are dropped no longer receive elements.
+  /// # Example
+  ///
+  /// ```
+  /// use std::thread;
+  /// use smoke::async::Stream;
+  ///
+  /// let mut branches = Stream::range(0, 4).tee(2);
+  /// let a = branches.remove(0);
+  /// let b = branches.remove(0);
+  /// let a = thread::spawn(move || a.fold(0, |p, c| p + c).wait().unwrap());
+  /// let b = thread::spawn(move || b.read().iter().count());
+  /// assert_eq!(a.join().unwrap(), 6);
+  /// assert_eq!(b.join().unwrap(), 4);
+  /// ```
+  pub fn tee(self, count: usize) -> Vec<Stream<T>> where T: Clone {
+      let (senders, receivers): (Vec<_>, Vec<_>) = (0..count).map(|_| stream_channel(1)).unzip();
+      let source = Arc::new(Mutex::new(Some((self, senders))));
+      receivers.into_iter().map(|receiver| {
+        let source = source.clone();
+        Stream::output(move |sender| {
+          // the first branch read starts reading the source.
+          let started = source.lock().unwrap().take();
+          if let Some((stream, senders)) = started {
+            scheduling::spawn(move || {
+              let receiver = stream.read();
+              for n in receiver.iter() {
+                let delivered = senders.iter()
+                                       .filter(|sender| sender.send(n.clone()).is_ok())
+                                       .count();
+                if delivered == 0 { break; }
+              }
+              if let Some(error) = receiver.error() {
+                for sender in senders {
+                  let _ = sender.fail(error.clone());
+                }
+              }
+            });
+          }
+          forward(receiver, &sender)
+        })
+      }).collect()
+  }
+  
+  /// Will filter elements from the source stream.
+  /// # Example
+  ///
+  /// ```
+  /// use smoke::async::Stream;
+  ///
+  /// let numbers = Stream::range(0, 100);
+  /// let evens   = numbers.filter(|n| n % 2 == 0);
+  /// for n in evens.read() {
+  ///     // only even numbers
+  /// }
+  /// ```
+  pub fn filter<F>(self, func:F) -> Stream<T> 
+      where F: Fn(&T) -> bool + Send + 'static {
+      Stream { body: Body::Fused(Box::new(Filter { stream: self, func: func })), runner: None }
+  }
+  
+  /// Will map the source stream into a new stream.
+  /// # Example
+  ///
+  /// ```
+  /// use smoke::async::Stream;
+  ///
+  /// let numbers = Stream::range(0, 100);
+  /// let strings = numbers.map(|n| format!("number {}", n));
+  /// for n in strings.read() {
+  ///     // strings
+  /// }
+  /// ```    
+  pub fn map<F, U>(self, func:F) -> Stream<U>
+     where U: Send + 'static,
+           F: Fn(T) -> U + Send + 'static {
+      Stream { body: Body::Fused(Box::new(Map { stream: self, func: func })), runner: None }
+  }
+  
+  /// Will emit elements of the source stream, gathering the number of
+  /// elements and the time spent waiting on the source stream and the
+  /// reader under the given name. Metrics are obtained with 
+  /// smoke::metrics::stream().
+  /// # Example
+  ///
+  /// ```
+  /// use smoke::async::Stream;
+  ///
+  /// let numbers = Stream::range(0, 4).metered("range").map(|n| n * 2).metered("map");
+  /// for _ in numbers.read() {}
+  /// assert_eq!(smoke::metrics::stream("map").unwrap().elements, 4);
+  /// ```
+  pub fn metered(self, name: &str) -> Stream<T> {
+      self.metered_by(name, |_| 0)
+  }
+  
+  /// Will emit elements of the source stream, gathering metrics as 
+  /// metered, with the size in bytes of each element given by the 
+  /// function.
+  /// # Example
+  ///
+  /// ```
+  /// use smoke::io::Read;
+  ///
+  /// let bytes = std::io::Cursor::new(vec![0; 64]).to_stream(16);
+  /// for _ in bytes.metered_by("bytes", |bytes| bytes.len()).read() {}
+  /// assert_eq!(smoke::metrics::stream("bytes").unwrap().bytes, 64);
+  /// ```
+  pub fn metered_by<F>(self, name: &str, size: F) -> Stream<T>
+      where F: Fn(&T) -> usize + Send + 'static {
+      let meter = metrics::meter(name);
+      Stream::output(move |sender| {
+        let receiver = self.read();
+        loop {
+          let started = Instant::now();
+          let n = match receiver.recv() {
+            Ok(n)  => n,
+            Err(_) => return end(&receiver, &sender)
+          };
+          meter.recv_blocked(started.elapsed());
+          meter.element(size(&n));
+          let started = Instant::now();
+          try!( sender.send(n) );
+          meter.send_blocked(started.elapsed());
+        }
+      })
+  }
+  
+  /// Will map the source stream into a new stream, applying the function
+  /// to up to the given number of elements in parallel on an internal 
+  /// threadpool. Elements are emitted in the order of the source stream.
+  /// If the function panics, the stream fails with the panic.
+  /// # Example
+  ///
+  /// ```
+  /// use smoke::async::Stream;
+  ///
+  /// let squares = Stream::range(0, 8).map_concurrent(4, |n| n * n);
+  /// assert_eq!(squares.read().iter().collect::<Vec<_>>(), vec![0, 1, 4, 9, 16, 25, 36, 49]);
+  /// ```
+  pub fn map_concurrent<F, U>(self, threads: usize, func: F) -> Stream<U>
+     where U: Send + 'static,
+           F: Fn(T) -> U + Send + Sync + 'static {
+      let threads = threads.max(1);
+      Stream::output(move |sender| {
+        let scheduler   = PooledScheduler::acquire(threads);
+        let func        = Arc::new(func);
+        let mut pending = VecDeque::new();
+        let receiver    = self.read();
+        for n in receiver.iter() {
+          let func = func.clone();
+          pending.push_back(scheduler.run(Task::new(move |sender| sender.send(func(n)))));
+          if pending.len() == threads {
+            match pending.pop_front().unwrap().wait() {
+              Ok(value)  => try!( sender.send(value) ),
+              Err(error) => return sender.fail(error)
+            }
+          }
+        }
+        for handle in pending {
+          match handle.wait() {
+            Ok(value)  => try!( sender.send(value) ),
+            Err(error) => return sender.fail(error)
+          }
+        }
+        end(&receiver, &sender)
+      })
+  }
+  
+  /// Will call the function with a reference to each element of the
+  /// source stream, emitting the elements unchanged. Useful for logging
+  /// and metrics within a pipeline.
+  /// # Example
+  ///
+  /// ```
+  /// use smoke::async::Stream;
+  ///
+  /// let numbers = Stream::range(0, 4).inspect(|n| println!("received {}", n));
+  /// assert_eq!(numbers.read().iter().collect::<Vec<_>>(), vec![0, 1, 2, 3]);
+  /// ```
+  pub fn inspect<F>(self, func: F) -> Stream<T>
+      where F: Fn(&T) + Send + 'static {
+      Stream { body: Body::Fused(Box::new(Inspect { stream: self, func: func })), runner: None }
+  }
+  
+  /// Will emit at most the first n elements of the source stream. Once
+  /// n elements have been emitted, the source stream is dropped, ending
+  /// its producer on its next send.
+  /// # Example
+  ///
+  /// ```
+  /// use smoke::async::Stream;
+  ///
+  /// let numbers = Stream::range(0, 100).take(3);
+  /// assert_eq!(numbers.read().iter().collect::<Vec<_>>(), vec![0, 1, 2]);
+  /// ```
+  pub fn take(self, count: usize) -> Stream<T> {
+      Stream::output(move |sender| {
+        if count > 0 {
+          let receiver = self.read();
+          for (index, n) in receiver.iter().enumerate() {
+            try!( sender.send(n) );
+            // the source is not read once taken, so is not failed.
+            if index + 1 == count { return Ok(()); }
+          }
+          return end(&receiver, &sender);
+        } Ok(())
+      })
+  }
+  
+  /// Will skip the first n elements of the source stream, emitting
+  /// the remaining elements.
+  /// # Example
+  ///
+  /// ```
+  /// use smoke::async::Stream;
+  ///
+  /// let numbers = Stream::range(0, 5).skip(3);
+  /// assert_eq!(numbers.read().iter().collect::<Vec<_>>(), vec![3, 4]);
+  /// ```
+  pub fn skip(self, count: usize) -> Stream<T> {
+      Stream::output(move |sender| {
+        let receiver = self.read();
+        for n in receiver.iter().skip(count) {
+          try!( sender.send(n) );
+        }
+        end(&receiver, &sender)
+      })
+  }
+  
+  /// Will emit the last n elements of the source stream once the 
+  /// source stream ends. Up to n elements are buffered.
+  /// # Example
+  ///
+  /// ```
+  /// use smoke::async::Stream;
+  ///
+  /// let numbers = Stream::range(0, 100).take_last(3);
+  /// assert_eq!(numbers.read().iter().collect::<Vec<_>>(), vec![97, 98, 99]);
+  /// ```
+  pub fn take_last(self, count: usize) -> Stream<T> {
+      Stream::output(move |sender| {
+        let mut buffer = VecDeque::with_capacity(count);
+        if count > 0 {
+          let receiver = self.read();
+          for n in receiver.iter() {
+            if buffer.len() == count {
+              buffer.pop_front();
+            }
+            buffer.push_back(n);
+          }
+          if let Some(error) = receiver.error() {
+            return sender.fail(error);
+          }
+        }
+        for n in buffer {
+          try!( sender.send(n) );
+        } Ok(())
+      })
+  }
+  
+  /// Will emit all but the last n elements of the source stream. 
+  /// Elements are emitted once n elements have been received after
+  /// them, so up to n elements are buffered.
+  /// # Example
+  ///
+  /// ```
+  /// use smoke::async::Stream;
+  ///
+  /// let numbers = Stream::range(0, 5).skip_last(2);
+  /// assert_eq!(numbers.read().iter().collect::<Vec<_>>(), vec![0, 1, 2]);
+  /// ```
+  pub fn skip_last(self, count: usize) -> Stream<T> {
+      Stream::output(move |sender| {
+        let mut buffer = VecDeque::with_capacity(count + 1);
+        let receiver   = self.read();
+        for n in receiver.iter() {
+          buffer.push_back(n);
+          if buffer.len() > count {
+            try!( sender.send(buffer.pop_front().unwrap()) );
+          }
+        }
+        end(&receiver, &sender)
+      })
+  }
+  
+  /// Will emit elements of the source stream while the predicate
+  /// returns true. Once the predicate returns false, the source stream
+  /// is dropped, ending its producer on its next send.
+  /// # Example
+  ///
+  /// ```
+  /// use smoke::async::Stream;
+  ///
+  /// let numbers = Stream::range(0, 100).take_while(|n| *n < 3);
+  /// assert_eq!(numbers.read().iter().collect::<Vec<_>>(), vec![0, 1, 2]);
+  /// ```
+  pub fn take_while<F>(self, func: F) -> Stream<T>
+      where F: Fn(&T) -> bool + Send + 'static {
+      Stream::output(move |sender| {
+        let receiver = self.read();
+        for n in receiver.iter() {
+          // the source is not read once the predicate fails, so is not failed.
+          if !func(&n) { return Ok(()); }
+          try!( sender.send(n) );
+        }
+        end(&receiver, &sender)
+      })
+  }
+  
+  /// Will skip elements of the source stream while the predicate
+  /// returns true, emitting the first element for which it returns
+  /// false and all elements after.
+  /// # Example
+  ///
+  /// ```
+  /// use smoke::async::Stream;
+  ///
+  /// let numbers = Stream::range(0, 5).skip_while(|n| *n < 3);
+  /// assert_eq!(numbers.read().iter().collect::<Vec<_>>(), vec![3, 4]);
+  /// ```
+  pub fn skip_while<F>(self, func: F) -> Stream<T>
+      where F: Fn(&T) -> bool + Send + 'static {
+      Stream::output(move |sender| {
+        let receiver = self.read();
+        for n in receiver.iter().skip_while(|n| func(n)) {
+          try!( sender.send(n) );
+        }
+        end(&receiver, &sender)
+      })
+  }
+  
+  /// Will pair elements of this stream with elements of the other
+  /// stream in lockstep, ending when either stream ends.
+  /// # Example
+  ///
+  /// ```
+  /// use smoke::async::Stream;
+  ///
+  /// let numbers = Stream::range(0, 3);
+  /// let words   = Stream::range(0, 100).map(|n| format!("{}", n * 10));
+  /// let pairs   = numbers.zip(words).read().iter().collect::<Vec<_>>();
+  /// assert_eq!(pairs, vec![(0, "0".to_string()), (1, "10".to_string()), (2, "20".to_string())]);
+  /// ```
+  pub fn zip<U>(self, other: Stream<U>) -> Stream<(T, U)> 
+      where U: Send + 'static {
+      Stream::output(move |sender| {
+        let (left, right) = (self.read(), other.read());
+        for n in left.iter().zip(right.iter()) {
+          try!( sender.send(n) );
+        }
+        try!( end(&left, &sender) );
+        end(&right, &sender)
+      })
+  }
+  
+  /// Will buffer elements of the source stream, emitting the elements
+  /// received within each window of the given duration. Windows in
+  /// which no elements were received are not emitted. When the source
+  /// stream ends, any buffered elements are emitted.
+  /// # Example
+  ///
+  /// ```
+  /// use std::time::Duration;
+  /// use smoke::async::Stream;
+  ///
+  /// let batches = Stream::range(0, 10).buffer_time(Duration::from_millis(100));
+  /// for batch in batches.read() {
+  ///   // batches of numbers.
+  /// }
+  /// ```
+  pub fn buffer_time(self, window: Duration) -> Stream<Vec<T>> {
+      Stream::output(move |sender| {
+        let receiver = self.read();
+        let mut buffer = Vec::new();
+        let mut flush  = Instant::now() + window;
+        loop {
+          let now = Instant::now();
+          if now >= flush {
+            if buffer.len() > 0 {
+              try!( sender.send(mem::replace(&mut buffer, Vec::new())) );
+            }
+            flush = now + window;
+          }
+          match receiver.recv_timeout(flush - now) {
+            Ok(n)                               => buffer.push(n),
+            Err(RecvTimeoutError::Timeout)      => continue,
+            Err(RecvTimeoutError::Disconnected)  => break
+          }
+        }
+        if buffer.len() > 0 {
+          try!( sender.send(buffer) );
+        }
+        end(&receiver, &sender)
+      })
+  }
+  
+  /// Will collect elements of the source stream into batches, emitting 
+  /// a batch once it holds the given number of elements, or once the 
+  /// given delay has passed since its first element was received, 
+  /// whichever comes first. When the source stream ends, any remaining
+  /// elements are emitted as a final batch.
+  /// # Example
+  ///
+  /// ```
+  /// use std::time::Duration;
+  /// use smoke::async::Stream;
+  ///
+  /// let batches = Stream::range(0, 10).batch(4, Duration::from_millis(100));
+  /// assert_eq!(batches.read().iter().collect::<Vec<_>>(), vec![
+  ///   vec![0, 1, 2, 3], 
+  ///   vec![4, 5, 6, 7], 
+  ///   vec![8, 9]
+  /// ]);
+  /// ```
+  pub fn batch(self, size: usize, delay: Duration) -> Stream<Vec<T>> {
+      let size = size.max(1);
+      Stream::output(move |sender| {
+        let receiver   = self.read();
+        let mut buffer = Vec::new();
+        let mut flush  = None;
+        loop {
+          let next = match flush {
+            None        => receiver.recv().map_err(|_| RecvTimeoutError::Disconnected),
+            Some(flush) => {
+              let now = Instant::now();
+              if now >= flush {
+                Err(RecvTimeoutError::Timeout)
+              } else {
+                receiver.recv_timeout(flush - now)
+              }
+            }
+          };
+          match next {
+            Ok(n) => {
+              if buffer.is_empty() {
+                flush = Some(Instant::now() + delay);
+              }
+              buffer.push(n);
+              if buffer.len() < size { continue; }
+            },
+            Err(RecvTimeoutError::Timeout)      => {},
+            Err(RecvTimeoutError::Disconnected) => break
+          }
+          flush = None;
+          try!( sender.send(mem::replace(&mut buffer, Vec::new())) );
+        }
+        if buffer.len() > 0 {
+          try!( sender.send(buffer) );
+        }
+        end(&receiver, &sender)
+      })
+  }
+  
+  /// Will map elements of the source stream with a running state, 
+  /// emitting the value returned for each element. Unlike fold,
+  /// a value is emitted for every element.
+  /// # Example
+  ///
+  /// ```
+  /// use smoke::async::Stream;
+  ///
+  /// let totals = Stream::range(1, 5).scan(0, |total, n| {
+  ///   *total += n;
+  ///   *total
+  /// });
+  /// assert_eq!(totals.read().iter().collect::<Vec<_>>(), vec![1, 3, 6, 10]);
+  /// ```
+  pub fn scan<S, U, F>(self, init: S, mut func: F) -> Stream<U>
+     where S: Send + 'static,
+           U: Send + 'static,
+           F: FnMut(&mut S, T) -> U + Send + 'static {
+      Stream::output(move |sender| {
+        let mut state = init;
+        let receiver  = self.read();
+        for n in receiver.iter() {
+          try!( sender.send(func(&mut state, n)) );
+        }
+        end(&receiver, &sender)
+      })
+  }
+  
+  /// Will pair each element of the source stream with its index.
+  /// # Example
+  ///
+  /// ```
+  /// use smoke::async::Stream;
+  ///
+  /// let numbers = Stream::range(10, 13).enumerate();
+  /// assert_eq!(numbers.read().iter().collect::<Vec<_>>(), vec![(0, 10), (1, 11), (2, 12)]);
+  /// ```
+  pub fn enumerate(self) -> Stream<(usize, T)> {
+      Stream::output(move |sender| {
+        let receiver = self.read();
+        for n in receiver.iter().enumerate() {
+          try!( sender.send(n) );
+        }
+        end(&receiver, &sender)
+      })
+  }
+  
+  /// Will drop elements of the source stream equal to the element
+  /// before them, emitting only the first of each consecutive run.
+  /// # Example
+  ///
+  /// ```
+  /// use smoke::async::ToStream;
+  ///
+  /// let numbers = vec![1, 1, 2, 2, 1].into_iter().to_stream().dedup();
+  /// assert_eq!(numbers.read().iter().collect::<Vec<_>>(), vec![1, 2, 1]);
+  /// ```
+  pub fn dedup(self) -> Stream<T> where T: PartialEq + Clone {
+      Stream::output(move |sender| {
+        let mut last = None;
+        let receiver = self.read();
+        for n in receiver.iter() {
+          if last.as_ref() != Some(&n) {
+            last = Some(n.clone());
+            try!( sender.send(n) );
+          }
+        }
+        end(&receiver, &sender)
+      })
+  }
+  
+  /// Will drop elements of the source stream equal to any element 
+  /// before them. Emitted elements are retained for the lifetime of 
+  /// the stream.
+  /// # Example
+  ///
+  /// ```
+  /// use smoke::async::ToStream;
+  ///
+  /// let numbers = vec![1, 2, 1, 3, 2].into_iter().to_stream().distinct();
+  /// assert_eq!(numbers.read().iter().collect::<Vec<_>>(), vec![1, 2, 3]);
+  /// ```
+  pub fn distinct(self) -> Stream<T> where T: Eq + Hash + Clone {
+      Stream::output(move |sender| {
+        let mut seen = HashSet::new();
+        let receiver = self.read();
+        for n in receiver.iter() {
+          if seen.insert(n.clone()) {
+            try!( sender.send(n) );
+          }
+        }
+        end(&receiver, &sender)
+      })
+  }
+  
+  /// Will route elements of the source stream into child streams by 
+  /// the key given for each element, emitting each child stream with
+  /// its key when its first element is received. Child streams end 
+  /// when the source stream ends, and fail if the source stream fails.
+  /// Elements are buffered in each child stream until read, and elements
+  /// for child streams that have been dropped are discarded. As child 
+  /// streams end only when the source stream ends, child streams should 
+  /// be read concurrently, or once all child streams have been received.
+  /// # Example
+  ///
+  /// ```
+  /// use smoke::async::Stream;
+  ///
+  /// let groups = Stream::range(0, 10).group_by(|n| n % 2 == 0);
+  /// let groups = groups.read().iter().collect::<Vec<_>>();
+  /// for (even, numbers) in groups {
+  ///   let numbers = numbers.read().iter().collect::<Vec<_>>();
+  ///   if even {
+  ///     assert_eq!(numbers, vec![0, 2, 4, 6, 8]);
+  ///   } else {
+  ///     assert_eq!(numbers, vec![1, 3, 5, 7, 9]);
+  ///   }
+  /// }
+  /// ```
+  pub fn group_by<K, F>(self, func: F) -> Stream<(K, Stream<T>)>
+     where K: Eq + Hash + Clone + Send + 'static,
+           F: Fn(&T) -> K + Send + 'static {
+      Stream::output(move |sender| {
+        let mut groups   = HashMap::new();
+        let receiver = self.read();
+        for n in receiver.iter() {
+          let key = func(&n);
+          if !groups.contains_key(&key) {
+            let (tx, rx) = channel();
+            let group = Stream::output(move |sender| {
+              for n in rx {
+                match n {
+                  Ok(n)      => try!( sender.send(n) ),
+                  Err(error) => return sender.fail(error)
+                }
+              } Ok(())
+            });
+            try!( sender.send((key.clone(), group)) );
+            groups.insert(key.clone(), tx);
+          }
+          let _ = groups[&key].send(Ok(n));
+        }
+        match receiver.error() {
+          None        => Ok(()),
+          Some(error) => {
+            for group in groups.values() {
+              let _ = group.send(Err(error.clone()));
+            }
+            sender.fail(error)
+          }
+        }
+      })
+  }
+  
+  /// Will emit elements of the source stream, failing the stream with 
+  /// a timeout error if no element is received within the given duration
+  /// of the stream starting or of the previous element. 
+  /// # Example
+  ///
+  /// ```
+  /// use std::time::Duration;
+  /// use smoke::async::Stream;
+  ///
+  /// let stalled = Stream::output(|sender| {
+  ///   try!(sender.send(1));
+  ///   std::thread::sleep(Duration::from_millis(100));
+  ///   sender.send(2)
+  /// });
+  /// let receiver = stalled.timeout(Duration::from_millis(10)).read();
+  /// assert_eq!(receiver.iter().collect::<Vec<_>>(), vec![1]);
+  /// assert!(receiver.error().is_some());
+  /// ```
+  pub fn timeout(self, duration: Duration) -> Stream<T> {
+      Stream::output(move |sender| {
+        let receiver = self.read();
+        loop {
+          match receiver.recv_timeout(duration) {
+            Ok(n)                               => try!( sender.send(n) ),
+            Err(RecvTimeoutError::Timeout)      => {
+              log_debug!("stream: timed out waiting for element");
+              return sender.fail(Error::Timeout)
+            },
+            Err(RecvTimeoutError::Disconnected) => return end(&receiver, &sender)
+          }
+        }
+      })
+  }
+  
+  /// Will sample the source stream at the given interval, emitting
+  /// the most recent element received within each interval and 
+  /// discarding the rest. Intervals in which no elements were received
+  /// are not emitted. When the source stream ends, any element received
+  /// since the last sample is emitted.
+  /// # Example
+  ///
+  /// ```
+  /// use std::time::Duration;
+  /// use smoke::async::Stream;
+  ///
+  /// let samples = Stream::range(0, 1000).sample(Duration::from_millis(10));
+  /// for n in samples.read() {
+  ///   // the latest number every 10 milliseconds.
+  /// }
+  /// ```
+  pub fn sample(self, interval: Duration) -> Stream<T> {
+      Stream::output(move |sender| {
+        let receiver   = self.read();
+        let mut latest = None;
+        let mut tick   = Instant::now() + interval;
+        loop {
+          let now = Instant::now();
+          if now >= tick {
+            if let Some(n) = latest.take() {
+              try!( sender.send(n) );
+            }
+            tick = now + interval;
+          }
+          match receiver.recv_timeout(tick - now) {
+            Ok(n)                               => latest = Some(n),
+            Err(RecvTimeoutError::Timeout)      => continue,
+            Err(RecvTimeoutError::Disconnected) => break
+          }
+        }
+        if let Some(n) = latest {
+          try!( sender.send(n) );
+        }
+        end(&receiver, &sender)
+      })
+  }
+  
+  /// Will emit elements of the source stream with at least the given
+  /// interval between them. Elements are not dropped; as streams are
+  /// bounded, the source stream is held back to the throttled rate.
+  /// # Example
+  ///
+  /// ```
+  /// use std::time::Duration;
+  /// use smoke::async::Stream;
+  ///
+  /// let numbers = Stream::range(0, 4).throttle(Duration::from_millis(10));
+  /// for n in numbers.read() {
+  ///   // 0, 1, 2, 3 at most every 10 milliseconds.
+  /// }
+  /// ```
+  pub fn throttle(self, interval: Duration) -> Stream<T> {
+      Stream::output(move |sender| {
+        let mut last: Option<Instant> = None;
+        let receiver = self.read();
+        for n in receiver.iter() {
+          if let Some(last) = last {
+            let elapsed = last.elapsed();
+            if elapsed < interval {
+              thread::sleep(interval - elapsed);
+            }
+          }
+          try!( sender.send(n) );
+          last = Some(Instant::now());
+        }
+        end(&receiver, &sender)
+      })
+  }
+  
+  /// Inserts a buffer of the given bound between the source stream and 
+  /// its reader. The source is read as its own stage, emitting up to 
+  /// bound elements ahead of the reader before it is held back. This 
+  /// decouples a slow reader from a bursty source.
+  /// # Example
+  ///
+  /// ```
+  /// use std::thread;
+  /// use std::time::Duration;
+  /// use smoke::async::Stream;
+  ///
+  /// let numbers = Stream::range(0, 64).stage(64);
+  /// for n in numbers.read() {
+  ///   // the source runs up to 64 elements ahead.
+  ///   thread::sleep(Duration::from_millis(1));
+  /// }
+  /// ```
+  pub fn stage(self, bound: usize) -> Stream<T> {
+      Stream::output(move |sender| {
+        forward(self.read_bounded(bound), &sender)
+      })
+  }
+  
+  /// Controls what happens when the reader of this stream is slower
+  /// than the source. With any strategy other than Block, the source 
+  /// is read on its own thread and never held back by the reader, with 
+  /// elements shed as per the strategy.
+  /// # Example
+  ///
+  /// ```
+  /// use std::thread;
+  /// use std::time::Duration;
+  /// use smoke::async::{Stream, Backpressure};
+  ///
+  /// let numbers = Stream::range(0, 1000).on_backpressure(Backpressure::Latest);
+  /// for n in numbers.read() {
+  ///   // slow reader receives the latest numbers only.
+  ///   thread::sleep(Duration::from_millis(1));
+  /// }
+  /// ```
+  pub fn on_backpressure(self, strategy: Backpressure) -> Stream<T> {
+      let capacity = match strategy {
+        Backpressure::Block                => return self,
+        Backpressure::Latest               => 1,
+        Backpressure::DropOldest(capacity) => capacity.max(1),
+        Backpressure::DropNewest(capacity) => capacity.max(1)
+      };
+      Stream::output(move |sender| {
+        let shared = Arc::new((Mutex::new(Buffer { 
+          queue:  VecDeque::new(), 
+          error:  None,
+          ended:  false, 
+          closed: false 
+        }), Condvar::new()));
+        let producer = shared.clone();
+        scheduling::spawn(move || {
+          let (ref lock, ref condvar) = *producer;
+          let receiver = self.read();
+          for n in receiver.iter() {
+            let mut buffer = lock.lock().unwrap();
+            if buffer.closed { break; }
+            if buffer.queue.len() >= capacity {
+              match strategy {
+                Backpressure::DropNewest(_) => continue,
+                _ => { buffer.queue.pop_front(); }
+              }
+            }
+            buffer.queue.push_back(n);
+            condvar.notify_one();
+          }
+          let mut buffer = lock.lock().unwrap();
+          buffer.error = receiver.error();
+          buffer.ended = true;
+          condvar.notify_one();
+        });
+        let (ref lock, ref condvar) = *shared;
+        loop {
+          let next = {
+            let mut buffer = lock.lock().unwrap();
+            while buffer.queue.is_empty() && !buffer.ended {
+              buffer = condvar.wait(buffer).unwrap();
+            }
+            buffer.queue.pop_front()
+          };
+          match next {
+            None    => break,
+            Some(n) => if let Err(error) = sender.send(n) {
+              lock.lock().unwrap().closed = true;
+              return Err(error);
+            }
+          }
+        }
+        let error = lock.lock().unwrap().error.take();
+        match error {
+          Some(error) => sender.fail(error),
+          None        => Ok(())
+        }
+      })
+  }
+  
+  /// Will emit each element of the source stream the given delay after
+  /// it is received, shifting the stream in time while preserving the 
+  /// spacing between elements. Elements received during the delay are
+  /// buffered.
+  /// # Example
+  ///
+  /// ```
+  /// use std::time::Duration;
+  /// use smoke::async::Stream;
+  ///
+  /// let numbers = Stream::range(0, 4).delay_elements(Duration::from_millis(10));
+  /// assert_eq!(numbers.read().iter().collect::<Vec<_>>(), vec![0, 1, 2, 3]);
+  /// ```
+  pub fn delay_elements(self, delay: Duration) -> Stream<T> {
+      self.delay_by(move |_| delay)
+  }
+  
+  /// Will emit each element of the source stream the delay given by the
+  /// function after it is received. Elements are emitted in order, so an
+  /// element is not emitted before the elements received before it.
+  /// Elements received during the delay are buffered.
+  /// # Example
+  ///
+  /// ```
+  /// use std::time::Duration;
+  /// use smoke::async::Stream;
+  ///
+  /// let numbers = Stream::range(0, 4).delay_by(|n| Duration::from_millis(*n as u64 * 10));
+  /// assert_eq!(numbers.read().iter().collect::<Vec<_>>(), vec![0, 1, 2, 3]);
+  /// ```
+  pub fn delay_by<F>(self, func: F) -> Stream<T>
+      where F: Fn(&T) -> Duration + Send + 'static {
+      Stream::output(move |sender| {
+        let (tx, rx) = channel();
+        spawn_stage(None, move || {
+          let receiver = self.read();
+          for n in receiver.iter() {
+            let due = Instant::now() + func(&n);
+            if tx.send(Ok((due, n))).is_err() { return; }
+          }
+          if let Some(error) = receiver.error() {
+            let _ = tx.send(Err(error));
+          }
+        });
+        for next in rx {
+          match next {
+            Err(error)    => return sender.fail(error),
+            Ok((due, n)) => {
+              let now = Instant::now();
+              if due > now {
+                thread::sleep(due - now);
+              }
+              try!( sender.send(n) );
+            }
+          }
+        } Ok(())
+      })
+  }
+  
+  /// Will limit the rate of the source stream to the given number of 
+  /// elements per duration, using a token bucket. Bursts of up to the
+  /// given number of elements are emitted immediately, after which 
+  /// elements are held back to the average rate. Unlike throttle, which
+  /// spaces every element, elements are only delayed once the bucket 
+  /// is empty.
+  /// # Example
+  ///
+  /// ```
+  /// use std::time::Duration;
+  /// use smoke::async::Stream;
+  ///
+  /// let requests = Stream::range(0, 20).rate_limit(10, Duration::from_millis(100));
+  /// for n in requests.read() {
+  ///   // 0 to 9 immediately, then 10 every 100 milliseconds.
+  /// }
+  /// ```
+  pub fn rate_limit(self, count: usize, per: Duration) -> Stream<T> {
+      let capacity = count.max(1) as f64;
+      let interval = per / count.max(1) as u32;
+      Stream::output(move |sender| {
+        let mut tokens = capacity;
+        let mut last   = Instant::now();
+        let receiver   = self.read();
+        for n in receiver.iter() {
+          let now = Instant::now();
+          tokens  = (tokens + duration_ratio(now - last, interval)).min(capacity);
+          last    = now;
+          if tokens < 1.0 {
+            let wait = interval.mul_f64(1.0 - tokens);
+            thread::sleep(wait);
+            last   = Instant::now();
+            tokens = 1.0;
+          }
+          tokens -= 1.0;
+          try!( sender.send(n) );
+        }
+        end(&receiver, &sender)
+      })
+  }
+  
+  /// Converts this stream into a hot stream shared among subscribers.
+  /// The source stream is connected when the first subscriber is read,
+  /// and each element is emitted to all subscribers attached at the 
+  /// time. Subscribers attaching late miss earlier elements. The source 
+  /// is ended once no subscribers remain.
+  /// # Example
+  ///
+  /// ```
+  /// use std::thread;
+  /// use smoke::async::Stream;
+  ///
+  /// let publisher = Stream::range(0, 4).publish();
+  /// let a = publisher.subscribe();
+  /// let b = publisher.subscribe();
+  /// let a = thread::spawn(move || a.read().iter().count());
+  /// let b = thread::spawn(move || b.read().iter().count());
+  /// assert_eq!(a.join().unwrap(), 4);
+  /// assert_eq!(b.join().unwrap(), 4);
+  /// ```
+  pub fn publish(self) -> Publisher<T> where T: Clone {
+    Publisher {
+      inner: Arc::new(Mutex::new(Published {
+        source:      Some(self),
+        subscribers: Vec::new(),
+        next:        0,
+        completed:   false
+      }))
+    }
+  }
+  
+  /// Will emit elements of the source stream, recording each element
+  /// with the time elapsed since the stream was read. The recording 
+  /// can be replayed once the stream has been read.
+  /// # Example
+  ///
+  /// ```
+  /// use smoke::async::Stream;
+  ///
+  /// let (numbers, recording) = Stream::range(0, 4).record();
+  /// assert_eq!(numbers.read().iter().count(), 4);
+  /// let replayed = recording.replay();
+  /// assert_eq!(replayed.read().iter().collect::<Vec<_>>(), vec![0, 1, 2, 3]);
+  /// ```
+  pub fn record(self) -> (Stream<T>, Recording<T>) where T: Clone {
+    let recording = Recording {
+      inner: Arc::new(Mutex::new(Recorded {
+        elements:  Vec::new(),
+        error:     None,
+        completed: false
+      }))
+    };
+    let recorder = recording.clone();
+    (Stream::output(move |sender| {
+      let receiver = self.read();
+      let started  = Instant::now();
+      for n in receiver.iter() {
+        recorder.inner.lock().unwrap().elements.push((started.elapsed(), n.clone()));
+        try!( sender.send(n) );
+      }
+      let error = receiver.error();
+      {
+        let mut inner = recorder.inner.lock().unwrap();
+        inner.error     = error.clone();
+        inner.completed = true;
+      }
+      match error {
+        Some(error) => sender.fail(error),
+        None        => Ok(())
+      }
+    }), recording)
+  }
+  
+  /// Reduces elements in the source stream into an accumulator 
+  /// and returns a task to obtain the result. The task fails if 
+  /// the stream fails.
+  /// # Example
+  ///
+  /// ```
+  /// use smoke::async::Stream;
+  ///
+  /// let numbers  = Stream::range(0, 100);
+  /// let task     = numbers.fold(0, |p, c| p + c);
+  /// let result   = task.wait().unwrap();
+  ///
+  /// let words    = Stream::once("hello".to_string());
+  /// let bytes    = words.fold(0, |p, c| p + c.len());
+  /// assert_eq!(bytes.wait().unwrap(), 5);
+  /// ```  
+  pub fn fold<A, F>(self, init: A, func:F) -> Task<A> 
+    where A: Send + 'static,
+          F: FnMut(A, T) -> A + Send + 'static {
+      Task::new(move |sender| {
+        let receiver = self.read();
+        let value    = receiver.iter().fold(init, func);
+        resolve(&receiver, sender, value)
+      })
+  }
+  
+  /// Reduces elements in the source stream, using the first element
+  /// as the initial accumulator, and returns a task to obtain the 
+  /// result, or None if the stream is empty. The task fails if the
+  /// stream fails.
+  /// # Example
+  ///
+  /// ```
+  /// use smoke::async::Stream;
+  ///
+  /// let max = Stream::range(0, 100).reduce(|p, c| if c > p { c } else { p });
+  /// assert_eq!(max.wait().unwrap(), Some(99));
+  /// ```
+  pub fn reduce<F>(self, mut func: F) -> Task<Option<T>>
+    where F: FnMut(T, T) -> T + Send + 'static {
+      Task::new(move |sender| {
+        let receiver = self.read();
+        let value    = match receiver.recv() {
+          Ok(first) => Some(receiver.iter().fold(first, |p, c| func(p, c))),
+          Err(_)    => None
+        };
+        resolve(&receiver, sender, value)
+      })
+  }
+  
+  /// Returns a task resolving the maximum element of the source stream
+  /// with respect to the given comparator, or None if the stream is empty.
+  /// If several elements are equally maximum, the last is returned.
+  /// # Example
+  ///
+  /// ```
+  /// use smoke::async::Stream;
+  ///
+  /// let max = Stream::range(0, 10).max_by(|a, b| (a % 4).cmp(&(b % 4)));
+  /// assert_eq!(max.wait().unwrap(), Some(7));
+  /// ```
+  pub fn max_by<F>(self, func: F) -> Task<Option<T>>
+    where F: FnMut(&T, &T) -> Ordering + Send + 'static {
+      Task::new(move |sender| {
+        let receiver = self.read();
+        let value    = receiver.iter().max_by(func);
+        resolve(&receiver, sender, value)
+      })
+  }
+
+  /// Returns a task resolving the minimum element of the source stream
+  /// with respect to the given comparator, or None if the stream is empty.
+  /// If several elements are equally minimum, the first is returned.
+  /// # Example
+  ///
+  /// ```
+  /// use smoke::async::Stream;
+  ///
+  /// let min = Stream::range(1, 10).min_by(|a, b| (a % 4).cmp(&(b % 4)));
+  /// assert_eq!(min.wait().unwrap(), Some(4));
+  /// ```
+  pub fn min_by<F>(self, func: F) -> Task<Option<T>>
+    where F: FnMut(&T, &T) -> Ordering + Send + 'static {
+      Task::new(move |sender| {
+        let receiver = self.read();
+        let value    = receiver.iter().min_by(func);
+        resolve(&receiver, sender, value)
+      })
+  }
+
+  /// Returns a task resolving the element of the source stream that gives
+  /// the maximum key, or None if the stream is empty. If several elements
+  /// are equally maximum, the last is returned.
+  /// # Example
+  ///
+  /// ```
+  /// use smoke::async::ToStream;
+  ///
+  /// let words   = vec!["smoke", "on", "the", "water"];
+  /// let longest = words.into_iter().to_stream().max_by_key(|word| word.len());
+  /// assert_eq!(longest.wait().unwrap(), Some("water"));
+  /// ```
+  pub fn max_by_key<K, F>(self, func: F) -> Task<Option<T>>
+    where K: Ord, F: FnMut(&T) -> K + Send + 'static {
+      Task::new(move |sender| {
+        let receiver = self.read();
+        let value    = receiver.iter().max_by_key(func);
+        resolve(&receiver, sender, value)
+      })
+  }
+
+  /// Returns a task resolving the element of the source stream that gives
+  /// the minimum key, or None if the stream is empty. If several elements
+  /// are equally minimum, the first is returned.
+  /// # Example
+  ///
+  /// ```
+  /// use smoke::async::ToStream;
+  ///
+  /// let words    = vec!["smoke", "on", "the", "water"];
+  /// let shortest = words.into_iter().to_stream().min_by_key(|word| word.len());
+  /// assert_eq!(shortest.wait().unwrap(), Some("on"));
+  /// ```
+  pub fn min_by_key<K, F>(self, func: F) -> Task<Option<T>>
+    where K: Ord, F: FnMut(&T) -> K + Send + 'static {
+      Task::new(move |sender| {
+        let receiver = self.read();
+        let value    = receiver.iter().min_by_key(func);
+        resolve(&receiver, sender, value)
+      })
+  }
+
+  /// Returns a task resolving the first element of the source stream,
+  /// or None if the stream is empty. The source stream is ended once 
+  /// the first element is received. The task fails if the stream fails.
+  /// # Example
+  ///
+  /// ```
+  /// use smoke::async::Stream;
+  ///
+  /// assert_eq!(Stream::repeat(1).first().wait().unwrap(), Some(1));
+  /// ```
+  pub fn first(self) -> Task<Option<T>> {
+      Task::new(move |sender| {
+        let receiver = self.read();
+        match receiver.recv() {
+          Ok(value) => {
+            drop(receiver);
+            sender.send(Some(value))
+          },
+          Err(_) => resolve(&receiver, sender, None)
+        }
+      })
+  }
+  
+  /// Returns a task resolving the last element of the source stream,
+  /// or None if the stream is empty. The task fails if the stream fails.
+  /// # Example
+  ///
+  /// ```
+  /// use smoke::async::Stream;
+  ///
+  /// assert_eq!(Stream::range(0, 4).last().wait().unwrap(), Some(3));
+  /// ```
+  pub fn last(self) -> Task<Option<T>> {
+      Task::new(move |sender| {
+        let receiver = self.read();
+        let last     = receiver.iter().last();
+        resolve(&receiver, sender, last)
+      })
+  }
+  
+  /// Returns a task resolving true if the predicate returns true for
+  /// any element of the source stream. The source stream is ended as 
+  /// soon as a matching element is received. The task fails if the 
+  /// stream fails before a matching element is received.
+  /// # Example
+  ///
+  /// ```
+  /// use smoke::async::Stream;
+  ///
+  /// assert_eq!(Stream::range(0, 4).any(|n| *n == 2).wait().unwrap(), true);
+  /// ```
+  pub fn any<F>(self, func: F) -> Task<bool>
+      where F: Fn(&T) -> bool + Send + 'static {
+      Task::new(move |sender| {
+        let receiver = self.read();
+        if receiver.iter().any(|n| func(&n)) {
+          drop(receiver);
+          return sender.send(true);
+        }
+        resolve(&receiver, sender, false)
+      })
+  }
+  
+  /// Returns a task resolving true if the predicate returns true for
+  /// all elements of the source stream. The source stream is ended as 
+  /// soon as a non matching element is received. The task fails if 
+  /// the stream fails before a non matching element is received.
+  /// # Example
+  ///
+  /// ```
+  /// use smoke::async::Stream;
+  ///
+  /// assert_eq!(Stream::range(0, 4).all(|n| *n < 4).wait().unwrap(), true);
+  /// ```
+  pub fn all<F>(self, func: F) -> Task<bool>
+      where F: Fn(&T) -> bool + Send + 'static {
+      Task::new(move |sender| {
+        let receiver = self.read();
+        if !receiver.iter().all(|n| func(&n)) {
+          drop(receiver);
+          return sender.send(false);
+        }
+        resolve(&receiver, sender, true)
+      })
+  }
+  
+  /// Returns a task resolving the first element of the source stream 
+  /// for which the predicate returns true, or None if there is none.
+  /// The source stream is ended as soon as the element is received. The
+  /// task fails if the stream fails before the element is received.
+  /// # Example
+  ///
+  /// ```
+  /// use smoke::io::Read;
+  ///
+  /// let log   = std::io::Cursor::new("info\nerror: disk full\ninfo\n".as_bytes().to_vec());
+  /// let error = log.to_line_stream().find(|line| line.starts_with("error"));
+  /// assert_eq!(error.wait().unwrap(), Some("error: disk full\n".to_string()));
+  /// ```
+  pub fn find<F>(self, func: F) -> Task<Option<T>>
+      where F: Fn(&T) -> bool + Send + 'static {
+      Task::new(move |sender| {
+        let receiver = self.read();
+        match receiver.iter().find(|n| func(n)) {
+          Some(value) => {
+            drop(receiver);
+            sender.send(Some(value))
+          },
+          None => resolve(&receiver, sender, None)
+        }
+      })
+  }
+  
+  /// Returns a task resolving the number of elements in the source 
+  /// stream. The task fails if the stream fails.
+  /// # Example
+  ///
+  /// ```
+  /// use smoke::async::Stream;
+  ///
+  /// assert_eq!(Stream::range(0, 4).count().wait().unwrap(), 4);
+  /// ```
+  pub fn count(self) -> Task<usize> {
+      Task::new(move |sender| {
+        let receiver = self.read();
+        let count    = receiver.iter().count();
+        resolve(&receiver, sender, count)
+      })
+  }
+}
+impl<T> Stream<T> where T: Send + 'static, Range<T>: Iterator<Item = T> + Send + 'static {
+  
+  /// Creates a linear sequence of integer values from the
+  /// given start and end range.
+  /// # Example
+  ///
+  /// ```
+  /// use smoke::async::Stream;
+  ///
+  /// let numbers = Stream::range(0, 100);
+  /// for n in numbers.read() {
   ///     // only even numbers
   /// }
+  ///
+  /// let offsets = Stream::range(0u64, 4);
+  /// ``` 
+  pub fn range(start: T, end: T) -> Stream<T> {
+    Stream::from_range(start..end)
+  }
+  
+  /// Creates a linear sequence of integer values from the
+  /// given start and end range, stepping by the given step. A 
+  /// step of zero is treated as one.
+  /// # Example
+  ///
+  /// ```
+  /// use smoke::async::Stream;
+  ///
+  /// let numbers = Stream::range_step(0, 10, 3);
+  /// assert_eq!(numbers.read().iter().collect::<Vec<_>>(), vec![0, 3, 6, 9]);
+  /// ``` 
+  pub fn range_step(start: T, end: T, step: usize) -> Stream<T> {
+    let step = step.max(1);
+    Stream::output(move |sender| {
+      for n in (start..end).step_by(step) {
+        try!( sender.send(n) );
+      } Ok(())
+    })
+  }
+  
+  /// Creates a linear sequence of integer values from the given range.
+  /// # Example
+  ///
+  /// ```
+  /// use smoke::async::Stream;
+  ///
+  /// let numbers = Stream::from_range(10u8..13);
+  /// assert_eq!(numbers.read().iter().collect::<Vec<_>>(), vec![10, 11, 12]);
   /// ``` 
-  pub fn range(start: i32, end: i32) -> Stream<i32> {
-    Stream::output(move |sender| (start..end)
-                  .map(|n| sender.send(n))
-                  .last()
-                  .unwrap())
+  pub fn from_range(range: Range<T>) -> Stream<T> {
+    Stream::output(move |sender| {
+      for n in range {
+        try!( sender.send(n) );
+      } Ok(())
+    })
+  }
+}
+
+/// The source of an unzipped stream, taken when first read.
+type Unzipped<A, B> = Arc<Mutex<Option<(Stream<(A, B)>, StreamSender<A>, StreamSender<B>)>>>;
+
+/// Starts reading the source of an unzipped stream if not started.
+fn unzip_source<A, B>(source: &Unzipped<A, B>) where A: Send + 'static, B: Send + 'static {
+  let started = source.lock().unwrap().take();
+  if let Some((stream, left, right)) = started {
+    scheduling::spawn(move || {
+      let receiver       = stream.read();
+      let mut left_open  = true;
+      let mut right_open = true;
+      for (a, b) in receiver.iter() {
+        left_open  = left_open  && left.send(a).is_ok();
+        right_open = right_open && right.send(b).is_ok();
+        if !left_open && !right_open { break; }
+      }
+      if let Some(error) = receiver.error() {
+        let _ = left.fail(error.clone());
+        let _ = right.fail(error);
+      }
+    });
+  }
+}
+
+impl<A, B> Stream<(A, B)> where A: Send + 'static, B: Send + 'static {
+  
+  /// Splits a stream of pairs into a stream of the first elements and 
+  /// a stream of the second elements. This stream is read once either
+  /// stream is read. As streams are bounded, both streams are read in 
+  /// lockstep, so should be read concurrently. If either stream is 
+  /// dropped, the other continues to receive elements.
+  /// # Example
+  ///
+  /// ```
+  /// use std::thread;
+  /// use smoke::async::ToStream;
+  ///
+  /// let pairs = vec![("a", 1), ("b", 2)].into_iter().to_stream();
+  /// let (keys, values) = pairs.unzip();
+  /// let keys   = thread::spawn(move || keys.read().iter().collect::<Vec<_>>());
+  /// let values = thread::spawn(move || values.read().iter().collect::<Vec<_>>());
+  /// assert_eq!(keys.join().unwrap(), vec!["a", "b"]);
+  /// assert_eq!(values.join().unwrap(), vec![1, 2]);
+  /// ```
+  pub fn unzip(self) -> (Stream<A>, Stream<B>) {
+    let (left, left_receiver)   = stream_channel(1);
+    let (right, right_receiver) = stream_channel(1);
+    let source       = Arc::new(Mutex::new(Some((self, left, right))));
+    let left_source  = source.clone();
+    let right_source = source;
+    (Stream::output(move |sender| {
+      unzip_source(&left_source);
+      forward(left_receiver, &sender)
+    }),
+    Stream::output(move |sender| {
+      unzip_source(&right_source);
+      forward(right_receiver, &sender)
+    }))
+  }
+}
+
+impl<T, E> Stream<Result<T, E>> where T: Send + 'static, E: Send + 'static {
+
+  /// Will emit the Ok elements of the source stream, passing Err elements
+  /// to the given handler. Use this to report non-fatal errors, such as 
+  /// unparseable lines, while the stream continues. To collect errors into
+  /// a side stream, send them from the handler.
+  /// # Example
+  ///
+  /// ```
+  /// use std::sync::mpsc::channel;
+  /// use smoke::async::ToStream;
+  ///
+  /// let (sender, receiver) = channel();
+  /// let lines   = vec!["1", "two", "3"].into_iter().to_stream();
+  /// let numbers = lines.map(|line| line.parse::<i32>())
+  ///                    .on_error(move |error| { let _ = sender.send(error.to_string()); });
+  /// assert_eq!(numbers.read().iter().collect::<Vec<_>>(), vec![1, 3]);
+  /// assert_eq!(receiver.iter().count(), 1);
+  /// ```
+  pub fn on_error<F>(self, func: F) -> Stream<T> 
+    where F: Fn(E) + Send + 'static {
+      Stream::output(move |sender| {
+        let receiver = self.read();
+        for n in receiver.iter() {
+          match n {
+            Ok(value)  => try!( sender.send(value) ),
+            Err(error) => func(error)
+          }
+        }
+        end(&receiver, &sender)
+      })
+  }
+}
+
+impl<T> Stream<Stream<T>> where T: Send + 'static {
+
+  /// Will emit elements of the most recent inner stream emitted by the 
+  /// source stream. When the source emits a new inner stream, the previous
+  /// inner stream is dropped and no longer read. The stream ends once both
+  /// the source and the current inner stream have ended, and fails if 
+  /// either fails.
+  /// # Example
+  ///
+  /// ```
+  /// use smoke::async::Stream;
+  ///
+  /// let streams  = Stream::range(0, 3).map(|n| Stream::range(n * 10, n * 10 + 2));
+  /// let switched = streams.switch();
+  /// let numbers  = switched.read().iter().collect::<Vec<_>>();
+  /// assert_eq!(numbers.last(), Some(&21));
+  /// ```
+  pub fn switch(self) -> Stream<T> {
+    Stream::output(move |sender| {
+      let (tx, rx) = sync_channel(1);
+      let current  = Arc::new(AtomicUsize::new(0));
+      let outer    = tx.clone();
+      spawn_stage(None, move || {
+        let receiver = self.read();
+        for stream in receiver.iter() {
+          if outer.send(Switched::Inner(stream)).is_err() { return; }
+        }
+        let _ = outer.send(Switched::Ended(receiver.error()));
+      });
+      let mut generation  = 0;
+      let mut outer_ended = false;
+      let mut inner_ended = true;
+      while !(outer_ended && inner_ended) {
+        match rx.recv().unwrap() {
+          Switched::Inner(stream) => {
+            generation += 1;
+            inner_ended = false;
+            current.store(generation, atomic::Ordering::SeqCst);
+            let (inner, current, id) = (tx.clone(), current.clone(), generation);
+            spawn_stage(None, move || {
+              let receiver = stream.read();
+              for n in receiver.iter() {
+                if current.load(atomic::Ordering::SeqCst) != id { return; }
+                if inner.send(Switched::Next(id, n)).is_err() { return; }
+              }
+              let _ = inner.send(Switched::End(id, receiver.error()));
+            });
+          },
+          Switched::Next(id, n) => if id == generation {
+            try!( sender.send(n) );
+          },
+          Switched::End(id, error) => if id == generation {
+            if let Some(error) = error { return sender.fail(error); }
+            inner_ended = true;
+          },
+          Switched::Ended(error) => {
+            if let Some(error) = error { return sender.fail(error); }
+            outer_ended = true;
+          }
+        }
+      } Ok(())
+    })
+  }
+}
+
+/// The state of a stream control.
+struct Control {
+  paused:  bool,
+  stopped: bool
+}
+
+/// A handle to pause, resume or stop a stream. Created with 
+/// Stream::read_controlled().
+#[derive(Clone)]
+pub struct StreamControl {
+  inner: Arc<(Mutex<Control>, Condvar)>
+}
+impl StreamControl {
+  
+  /// Creates a new stream control.
+  fn new() -> StreamControl {
+    StreamControl { 
+      inner: Arc::new((Mutex::new(Control { paused: false, stopped: false }), Condvar::new())) 
+    }
+  }
+  
+  /// Pauses the stream. Elements already received are still emitted.
+  pub fn pause(&self) {
+    let (ref lock, _) = *self.inner;
+    lock.lock().unwrap().paused = true;
+  }
+  
+  /// Resumes the stream if paused.
+  pub fn resume(&self) {
+    let (ref lock, ref condvar) = *self.inner;
+    lock.lock().unwrap().paused = false;
+    condvar.notify_all();
+  }
+  
+  /// Stops the stream, ending it for its reader.
+  pub fn stop(&self) {
+    let (ref lock, ref condvar) = *self.inner;
+    lock.lock().unwrap().stopped = true;
+    condvar.notify_all();
+  }
+  
+  /// Returns true if the stream is paused.
+  pub fn is_paused(&self) -> bool {
+    let (ref lock, _) = *self.inner;
+    lock.lock().unwrap().paused
+  }
+  
+  /// Returns true if the stream has been stopped.
+  pub fn is_stopped(&self) -> bool {
+    let (ref lock, _) = *self.inner;
+    lock.lock().unwrap().stopped
+  }
+  
+  /// Waits while the stream is paused, returning false once stopped.
+  fn wait(&self) -> bool {
+    let (ref lock, ref condvar) = *self.inner;
+    let mut control = lock.lock().unwrap();
+    while control.paused && !control.stopped {
+      control = condvar.wait(control).unwrap();
+    }
+    !control.stopped
+  }
+}
+
+/// Shared state of a published stream.
+struct Published<T> {
+  source:      Option<Stream<T>>,
+  subscribers: Vec<(usize, StreamSender<T>)>,
+  next:        usize,
+  completed:   bool
+}
+
+/// A hot stream shared among subscribers. Created with Stream::publish().
+pub struct Publisher<T> {
+  inner: Arc<Mutex<Published<T>>>
+}
+impl<T> Clone for Publisher<T> {
+  fn clone(&self) -> Publisher<T> {
+    Publisher { inner: self.inner.clone() }
+  }
+}
+impl<T> Publisher<T> where T: Clone + Send + 'static {
+  
+  /// Attaches a new subscriber, returning a stream of the elements
+  /// emitted from this point on. Reading the stream connects the
+  /// publisher to its source if not already connected. Subscribing 
+  /// after the source has ended returns an empty stream.
+  /// # Example
+  ///
+  /// ```
+  /// use smoke::async::Stream;
+  ///
+  /// let publisher = Stream::range(0, 4).publish();
+  /// let numbers   = publisher.subscribe();
+  /// assert_eq!(numbers.read().iter().count(), 4);
+  /// assert_eq!(publisher.subscribe().read().iter().count(), 0);
+  /// ```
+  pub fn subscribe(&self) -> Stream<T> {
+    let (subscriber, receiver) = stream_channel(1);
+    {
+      let mut inner = self.inner.lock().unwrap();
+      if !inner.completed {
+        let id = inner.next;
+        inner.next += 1;
+        inner.subscribers.push((id, subscriber));
+      }
+    }
+    let publisher = self.clone();
+    Stream::output(move |sender| {
+      publisher.connect();
+      forward(receiver, &sender)
+    })
+  }
+  
+  /// Connects this publisher to its source stream if not already 
+  /// connected. Elements are emitted to subscribers attached at the 
+  /// time, and the source is ended once no subscribers remain.
+  pub fn connect(&self) {
+    let source = self.inner.lock().unwrap().source.take();
+    if let Some(stream) = source {
+      let inner = self.inner.clone();
+      scheduling::spawn(move || {
+        let receiver = stream.read();
+        for n in receiver.iter() {
+          // send outside the lock, so subscribers may attach while
+          // other subscribers are slow to read.
+          let subscribers = inner.lock().unwrap().subscribers.clone();
+          let failed = subscribers.into_iter()
+                                  .filter(|&(_, ref subscriber)| subscriber.send(n.clone()).is_err())
+                                  .map(|(id, _)| id)
+                                  .collect::<Vec<_>>();
+          let mut inner = inner.lock().unwrap();
+          inner.subscribers.retain(|&(id, _)| !failed.contains(&id));
+          if inner.subscribers.is_empty() { break; }
+        }
+        let mut inner = inner.lock().unwrap();
+        if let Some(error) = receiver.error() {
+          for &(_, ref subscriber) in &inner.subscribers {
+            let _ = subscriber.fail(error.clone());
+          }
+        }
+        inner.subscribers.clear();
+        inner.completed = true;
+      });
+    }
+  }
+}
+
+/// Shared state of a recorded stream.
+struct Recorded<T> {
+  elements:  Vec<(Duration, T)>,
+  error:     Option<Error>,
+  completed: bool
+}
+
+/// The elements of a stream recorded with their timings. Created with 
+/// Stream::record().
+pub struct Recording<T> {
+  inner: Arc<Mutex<Recorded<T>>>
+}
+impl<T> Clone for Recording<T> {
+  fn clone(&self) -> Recording<T> {
+    Recording { inner: self.inner.clone() }
+  }
+}
+impl<T> Recording<T> where T: Clone + Send + 'static {
+  
+  /// Returns a stream emitting the recorded elements with the timings
+  /// they were recorded with. If the recorded stream failed, the replayed
+  /// stream fails with the same error. Elements recorded after the 
+  /// replayed stream is read are not emitted.
+  /// # Example
+  ///
+  /// ```
+  /// use std::time::Duration;
+  /// use smoke::async::Stream;
+  ///
+  /// let ticks = Stream::range(0, 3).delay_elements(Duration::from_millis(10));
+  /// let (ticks, recording) = ticks.record();
+  /// ticks.read().iter().count();
+  /// for n in recording.replay().read() {
+  ///   // 0, 1, 2 at 10ms intervals
+  /// }
+  /// ```
+  pub fn replay(&self) -> Stream<T> {
+    let recording = self.clone();
+    Stream::output(move |sender| {
+      let (elements, error) = {
+        let inner = recording.inner.lock().unwrap();
+        (inner.elements.clone(), inner.error.clone())
+      };
+      let started = Instant::now();
+      for (offset, n) in elements {
+        let elapsed = started.elapsed();
+        if offset > elapsed {
+          thread::sleep(offset - elapsed);
+        }
+        try!( sender.send(n) );
+      }
+      match error {
+        Some(error) => sender.fail(error),
+        None        => Ok(())
+      }
+    })
+  }
+  
+  /// Returns the recorded elements with the time elapsed from the start
+  /// of the recorded stream.
+  pub fn elements(&self) -> Vec<(Duration, T)> {
+    self.inner.lock().unwrap().elements.clone()
+  }
+  
+  /// Returns true if the recorded stream has ended.
+  pub fn is_completed(&self) -> bool {
+    self.inner.lock().unwrap().completed
   }
 }
 
@@ -24,35 +24,130 @@
  THE SOFTWARE.
 ---------------------------------------------------------------------------*/
 
+use std::fmt;
+use std::mem;
+use std::error;
+use std::thread;
+use std::panic::{self, AssertUnwindSafe};
+use std::time::{Duration, Instant};
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::mpsc::{
+  sync_channel,
   SyncSender, 
-  SendError, 
-  RecvError
+  SendError
 };
 
+use super::handle::{self, TaskHandle};
 use super::scheduling::{
-  TaskHandle,
+  self,
   Scheduler,
   SyncScheduler,
-  ThreadScheduler,
-  ThreadPoolScheduler
+  SharedScheduler,
+  DefaultScheduler,
+  PooledScheduler
 };
+use super::retry::RetryPolicy;
+use super::stream::Stream;
+use super::shared::SharedTask;
+use super::local::LocalTask;
+use super::context;
+use super::super::metrics;
+use super::super::error::Error;
 
 /// A container for a SyncSender&lt;T&gt; to enforce single send.
-pub struct TaskSender<T> {
-   sender: SyncSender<T>
+/// If dropped without sending, the task resolves with a NoResult
+/// error.
+pub struct TaskSender<T, E = Error> {
+   sender:    Option<SyncSender<Result<T, E>>>,
+   name:      Option<String>,
+   no_result: fn(Error) -> E
 }
-impl<T> TaskSender<T>  {
+impl<T, E> TaskSender<T, E>  {
     /// Creates a new task sender.
-    pub fn new(sender: SyncSender<T>) -> TaskSender<T> {
-      TaskSender { sender: sender }
+    pub fn new(sender: SyncSender<Result<T, E>>) -> TaskSender<T, E> where E: From<Error> {
+      TaskSender::named(sender, None)
+    }
+    /// Creates a new task sender for the named task, reporting 
+    /// the name if dropped without sending.
+    pub(crate) fn named(sender: SyncSender<Result<T, E>>, name: Option<String>) -> TaskSender<T, E> where E: From<Error> {
+      TaskSender { sender: Some(sender), name: name, no_result: E::from }
     }
     /// Resolves this task sender with the given value.
-    pub fn send(self, value:T) -> Result<(), SendError<T>> {
-      self.sender.send(value)
+    pub fn send(mut self, value:T) -> Result<(), SendError<T>> {
+      match self.sender.take().unwrap().send(Ok(value)) {
+        Err(SendError(Ok(value))) => Err(SendError(value)),
+        _ => Ok(())
+      }
+    }
+    /// Fails this task sender with the given error. If the
+    /// receiving end has been dropped, the error is discarded.
+    pub fn fail(mut self, error: E) -> Result<(), SendError<T>> {
+      let _ = self.sender.take().unwrap().send(Err(error));
+      Ok(())
+    }
+}
+impl<T, E> Drop for TaskSender<T, E> {
+    /// Resolves the task with a NoResult error if nothing was sent. 
+    /// Senders dropped while panicking are left for the scheduler to
+    /// resolve with the panic.
+    fn drop(&mut self) {
+      if let Some(sender) = self.sender.take() {
+        if !thread::panicking() {
+          let error = (self.no_result)(Error::NoResult(self.name.take()));
+          let _ = sender.try_send(Err(error));
+        }
+      }
     }
 }
 
+/// A scheduling priority hint for tasks. Schedulers that queue
+/// tasks, such as the ThreadPoolScheduler, run higher priority
+/// tasks first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Priority {
+    /// Background work, run after all other queued tasks.
+    Low,
+    /// The default priority.
+    Normal,
+    /// Latency sensitive work, run before all other queued tasks.
+    High
+}
+impl Default for Priority {
+    fn default() -> Priority {
+      Priority::Normal
+    }
+}
+
+/// A error identifying which task in a batch of tasks failed.
+#[derive(Debug)]
+pub struct TaskError<E = Error> {
+    /// The index of the task that failed, or None if the
+    /// batch itself failed before a task could be identified.
+    pub index: Option<usize>,
+    /// The error the task failed with.
+    pub error: E
+}
+impl<E> TaskError<E> {
+    /// Creates a new task error for the task at the given index.
+    pub fn new(index: usize, error: E) -> TaskError<E> {
+      TaskError { index: Some(index), error: error }
+    }
+}
+impl<E> From<Error> for TaskError<E> where E: From<Error> {
+    fn from(error: Error) -> TaskError<E> {
+      TaskError { index: None, error: E::from(error) }
+    }
+}
+impl<E> fmt::Display for TaskError<E> where E: fmt::Display {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+      match self.index {
+        Some(index) => write!(f, "task {} failed: {}", index, self.error),
+        None        => write!(f, "task failed: {}", self.error)
+      }
+    }
+}
+impl<E> error::Error for TaskError<E> where E: error::Error {}
+
 /// Specialized boxed FnOnce() closure type for tasks.
 pub trait TaskFunc<T, TResult> {
     fn call(self: Box<Self>, value:T) -> TResult;
@@ -63,11 +158,156 @@ impl<T, TResult, F> TaskFunc<T, TResult> for F where F: FnOnce(T) -> TResult {
     }
 }
 
+/// The boxed closure type of tasks resolved through a task sender.
+pub(crate) type BoxedFunc<T, E> = Box<TaskFunc<TaskSender<T, E>, Result<(), SendError<T>>> + Send + 'static>;
+
+/// A combinator stage of a task, resolved on the thread running the
+/// task without a channel of its own.
+trait Stage<T, E>: Send {
+    fn resolve(self: Box<Self>) -> Result<T, E>;
+}
+
+/// The operation of a task. Tasks created from a closure resolve 
+/// through a task sender, while results known up front and the typed
+/// combinator stages resolve directly.
+enum Body<T, E> {
+    Func(BoxedFunc<T, E>),
+    Ready(Result<T, E>),
+    Stage(Box<Stage<T, E>>)
+}
+
+/// A task mapped through a closure.
+struct Map<T, E, F> {
+    task: Task<T, E>,
+    func: F
+}
+impl<T, U, E, F> Stage<U, E> for Map<T, E, F> where 
+    T: Send + 'static,
+    E: From<Error> + Send + 'static,
+    F: FnOnce(Result<T, E>) -> U + Send {
+    fn resolve(self: Box<Self>) -> Result<U, E> {
+        let result = run_inline(self.task);
+        try!(context::check().map_err(E::from));
+        Ok((self.func)(result))
+    }
+}
+
+/// A task followed by the task given by a closure.
+struct Then<T, E, F> {
+    task: Task<T, E>,
+    func: F
+}
+impl<T, E, F> Stage<T, E> for Then<T, E, F> where 
+    T: Send + 'static,
+    E: From<Error> + Send + 'static,
+    F: FnOnce(T) -> Task<T, E> + Send {
+    fn resolve(self: Box<Self>) -> Result<T, E> {
+        let value = try!(run_inline(self.task));
+        run_inline((self.func)(value))
+    }
+}
+
+/// A task whose error is mapped through a closure.
+struct MapErr<T, E, F> {
+    task: Task<T, E>,
+    func: F
+}
+impl<T, E, U, F> Stage<T, U> for MapErr<T, E, F> where 
+    T: Send + 'static,
+    E: From<Error> + Send + 'static,
+    F: FnOnce(E) -> U + Send {
+    fn resolve(self: Box<Self>) -> Result<T, U> {
+        run_inline(self.task).map_err(self.func)
+    }
+}
+
+/// A task replaced by the task given by a closure should it fail.
+struct Recover<T, E, F> {
+    task: Task<T, E>,
+    func: F
+}
+impl<T, E, F> Stage<T, E> for Recover<T, E, F> where 
+    T: Send + 'static,
+    E: From<Error> + Send + 'static,
+    F: FnOnce(E) -> Task<T, E> + Send {
+    fn resolve(self: Box<Self>) -> Result<T, E> {
+        match run_inline(self.task) {
+          Ok(value)  => Ok(value),
+          Err(error) => run_inline((self.func)(error))
+        }
+    }
+}
+
 /// Encapsulates an asynchronous operation. Tasks can be run either synchronously or asynchronously.
-pub struct Task<T> {
-    /// The closure to resolve this task.
-    pub func: Box<TaskFunc<TaskSender<T>, Result<(), SendError<T>>> + Send + 'static>
+/// Tasks resolve with either a value of T, or a error of E. The error type defaults to
+/// smoke::Error. Custom error types must be convertible from smoke::Error, which is
+/// used to report tasks that fail without producing a result.
+pub struct Task<T, E = Error> {
+    /// The operation resolving this task.
+    body: Body<T, E>,
+    /// The scheduling priority of this task.
+    priority: Priority,
+    /// The unique id of this task.
+    id: usize,
+    /// The optional name of this task.
+    name: Option<String>,
+    /// True if this task performs blocking io.
+    blocking: bool
 }
+
+/// The id given to the next task created.
+static NEXT_ID: AtomicUsize = AtomicUsize::new(1);
+
+impl<T, E> Task<T, E> {
+    /// Creates a new task from the given closure, with a new id.
+    fn from_func(func: BoxedFunc<T, E>) -> Task<T, E> {
+        Task::from_body(Body::Func(func))
+    }
+    
+    /// Creates a new task from the given operation, with a new id.
+    fn from_body(body: Body<T, E>) -> Task<T, E> {
+        Task { 
+          body:     body, 
+          priority: Priority::Normal, 
+          id:       NEXT_ID.fetch_add(1, Ordering::Relaxed), 
+          name:     None,
+          blocking: false
+        }
+    }
+}
+/// Runs the given tasks on the given scheduler, waiting for their
+/// results in order. Fails with the first error in input order.
+fn run_all<S, T, E>(scheduler: &S, tasks: Vec<Task<T, E>>) -> Result<Vec<T>, E> where
+    S: Scheduler,
+    T: Send + 'static, 
+    E: From<Error> + Send + 'static {
+    tasks.into_iter()
+         .map(|task| scheduler.run(task))
+         .collect::<Vec<_>>()
+         .into_iter()
+         .map(|handle| handle.wait())
+         .collect()
+}
+
+/// Runs the given task on the current thread and waits for its
+/// result. Panics raised while running the task are caught and 
+/// resolved as errors, so continuations can run on the thread 
+/// that resolved the previous task without a panic escaping.
+/// Tasks are not run once the current deadline has passed.
+pub(crate) fn run_inline<T, E>(task: Task<T, E>) -> Result<T, E> where 
+    T: Send + 'static, 
+    E: From<Error> + Send + 'static {
+    try!(context::check().map_err(E::from));
+    match task.body {
+      Body::Ready(result) => result,
+      Body::Stage(stage)  => match panic::catch_unwind(AssertUnwindSafe(move || stage.resolve())) {
+        Err(payload) => Err(E::from(Error::from_panic(payload))),
+        Ok(result)   => result
+      },
+      Body::Func(func)    => LocalTask { func: func }.wait()
+    }
+}
+
 impl <T> Task<T> where T: Send + 'static {
     /// Creates a new task.
     /// # Example
@@ -78,10 +318,86 @@ impl <T> Task<T> where T: Send + 'static {
     /// ```    
     pub fn new<F>(func: F) -> Task<T> 
       where F: FnOnce(TaskSender<T>) -> Result<(), SendError<T>> + Send + 'static {
-        Task { func: Box::new(func) }
+        Task::from_func(Box::new(func))
+    }
+    
+    /// Creates a new task that resolves immediately with the given value.
+    /// # Example
+    /// ```
+    /// use smoke::async::Task;
+    ///
+    /// let task = Task::from_value(10);
+    /// assert_eq!(task.wait().unwrap(), 10);
+    /// ```   
+    pub fn from_value(value: T) -> Task<T> {
+        Task::from_body(Body::Ready(Ok(value)))
+    }
+}
+impl <T, E> Task<T, E> where T: Send + 'static, E: From<Error> + Send + 'static {
+    /// Creates a new task with a custom error type. The task
+    /// can either send a value, or fail with a error.
+    /// # Example
+    /// ```
+    /// use smoke::Error;
+    /// use smoke::async::Task;
+    ///
+    /// #[derive(Debug)]
+    /// enum FetchError {
+    ///   NotFound,
+    ///   Internal(Error)
+    /// }
+    /// impl From<Error> for FetchError {
+    ///   fn from(error: Error) -> FetchError {
+    ///     FetchError::Internal(error)
+    ///   }
+    /// }
+    ///
+    /// let task = Task::<i32, FetchError>::fallible(|sender| sender.fail(FetchError::NotFound));
+    /// match task.wait() {
+    ///   Err(FetchError::NotFound) => { /* ok */ },
+    ///   _ => panic!()
+    /// }
+    /// ```    
+    pub fn fallible<F>(func: F) -> Task<T, E>
+      where F: FnOnce(TaskSender<T, E>) -> Result<(), SendError<T>> + Send + 'static {
+        Task::from_func(Box::new(func))
+    }
+    
+    /// Creates a new task that fails immediately with the given error.
+    /// # Example
+    /// ```
+    /// use smoke::Error;
+    /// use smoke::async::Task;
+    ///
+    /// let task = Task::<i32>::err(Error::Cancelled);
+    /// assert!(task.wait().is_err());
+    /// ```   
+    pub fn err(error: E) -> Task<T, E> {
+        Task::from_body(Body::Ready(Err(error)))
     }
     
-    /// Maps this task into another value.
+    /// Creates a new task that never completes. Waiting on this
+    /// task will block forever. Intended for testing timeouts
+    /// and races.
+    /// # Example
+    /// ```
+    /// use smoke::async::Task;
+    ///
+    /// let task = Task::race(vec![
+    ///   Task::never(), 
+    ///   Task::from_value(10)
+    /// ]);
+    /// assert_eq!(task.wait().unwrap(), 10);
+    /// ```   
+    pub fn never() -> Task<T, E> {
+        Task::fallible(|sender| {
+            mem::forget(sender);
+            Ok(())
+        })
+    }
+    
+    /// Maps this task into another value. The mapping runs on the
+    /// thread that resolved this task.
     /// # Example
     /// ```
     /// use smoke::async::Task;
@@ -90,22 +406,21 @@ impl <T> Task<T> where T: Send + 'static {
     ///                 .map(|n| 10);
     /// assert_eq!(task.wait().unwrap(), 10);
     /// ```       
-    pub fn map<U, F>(self, func: F) -> Task<U> where 
+    pub fn map<U, F>(self, func: F) -> Task<U, E> where 
         U : Send + 'static,
-        F : FnOnce(Result<T, RecvError>) -> U + Send + 'static {
-          Task::<U>::new(move |sender| {
-              let result = ThreadScheduler.run(self).wait();
-              sender.send(func(result))
-          })
+        F : FnOnce(Result<T, E>) -> U + Send + 'static {
+          Task::from_body(Body::Stage(Box::new(Map { task: self, func: func })))
     }
     
     /// Creates a new task that runs this task followed by the next.
+    /// The next task runs on the thread that resolved this task.
     /// # Example
     /// ```
     ///
+    /// use std::time::Duration;
     /// use smoke::async::Task;
     /// fn increment(value: i32) -> Task<i32> {
-    ///  Task::delay(10).map(move |_| value + 1) 
+    ///  Task::delay_for(Duration::from_millis(10)).map(move |_| value + 1) 
     /// }
     ///
     /// assert_eq!(4, increment(0)
@@ -115,24 +430,108 @@ impl <T> Task<T> where T: Send + 'static {
     ///              .wait()
     ///              .unwrap());
     /// ```  
-    pub fn then<F>(self, func: F) -> Task<T> where 
-        F : FnOnce(T) -> Task<T> + Send + 'static {
-          Task::new(move |sender| {
-            let scheduler = ThreadScheduler;
-            match scheduler.run(self).wait() {
-              Err(_)     => {/* no result */},
-              Ok(result) => match scheduler.run(func(result)).wait() {
-                Err(_)     =>  {/* no result */},
-                Ok(result) => try!(sender.send(result))
-              }
-            }; Ok(())
-          })
+    pub fn then<F>(self, func: F) -> Task<T, E> where 
+        F : FnOnce(T) -> Task<T, E> + Send + 'static {
+          Task::from_body(Body::Stage(Box::new(Then { task: self, func: func })))
+    }
+    
+    /// Maps the error of this task into another error.
+    /// # Example
+    /// ```
+    /// use smoke::Error;
+    /// use smoke::async::Task;
+    ///
+    /// let task = Task::<i32>::new(|sender| sender.fail(Error::Timeout))
+    ///                 .map_err(|error| Error::Protocol(format!("{}", error)));
+    /// assert!(task.wait().is_err());
+    /// ```
+    pub fn map_err<U, F>(self, func: F) -> Task<T, U> where
+        U : From<Error> + Send + 'static,
+        F : FnOnce(E) -> U + Send + 'static {
+          Task::from_body(Body::Stage(Box::new(MapErr { task: self, func: func })))
+    }
+    
+    /// Creates a new task that runs this task, and if it fails, runs
+    /// the task given by the closure in its place.
+    /// # Example
+    /// ```
+    /// use smoke::Error;
+    /// use smoke::async::Task;
+    ///
+    /// let task = Task::<i32>::new(|sender| sender.fail(Error::Timeout))
+    ///                 .recover(|_| Task::new(|sender| sender.send(10)));
+    /// assert_eq!(task.wait().unwrap(), 10);
+    /// ```
+    pub fn recover<F>(self, func: F) -> Task<T, E> where 
+        F : FnOnce(E) -> Task<T, E> + Send + 'static {
+          Task::from_body(Body::Stage(Box::new(Recover { task: self, func: func })))
+    }
+    
+    /// Creates a new task that runs this task, resolving with its value
+    /// and the time taken to run it. Timing begins when this task starts
+    /// running, so excludes any time spent waiting to be scheduled.
+    /// # Example
+    /// ```
+    /// use std::time::Duration;
+    /// use smoke::async::Task;
+    ///
+    /// let task = Task::delay_for(Duration::from_millis(10)).map(|_| 10).timed();
+    /// let (value, elapsed) = task.wait().unwrap();
+    /// assert_eq!(value, 10);
+    /// assert!(elapsed >= Duration::from_millis(10));
+    /// ```
+    pub fn timed(self) -> Task<(T, Duration), E> {
+        Task::<(T, Duration), E>::fallible(move |sender| {
+            let started = Instant::now();
+            match run_inline(self) {
+              Ok(value)  => sender.send((value, started.elapsed())),
+              Err(error) => sender.fail(error)
+            }
+        })
+    }
+    
+    /// Creates a new task that runs this task, and if it fails, runs
+    /// the task given by the closure in its place. Unlike recover, the
+    /// closure is not given the error.
+    /// # Example
+    /// ```
+    /// use smoke::Error;
+    /// use smoke::async::Task;
+    ///
+    /// let task = Task::<i32>::err(Error::Timeout)
+    ///                 .or_else(|| Task::from_value(10));
+    /// assert_eq!(task.wait().unwrap(), 10);
+    /// ```
+    pub fn or_else<F>(self, func: F) -> Task<T, E> where 
+        F : FnOnce() -> Task<T, E> + Send + 'static {
+          self.recover(move |_| func())
+    }
+    
+    /// Creates a new task that runs this task, resolving with the
+    /// given default value if it fails.
+    /// # Example
+    /// ```
+    /// use smoke::Error;
+    /// use smoke::async::Task;
+    ///
+    /// let task = Task::<i32>::err(Error::Timeout).unwrap_or(10);
+    /// assert_eq!(task.wait().unwrap(), 10);
+    /// ```
+    pub fn unwrap_or(self, default: T) -> Task<T, E> {
+        Task::fallible(move |sender| {
+            match run_inline(self) {
+              Ok(value) => sender.send(value),
+              Err(_)    => sender.send(default)
+            }
+        })
     }
     
     /// Creates a new task that will process the given tasks in
     /// parallel. Tasks executed in parallel will be scheduled
     /// on a internal threadpool with a pool size of the threads
-    /// argument.
+    /// argument. Idle threadpools are reused across calls. If any 
+    /// task fails, this task fails with the first error in input 
+    /// order.
     /// # Example
     /// ```
     /// use smoke::async::Task;
@@ -148,22 +547,580 @@ impl <T> Task<T> where T: Send + 'static {
     ///   add(7, 8)
     /// ]);
     /// ```      
-    pub fn all(threads: usize, tasks: Vec<Task<T>>) -> Task<Vec<T>>  {
-        Task::<Vec<T>>::new(move |sender| {
-              let scheduler = ThreadPoolScheduler::new(threads);
-              let result    = tasks.into_iter()
-                                .map(|task| scheduler.run(task))
-                                .collect::<Vec<_>>()
-                                .into_iter()
-                                .map(|handle| handle.wait())
-                                .collect::<Result<Vec<_>, RecvError>>();          
-            match result {
+    pub fn all(threads: usize, tasks: Vec<Task<T, E>>) -> Task<Vec<T>, E>  {
+        Task::<Vec<T>, E>::fallible(move |sender| {
+            let scheduler = PooledScheduler::acquire(threads);
+            match run_all(&scheduler, tasks) {
+              Ok (value) => sender.send(value),
+              Err(error) => sender.fail(error)
+            }
+        })
+    }
+    
+    /// Creates a new task that will process the given tasks in
+    /// parallel on the given scheduler, allowing a scheduler to be
+    /// reused across calls. If any task fails, this task fails with 
+    /// the first error in input order.
+    /// # Example
+    /// ```
+    /// use std::sync::Arc;
+    /// use smoke::async::{Task, ThreadPoolScheduler};
+    ///
+    /// fn add(a: i32, b: i32) -> Task<i32> {
+    ///   Task::new(move |sender| sender.send(a + b)) 
+    /// }
+    ///
+    /// let scheduler = Arc::new(ThreadPoolScheduler::new(4));
+    /// let a = Task::all_on(scheduler.clone(), vec![add(1, 2), add(3, 4)]);
+    /// let b = Task::all_on(scheduler.clone(), vec![add(5, 6), add(7, 8)]);
+    /// assert_eq!(a.wait().unwrap(), vec![3, 7]);
+    /// assert_eq!(b.wait().unwrap(), vec![11, 15]);
+    /// ```
+    pub fn all_on<S>(scheduler: S, tasks: Vec<Task<T, E>>) -> Task<Vec<T>, E> where 
+        S: Scheduler + Send + 'static {
+        Task::<Vec<T>, E>::fallible(move |sender| {
+            match run_all(&scheduler, tasks) {
               Ok (value) => sender.send(value),
-              Err(error) => panic!(error)
+              Err(error) => sender.fail(error)
             }
         })
     }
     
+    /// Creates a new task that will process the given tasks one
+    /// at a time, in order, on the thread running this task. Each 
+    /// task is started only once the previous task has completed.
+    /// If any task fails, this task fails with its error, and the
+    /// remaining tasks are not run.
+    /// # Example
+    /// ```
+    /// use smoke::async::Task;
+    ///
+    /// fn add(a: i32, b: i32) -> Task<i32> {
+    ///   Task::new(move |sender| sender.send(a + b)) 
+    /// }
+    ///
+    /// let task = Task::sequence(vec![
+    ///   add(1, 2), 
+    ///   add(3, 4)
+    /// ]);
+    /// assert_eq!(task.wait().unwrap(), vec![3, 7]);
+    /// ```
+    pub fn sequence(tasks: Vec<Task<T, E>>) -> Task<Vec<T>, E> {
+        Task::<Vec<T>, E>::fallible(move |sender| {
+            let mut values = Vec::with_capacity(tasks.len());
+            for task in tasks {
+              match run_inline(task) {
+                Ok(value)  => values.push(value),
+                Err(error) => return sender.fail(error)
+              }
+            }
+            sender.send(values)
+        })
+    }
+    
+    /// Creates a new task that will run the given tasks in parallel
+    /// and resolve with the result of whichever task completes first.
+    /// The results of the remaining tasks are ignored. If every task
+    /// fails, this task fails with the last error received.
+    /// # Example
+    /// ```
+    /// use std::time::Duration;
+    /// use smoke::async::Task;
+    ///
+    /// fn query(delay: u64, backend: &'static str) -> Task<&'static str> {
+    ///   Task::delay_for(Duration::from_millis(delay)).map(move |_| backend)
+    /// }
+    ///
+    /// let task = Task::race(vec![
+    ///   query(100, "slow"),
+    ///   query(1,   "fast")
+    /// ]);
+    /// assert_eq!(task.wait().unwrap(), "fast");
+    /// ```
+    pub fn race(tasks: Vec<Task<T, E>>) -> Task<T, E> {
+        Task::fallible(move |sender| {
+            let count = tasks.len();
+            let (tx, rx) = sync_channel(count);
+            for task in tasks {
+              let tx = tx.clone();
+              scheduling::spawn(move || { let _ = tx.send(task.wait()); });
+            }
+            let mut last = None;
+            for result in rx.iter().take(count) {
+              match result {
+                Ok(value)  => return sender.send(value),
+                Err(error) => last = Some(error)
+              }
+            } 
+            match last {
+              Some(error) => sender.fail(error),
+              None        => Ok(())
+            }
+        })
+    }
+    
+    /// Creates a new task that will run the given tasks in parallel
+    /// and resolve with the value of whichever task completes first,
+    /// along with handles to the tasks still running. Unlike race, the
+    /// remaining tasks can still be waited on. Tasks that fail before
+    /// the first value is received are discarded. If every task fails, 
+    /// this task fails with the last error received.
+    /// # Example
+    /// ```
+    /// use std::time::Duration;
+    /// use smoke::async::Task;
+    ///
+    /// fn query(delay: u64, backend: &'static str) -> Task<&'static str> {
+    ///   Task::delay_for(Duration::from_millis(delay)).map(move |_| backend)
+    /// }
+    ///
+    /// let task = Task::select(vec![
+    ///   query(100, "slow"),
+    ///   query(1,   "fast")
+    /// ]);
+    /// let (value, remaining) = task.wait().unwrap();
+    /// assert_eq!(value, "fast");
+    /// for handle in remaining {
+    ///   assert_eq!(handle.wait().unwrap(), "slow");
+    /// }
+    /// ```
+    pub fn select(tasks: Vec<Task<T, E>>) -> Task<(T, Vec<TaskHandle<T, E>>), E> {
+        Task::<(T, Vec<TaskHandle<T, E>>), E>::fallible(move |sender| {
+            let mut handles = tasks.into_iter()
+                                   .map(|task| SharedScheduler.run(task))
+                                   .collect::<Vec<_>>();
+            let mut last = None;
+            while handles.len() > 0 {
+              let (_, result, remaining) = handle::select(handles);
+              match result {
+                Ok(value)  => return sender.send((value, remaining)),
+                Err(error) => last = Some(error)
+              }
+              handles = remaining;
+            }
+            match last {
+              Some(error) => sender.fail(error),
+              None        => Ok(())
+            }
+        })
+    }
+    
+    /// Creates a new task that runs the task given by the factory, 
+    /// running it again up to the given number of retries if it fails.
+    /// If every attempt fails, this task fails with the last error.
+    /// # Example
+    /// ```
+    /// use std::time::Duration;
+    /// use smoke::async::Task;
+    ///
+    /// fn fetch() -> Task<i32> {
+    ///   Task::delay_for(Duration::from_millis(1)).map(|_| 10)
+    /// }
+    ///
+    /// let task = Task::retry(3, fetch);
+    /// assert_eq!(task.wait().unwrap(), 10);
+    /// ```
+    pub fn retry<F>(retries: usize, factory: F) -> Task<T, E> where
+        F: Fn() -> Task<T, E> + Send + 'static {
+          Task::retry_with(RetryPolicy::immediate(retries), factory)
+    }
+    
+    /// Creates a new task that runs the task given by the factory,
+    /// running it again if it fails. The number of retries and the 
+    /// delay between them is given by the policy.
+    /// # Example
+    /// ```
+    /// use std::time::Duration;
+    /// use smoke::async::{Task, RetryPolicy};
+    ///
+    /// fn fetch() -> Task<i32> {
+    ///   Task::delay_for(Duration::from_millis(1)).map(|_| 10)
+    /// }
+    ///
+    /// let policy = RetryPolicy::fixed(3, Duration::from_millis(10));
+    /// let task   = Task::retry_with(policy, fetch);
+    /// assert_eq!(task.wait().unwrap(), 10);
+    /// ```
+    pub fn retry_with<F>(policy: RetryPolicy, factory: F) -> Task<T, E> where
+        F: Fn() -> Task<T, E> + Send + 'static {
+          Task::fallible(move |sender| {
+            let mut retry = 0;
+            loop {
+              match run_inline(factory()) {
+                Ok(value)  => return sender.send(value),
+                Err(error) => {
+                  if retry >= policy.retries {
+                    return sender.fail(error)
+                  }
+                  retry += 1;
+                  thread::sleep(policy.delay(retry));
+                }
+              }
+            }
+          })
+    }
+    
+    /// Creates a new task that runs the task given by the factory
+    /// repeatedly until the predicate returns true for its value,
+    /// resolving with that value. If an attempt fails, this task 
+    /// fails with its error. Delays between attempts can be given
+    /// by the factory.
+    /// # Example
+    /// ```
+    /// use std::time::Duration;
+    /// use std::sync::Arc;
+    /// use std::sync::atomic::{AtomicUsize, Ordering};
+    /// use smoke::async::Task;
+    ///
+    /// let polls = Arc::new(AtomicUsize::new(0));
+    /// let task  = Task::repeat_until(move || {
+    ///   let polls = polls.clone();
+    ///   Task::delay_for(Duration::from_millis(1))
+    ///     .map(move |_| polls.fetch_add(1, Ordering::SeqCst) + 1)
+    /// }, |polls| *polls == 3);
+    /// assert_eq!(task.wait().unwrap(), 3);
+    /// ```
+    pub fn repeat_until<F, P>(factory: F, predicate: P) -> Task<T, E> where
+        F: Fn() -> Task<T, E> + Send + 'static,
+        P: Fn(&T) -> bool + Send + 'static {
+          Task::fallible(move |sender| {
+            loop {
+              match run_inline(factory()) {
+                Err(error) => return sender.fail(error),
+                Ok(value)  => if predicate(&value) {
+                  return sender.send(value)
+                }
+              }
+            }
+          })
+    }
+    
+    /// Creates a new task that will process the given tasks in
+    /// parallel, as with Task::all. If any task fails, this task
+    /// fails with a TaskError identifying the index of the first 
+    /// failed task in input order.
+    /// # Example
+    /// ```
+    /// use smoke::Error;
+    /// use smoke::async::Task;
+    ///
+    /// let task = Task::try_all(2, vec![
+    ///   Task::new(|sender| sender.send(1)), 
+    ///   Task::new(|sender| sender.fail(Error::Timeout))
+    /// ]);
+    /// match task.wait() {
+    ///   Err(error) => assert_eq!(error.index, Some(1)),
+    ///   Ok(_)      => panic!()
+    /// }
+    /// ```
+    pub fn try_all(threads: usize, tasks: Vec<Task<T, E>>) -> Task<Vec<T>, TaskError<E>> {
+        Task::<Vec<T>, TaskError<E>>::fallible(move |sender| {
+            let scheduler = PooledScheduler::acquire(threads);
+            let handles   = tasks.into_iter()
+                                 .map(|task| scheduler.run(task))
+                                 .collect::<Vec<_>>();
+            let mut values = Vec::with_capacity(handles.len());
+            for (index, handle) in handles.into_iter().enumerate() {
+              match handle.wait() {
+                Ok(value)  => values.push(value),
+                Err(error) => return sender.fail(TaskError::new(index, error))
+              }
+            }
+            sender.send(values)
+        })
+    }
+    
+    /// Creates a new task that will process the given tasks in
+    /// parallel, resolving with the outcome of every task in input
+    /// order once all have completed. Unlike Task::all, failures
+    /// do not short circuit, with each failure reported as a
+    /// TaskError carrying the index of the failed task.
+    /// # Example
+    /// ```
+    /// use smoke::Error;
+    /// use smoke::async::Task;
+    ///
+    /// let task = Task::all_settled(2, vec![
+    ///   Task::new(|sender| sender.send(1)), 
+    ///   Task::new(|sender| sender.fail(Error::Timeout))
+    /// ]);
+    /// let results = task.wait().unwrap();
+    /// assert_eq!(results[0].as_ref().unwrap(), &1);
+    /// assert!(results[1].is_err());
+    /// ```
+    pub fn all_settled(threads: usize, tasks: Vec<Task<T, E>>) -> Task<Vec<Result<T, TaskError<E>>>, E> {
+        Task::<Vec<Result<T, TaskError<E>>>, E>::fallible(move |sender| {
+            let scheduler = PooledScheduler::acquire(threads);
+            let results   = tasks.into_iter()
+                                 .map(|task| scheduler.run(task))
+                                 .collect::<Vec<_>>()
+                                 .into_iter()
+                                 .enumerate()
+                                 .map(|(index, handle)| handle.wait()
+                                    .map_err(|error| TaskError::new(index, error)))
+                                 .collect::<Vec<_>>();
+            sender.send(results)
+        })
+    }
+    
+    /// Creates a new stream that will process the given tasks in
+    /// parallel, emitting each result as soon as it is available. 
+    /// Results are emitted in completion order, not input order.
+    /// The stream fails with the error of the first task to fail, 
+    /// ending without the results of tasks still running. Tasks will be 
+    /// scheduled on a internal threadpool with a pool size of the 
+    /// threads argument.
+    /// # Example
+    /// ```
+    /// use smoke::async::Task;
+    ///
+    /// fn add(a: i32, b: i32) -> Task<i32> {
+    ///   Task::new(move |sender| sender.send(a + b)) 
+    /// }
+    ///
+    /// let stream = Task::all_stream(4, vec![
+    ///   add(1, 2), 
+    ///   add(3, 4), 
+    ///   add(5, 6), 
+    ///   add(7, 8)
+    /// ]);
+    /// for n in stream.read() {
+    ///   // 3, 7, 11, 15 in the order completed.
+    /// }
+    /// ```
+    pub fn all_stream(threads: usize, tasks: Vec<Task<T, E>>) -> Stream<T> where E: Into<Error> {
+        Stream::output(move |sender| {
+            let scheduler = PooledScheduler::acquire(threads);
+            let (tx, rx)  = sync_channel(tasks.len());
+            for task in tasks {
+              let tx = tx.clone();
+              scheduler.run(Task::new(move |sender| {
+                let _ = tx.send(task.wait());
+                sender.send(())
+              }));
+            } 
+            drop(tx);
+            for result in rx {
+              match result {
+                Ok(value)  => try!(sender.send(value)),
+                Err(error) => return sender.fail(error.into())
+              }
+            } Ok(())
+        })
+    }
+    
+    /// Converts this task into a stream emitting the task's value. 
+    /// The task is run when the stream is read. If the task fails,
+    /// the stream fails with the task's error.
+    /// # Example
+    /// ```
+    /// use smoke::async::Task;
+    ///
+    /// let stream = Task::from_value(10).into_stream();
+    /// assert_eq!(stream.read().iter().collect::<Vec<_>>(), vec![10]);
+    /// ```
+    pub fn into_stream(self) -> Stream<T> where E: Into<Error> {
+        Stream::output(move |sender| {
+            match run_inline(self) {
+              Ok(value)  => sender.send(value),
+              Err(error) => sender.fail(error.into())
+            }
+        })
+    }
+    
+    /// Converts this task into a shared task. The shared task can be
+    /// cloned, with each clone able to wait on a clone of the single
+    /// result.
+    /// # Example
+    /// ```
+    /// use smoke::async::Task;
+    ///
+    /// let shared = Task::new(|sender| sender.send(10)).shared();
+    /// let a      = shared.clone();
+    /// let b      = shared.clone();
+    /// assert_eq!(a.wait().unwrap(), 10);
+    /// assert_eq!(b.wait().unwrap(), 10);
+    /// ```
+    pub fn shared(self) -> SharedTask<T, E> where T: Clone, E: Clone {
+        SharedTask::new(self)
+    }
+    
+    /// Sets a deadline for this task. The deadline is carried through 
+    /// the continuations of this task and onto the tasks it schedules,
+    /// which fail with DeadlineExceeded rather than run once it has
+    /// passed. Where a deadline is already set, the earlier is used.
+    /// # Example
+    /// ```
+    /// use std::time::{Duration, Instant};
+    /// use smoke::Error;
+    /// use smoke::async::Task;
+    ///
+    /// let deadline = Instant::now() + Duration::from_millis(10);
+    /// let task     = Task::delay_for(Duration::from_millis(20))
+    ///                    .then(|_| Task::from_value(()))
+    ///                    .with_deadline(deadline);
+    /// match task.wait() {
+    ///   Err(Error::DeadlineExceeded) => { /* ok */ },
+    ///   _ => panic!("expected deadline exceeded")
+    /// }
+    /// ```
+    pub fn with_deadline(self, deadline: Instant) -> Task<T, E> {
+        let id       = self.id;
+        let priority = self.priority;
+        let name     = self.name.clone();
+        let blocking = self.blocking;
+        let mut task = Task::fallible(move |sender| {
+            let _context = context::enter(Some(deadline));
+            match run_inline(self) {
+              Ok(value)  => sender.send(value),
+              Err(error) => sender.fail(error)
+            }
+        });
+        task.id       = id;
+        task.priority = priority;
+        task.name     = name;
+        task.blocking = blocking;
+        task
+    }
+    
+    /// Sets the scheduling priority of this task. The priority applies
+    /// to this task as scheduled, so should be set last when composing
+    /// tasks.
+    /// # Example
+    /// ```
+    /// use smoke::async::{Task, Priority, ThreadPoolScheduler};
+    ///
+    /// let task   = Task::from_value(10).with_priority(Priority::High);
+    /// let handle = task.schedule(ThreadPoolScheduler::new(4));
+    /// assert_eq!(handle.wait().unwrap(), 10);
+    /// ```
+    pub fn with_priority(mut self, priority: Priority) -> Task<T, E> {
+        self.priority = priority;
+        self
+    }
+    
+    /// Returns the scheduling priority of this task.
+    pub fn priority(&self) -> Priority {
+        self.priority
+    }
+    
+    /// Marks this task as performing blocking io. Blocking tasks 
+    /// scheduled on a ThreadPoolScheduler are run on a separate elastic
+    /// threadpool, leaving the scheduler's threads free for compute. As 
+    /// with priority, this applies to this task as scheduled, so should
+    /// be set last when composing tasks.
+    /// # Example
+    /// ```
+    /// use std::fs::File;
+    /// use std::io::Read;
+    /// use smoke::async::{Task, ThreadPoolScheduler};
+    ///
+    /// let task = Task::new(|sender| {
+    ///   let mut content = String::new();
+    ///   let _ = File::open("Cargo.toml").and_then(|mut file| file.read_to_string(&mut content));
+    ///   sender.send(content)
+    /// }).blocking();
+    /// assert!(task.is_blocking());
+    /// let handle = task.schedule(ThreadPoolScheduler::new(4));
+    /// assert!(handle.wait().unwrap().len() > 0);
+    /// ```
+    pub fn blocking(mut self) -> Task<T, E> {
+        self.blocking = true;
+        self
+    }
+    
+    /// Returns true if this task is marked as blocking.
+    pub fn is_blocking(&self) -> bool {
+        self.blocking
+    }
+    
+    /// Names this task. The name is reported to task observers and
+    /// included in scheduler panic messages for this task. As with
+    /// priority, the name applies to this task as scheduled, so 
+    /// should be set last when composing tasks.
+    /// # Example
+    /// ```
+    /// use smoke::async::Task;
+    ///
+    /// let task = Task::from_value(10).named("fetch-user");
+    /// assert_eq!(task.name(), Some("fetch-user"));
+    /// assert_eq!(task.wait().unwrap(), 10);
+    /// ```
+    pub fn named<N: Into<String>>(mut self, name: N) -> Task<T, E> {
+        self.name = Some(name.into());
+        self
+    }
+    
+    /// Returns the name of this task, if named.
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_ref().map(|name| name.as_str())
+    }
+    
+    /// Returns the unique id of this task, generated when the task 
+    /// was created.
+    pub fn id(&self) -> usize {
+        self.id
+    }
+    
+    /// Schedules this task to run on the default scheduler, as set with
+    /// set_default_scheduler. Returns a wait handle to the caller.
+    /// # Example
+    /// ```
+    /// use smoke::async::Task;
+    ///
+    /// let handle = Task::from_value(10).spawn();
+    /// assert_eq!(handle.wait().unwrap(), 10);
+    /// ```
+    pub fn spawn(self) -> TaskHandle<T, E> {
+        DefaultScheduler.run(self)
+    }
+    
+    /// Converts this task into a closure resolving the task through a
+    /// task sender, as run by the schedulers. Panics raised by stages
+    /// resolved within the closure are left for the caller to catch.
+    pub(crate) fn into_func(self) -> BoxedFunc<T, E> {
+        match self.body {
+          Body::Func(func)    => func,
+          Body::Ready(result) => Box::new(move |sender: TaskSender<T, E>| match result {
+            Ok(value)  => sender.send(value),
+            Err(error) => sender.fail(error)
+          }),
+          Body::Stage(stage)  => Box::new(move |sender: TaskSender<T, E>| match stage.resolve() {
+            Ok(value)  => sender.send(value),
+            Err(error) => sender.fail(error)
+          })
+        }
+    }
+    
+    /// Converts this task into a unit task that resolves this task's 
+    /// result on the returned handle, keeping the id, name, priority
+    /// and blocking hint of this task. Allows tasks to be run on type
+    /// erased schedulers.
+    pub(crate) fn erase(self) -> (Task<()>, TaskHandle<T, E>) {
+        let (tx, rx) = sync_channel(1);
+        (self.erase_into(tx), TaskHandle::new(rx))
+    }
+    
+    /// Converts this task into a unit task as with erase, resolving
+    /// this task's result on the given sender.
+    pub(crate) fn erase_into(self, tx: SyncSender<Result<T, E>>) -> Task<()> {
+        let id       = self.id;
+        let name     = self.name.clone();
+        let priority = self.priority;
+        let blocking = self.blocking;
+        let mut task = Task::new(move |sender| {
+            let _ = tx.send(run_inline(self));
+            // the handle of the unit task is not kept.
+            let _ = sender.send(());
+            Ok(())
+        });
+        task.id       = id;
+        task.name     = name;
+        task.priority = priority;
+        task.blocking = blocking;
+        task
+    }
+    
     /// Schedules this task to run on the given scheduler. Returns
     /// a wait handle to the caller.
     /// # Example
@@ -179,12 +1136,13 @@ impl <T> Task<T> where T: Send + 'static {
     /// let handle = add(10, 20).schedule(scheduler);
     /// assert_eq!(handle.wait().unwrap(), 30); 
     /// ```     
-    pub fn schedule<S: Scheduler>(self, scheduler:S) -> TaskHandle<T> {
+    pub fn schedule<S: Scheduler>(self, scheduler:S) -> TaskHandle<T, E> {
         scheduler.run(self)
     }
     
-    /// Runs this task immediately on its only thread. The result will
-    /// be passed into the given closure.
+    /// Runs this task on the default scheduler, which unless set with
+    /// set_default_scheduler, runs each task on its own thread. The 
+    /// result will be passed into the given closure.
     /// # Example
     /// ```
     /// use smoke::async::Task;
@@ -201,9 +1159,33 @@ impl <T> Task<T> where T: Send + 'static {
     /// ```     
     pub fn async<U, F>(self, func: F) -> TaskHandle<U>
         where U : Send + 'static,
-              F : FnOnce(Result<T, RecvError>) -> U + Send + 'static {
-        ThreadScheduler.run(Task::new(|sender| {
-          let result    = ThreadScheduler.run(self).wait();
+              F : FnOnce(Result<T, E>) -> U + Send + 'static {
+        self.async_on(DefaultScheduler, func)
+    }
+    
+    /// Runs this task on the given scheduler. The result will be passed 
+    /// into the given closure, which runs on the same scheduler.
+    /// # Example
+    /// ```
+    /// use smoke::async::{Task, ThreadPoolScheduler};
+    ///
+    /// fn add(a: i32, b: i32) -> Task<i32> {
+    ///   Task::new(move |sender| sender.send(a + b)) 
+    /// }
+    ///
+    /// let scheduler = ThreadPoolScheduler::new(4);
+    /// let handle    = add(10, 20).async_on(&scheduler, |result| {
+    ///    assert_eq!(result.unwrap(), 30);
+    ///    123  
+    /// });
+    /// assert_eq!(handle.wait().unwrap(), 123);
+    /// ```     
+    pub fn async_on<S, U, F>(self, scheduler: S, func: F) -> TaskHandle<U>
+        where S : Scheduler,
+              U : Send + 'static,
+              F : FnOnce(Result<T, E>) -> U + Send + 'static {
+        scheduler.run(Task::new(|sender| {
+          let result    = run_inline(self);
           let result    = func(result);
           sender.send(result)
         }))
@@ -217,27 +1199,162 @@ impl <T> Task<T> where T: Send + 'static {
     /// let task = Task::new(|sender| sender.send(10));
     /// assert_eq!(task.wait().unwrap(), 10);
     /// ```      
-    pub fn wait(self) -> Result<T, RecvError> {
+    pub fn wait(self) -> Result<T, E> {
         SyncScheduler.run(self).wait()
     }
 }
 
+impl <A, B, E> Task<(A, B), E> where 
+    A: Send + 'static, 
+    B: Send + 'static, 
+    E: From<Error> + Send + 'static {
+    /// Creates a new task that runs the given tasks in parallel
+    /// and resolves with both results. If either task fails, this
+    /// task fails with the first error in argument order.
+    /// # Example
+    /// ```
+    /// use smoke::async::Task;
+    ///
+    /// let task = Task::join2(
+    ///   Task::new(|sender| sender.send(1)),
+    ///   Task::new(|sender| sender.send("hello"))
+    /// );
+    /// assert_eq!(task.wait().unwrap(), (1, "hello"));
+    /// ```
+    pub fn join2(a: Task<A, E>, b: Task<B, E>) -> Task<(A, B), E> {
+        Task::fallible(move |sender| {
+            let a = SharedScheduler.run(a);
+            let b = run_inline(b);
+            match (a.wait(), b) {
+              (Ok(a), Ok(b)) => sender.send((a, b)),
+              (Err(error), _) => sender.fail(error),
+              (_, Err(error)) => sender.fail(error)
+            }
+        })
+    }
+}
+
+impl <A, B, C, E> Task<(A, B, C), E> where 
+    A: Send + 'static, 
+    B: Send + 'static, 
+    C: Send + 'static, 
+    E: From<Error> + Send + 'static {
+    /// Creates a new task that runs the given tasks in parallel
+    /// and resolves with all three results. If any task fails, this
+    /// task fails with the first error in argument order.
+    /// # Example
+    /// ```
+    /// use smoke::async::Task;
+    ///
+    /// let task = Task::join3(
+    ///   Task::new(|sender| sender.send(1)),
+    ///   Task::new(|sender| sender.send("hello")),
+    ///   Task::new(|sender| sender.send(true))
+    /// );
+    /// assert_eq!(task.wait().unwrap(), (1, "hello", true));
+    /// ```
+    pub fn join3(a: Task<A, E>, b: Task<B, E>, c: Task<C, E>) -> Task<(A, B, C), E> {
+        Task::fallible(move |sender| {
+            let a = SharedScheduler.run(a);
+            let b = SharedScheduler.run(b);
+            let c = run_inline(c);
+            match (a.wait(), b.wait(), c) {
+              (Ok(a), Ok(b), Ok(c)) => sender.send((a, b, c)),
+              (Err(error), _, _) => sender.fail(error),
+              (_, Err(error), _) => sender.fail(error),
+              (_, _, Err(error)) => sender.fail(error)
+            }
+        })
+    }
+}
+
+impl <A, B, C, D, E> Task<(A, B, C, D), E> where 
+    A: Send + 'static, 
+    B: Send + 'static, 
+    C: Send + 'static, 
+    D: Send + 'static, 
+    E: From<Error> + Send + 'static {
+    /// Creates a new task that runs the given tasks in parallel
+    /// and resolves with all four results. If any task fails, this
+    /// task fails with the first error in argument order.
+    /// # Example
+    /// ```
+    /// use smoke::async::Task;
+    ///
+    /// let task = Task::join4(
+    ///   Task::new(|sender| sender.send(1)),
+    ///   Task::new(|sender| sender.send("hello")),
+    ///   Task::new(|sender| sender.send(true)),
+    ///   Task::new(|sender| sender.send(1.5))
+    /// );
+    /// assert_eq!(task.wait().unwrap(), (1, "hello", true, 1.5));
+    /// ```
+    pub fn join4(a: Task<A, E>, b: Task<B, E>, c: Task<C, E>, d: Task<D, E>) -> Task<(A, B, C, D), E> {
+        Task::fallible(move |sender| {
+            let a = SharedScheduler.run(a);
+            let b = SharedScheduler.run(b);
+            let c = SharedScheduler.run(c);
+            let d = run_inline(d);
+            match (a.wait(), b.wait(), c.wait(), d) {
+              (Ok(a), Ok(b), Ok(c), Ok(d)) => sender.send((a, b, c, d)),
+              (Err(error), _, _, _) => sender.fail(error),
+              (_, Err(error), _, _) => sender.fail(error),
+              (_, _, Err(error), _) => sender.fail(error),
+              (_, _, _, Err(error)) => sender.fail(error)
+            }
+        })
+    }
+}
+
 impl Task<()> {
     /// Creates a task that will delay for the given duration
     /// in milliseconds.
     /// # Example
     /// ```
+    /// # #![allow(deprecated)]
     /// use smoke::async::Task;
     ///
     /// Task::delay(1000).wait();
     /// ```      
+    #[deprecated(note = "use Task::delay_for or Task::delay_until")]
     pub fn delay(millis: u64) -> Task<()> {
-      use std::thread;
-      use std::time::Duration;
+      Task::delay_for(Duration::from_millis(millis))
+    }
+    
+    /// Creates a task that will delay for the given duration.
+    /// # Example
+    /// ```
+    /// use std::time::Duration;
+    /// use smoke::async::Task;
+    ///
+    /// Task::delay_for(Duration::from_millis(1000)).wait();
+    /// ```      
+    pub fn delay_for(duration: Duration) -> Task<()> {
+      Task::new(move|sender| {
+        metrics::DELAYS_STARTED.increment();
+        thread::sleep(duration);
+        sender.send(())
+      })
+    }
+    
+    /// Creates a task that will delay until the given instant. If
+    /// the instant has already passed, the task completes immediately.
+    /// # Example
+    /// ```
+    /// use std::time::{Duration, Instant};
+    /// use smoke::async::Task;
+    ///
+    /// let deadline = Instant::now() + Duration::from_millis(1000);
+    /// Task::delay_until(deadline).wait();
+    /// ```      
+    pub fn delay_until(instant: Instant) -> Task<()> {
       Task::new(move|sender| {
-        thread::sleep(Duration::from_millis(millis));
+        metrics::DELAYS_STARTED.increment();
+        let now = Instant::now();
+        if instant > now {
+          thread::sleep(instant - now);
+        }
         sender.send(())
       })
     }
 }
-
@@ -0,0 +1,379 @@
+/*--------------------------------------------------------------------------
+ smoke-rs
+
+ The MIT License (MIT)
+
+ Copyright (c) 2016 Haydn Paterson (sinclair) <haydn.developer@gmail.com>
+
+ Permission is hereby granted, free of charge, to any person obtaining a copy
+ of this software and associated documentation files (the "Software"), to deal
+ in the Software without restriction, including without limitation the rights
+ to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ copies of the Software, and to permit persons to whom the Software is
+ furnished to do so, subject to the following conditions:
+
+ The above copyright notice and this permission notice shall be included in
+ all copies or substantial portions of the Software.
+
+ THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+ THE SOFTWARE.
+---------------------------------------------------------------------------*/
+
+use std::cmp;
+use std::thread;
+use std::usize;
+use std::time::Duration;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex, Condvar};
+use std::panic::{self, AssertUnwindSafe};
+use super::task::Task;
+use super::super::error::Error;
+
+/// A type erased closure run on the threadpool.
+type Job = Box<FnOnce() + Send + 'static>;
+
+struct Inner {
+  bound:      usize,
+  threads:    usize,
+  idle:       usize,
+  active:     usize,
+  queue:      VecDeque<Job>,
+  capacity:   usize,
+  closed:     bool,
+  name:       Option<String>,
+  stack_size: Option<usize>,
+  keep_alive: Option<Duration>,
+  on_error:   Option<Arc<Fn(Error) + Send + Sync>>
+}
+
+/// The state shared by a threadpool and its threads, signalling
+/// threads as jobs are queued, spawners as the queue has room and
+/// waiters once idle.
+struct Shared {
+  inner: Mutex<Inner>,
+  work:  Condvar,
+  room:  Condvar,
+  idle:  Condvar
+}
+impl Shared {
+  fn wait_idle(&self) {
+    let mut inner = self.inner.lock().unwrap();
+    while inner.active > 0 || inner.queue.len() > 0 {
+      inner = self.idle.wait(inner).unwrap();
+    }
+  }
+}
+
+/// Closes the threadpool once the last clone of the threadpool is dropped.
+struct Pool {
+  shared: Arc<Shared>
+}
+impl Drop for Pool {
+  fn drop(&mut self) {
+    self.shared.inner.lock().unwrap().closed = true;
+    self.shared.work.notify_all();
+  }
+}
+
+/// A elastic threadpool. Threads are started as jobs are spawned, up
+/// to the given bound, and are kept to run the jobs spawned after, with
+/// jobs beyond the bound queued until a thread is free. Threads exit 
+/// once idle for longer than the keep alive, if set, or once the 
+/// threadpool is dropped and the jobs queued have run. Used by the 
+/// ThreadPoolScheduler to run blocking tasks.
+///
+/// # Example
+/// ```
+/// use std::sync::mpsc::channel;
+/// use smoke::async::threadpool::ThreadPool;
+///
+/// let pool       = ThreadPool::new(4);
+/// let (tx, rx)   = channel();
+/// for n in 0..8 {
+///   let tx = tx.clone();
+///   pool.spawn(move || tx.send(n).unwrap());
+/// }
+/// assert_eq!(rx.iter().take(8).sum::<i32>(), 28);
+/// ```
+#[derive(Clone)]
+pub struct ThreadPool {
+  pool: Arc<Pool>
+}
+impl ThreadPool {
+  
+  /// Creates a new threadpool running at most bound jobs at once. A 
+  /// bound of zero is raised to one.
+  pub fn new(bound: usize) -> ThreadPool {
+    ThreadPool::with_threads(bound, None, None)
+  }
+  
+  /// Creates a new threadpool running at most bound jobs at once, and 
+  /// queueing at most capacity jobs waiting for a thread. Spawning a 
+  /// job while the queue is full blocks until the queue has room, and
+  /// try_spawn fails.
+  ///
+  /// # Example
+  /// ```
+  /// use smoke::async::threadpool::ThreadPool;
+  ///
+  /// let pool = ThreadPool::bounded(4, 64);
+  /// pool.spawn(|| println!("hello"));
+  /// ```
+  pub fn bounded(bound: usize, capacity: usize) -> ThreadPool {
+    let pool = ThreadPool::new(bound);
+    // an empty queue would never accept a job.
+    pool.pool.shared.inner.lock().unwrap().capacity = cmp::max(capacity, 1);
+    pool
+  }
+  
+  /// Creates a new threadpool whose threads are started with the given
+  /// name and stack size.
+  pub(crate) fn with_threads(bound: usize, name: Option<String>, stack_size: Option<usize>) -> ThreadPool {
+    let inner = Inner {
+      // a pool without threads would never run a job.
+      bound:      cmp::max(bound, 1),
+      threads:    0,
+      idle:       0,
+      active:     0,
+      queue:      VecDeque::new(),
+      capacity:   usize::MAX,
+      closed:     false,
+      name:       name,
+      stack_size: stack_size,
+      keep_alive: None,
+      on_error:   None
+    };
+    ThreadPool {
+      pool: Arc::new(Pool { 
+        shared: Arc::new(Shared { inner: Mutex::new(inner), work: Condvar::new(), room: Condvar::new(), idle: Condvar::new() }) 
+      })
+    }
+  }
+  
+  /// Spawns the given closure on this threadpool, blocking while the
+  /// queue of a bounded threadpool is full. 
+  pub fn spawn<F>(&self, func: F) where F: FnOnce() + Send + 'static {
+    let shared    = &self.pool.shared;
+    let mut inner = shared.inner.lock().unwrap();
+    while inner.queue.len() >= inner.capacity {
+      inner = shared.room.wait(inner).unwrap();
+    }
+    push(shared, &mut inner, Box::new(func));
+  }
+  
+  /// Spawns the given closure on this threadpool, failing with a 
+  /// Rejected error without running the closure if the queue of a 
+  /// bounded threadpool is full.
+  ///
+  /// # Example
+  /// ```
+  /// use smoke::async::threadpool::ThreadPool;
+  ///
+  /// let pool = ThreadPool::bounded(1, 16);
+  /// match pool.try_spawn(|| println!("hello")) {
+  ///   Ok(())     => println!("spawned"),
+  ///   Err(error) => println!("{}", error)
+  /// }
+  /// ```
+  pub fn try_spawn<F>(&self, func: F) -> Result<(), Error> where F: FnOnce() + Send + 'static {
+    let shared    = &self.pool.shared;
+    let mut inner = shared.inner.lock().unwrap();
+    if inner.queue.len() >= inner.capacity {
+      return Err(Error::Rejected);
+    }
+    push(shared, &mut inner, Box::new(func));
+    Ok(())
+  }
+  
+  /// Sets a callback run with a Panicked error each time a job spawned
+  /// on this threadpool panics. The thread of the job is replaced, so
+  /// panics never reduce the jobs run at once. The callback is run on 
+  /// the thread of the job.
+  ///
+  /// # Example
+  /// ```
+  /// use smoke::async::threadpool::ThreadPool;
+  ///
+  /// let pool = ThreadPool::new(4);
+  /// pool.on_error(|error| println!("job failed: {}", error));
+  /// pool.spawn(|| panic!("boom"));
+  /// pool.wait_idle();
+  /// ```
+  pub fn on_error<F>(&self, func: F) where F: Fn(Error) + Send + Sync + 'static {
+    self.pool.shared.inner.lock().unwrap().on_error = Some(Arc::new(func));
+  }
+  
+  /// Sets how long a thread waits idle for a job before exiting. By
+  /// default threads are kept until the threadpool is dropped. Threads
+  /// exited are started again as jobs are spawned.
+  ///
+  /// # Example
+  /// ```
+  /// use std::time::Duration;
+  /// use smoke::async::threadpool::ThreadPool;
+  ///
+  /// let pool = ThreadPool::new(64);
+  /// pool.keep_alive(Duration::from_secs(5));
+  /// pool.spawn(|| println!("hello"));
+  /// ```
+  pub fn keep_alive(&self, keep_alive: Duration) {
+    self.pool.shared.inner.lock().unwrap().keep_alive = Some(keep_alive);
+  }
+  
+  /// Returns the number of jobs currently running.
+  pub fn active(&self) -> usize {
+    self.pool.shared.inner.lock().unwrap().active
+  }
+  
+  /// Returns the most jobs run at once.
+  pub fn bound(&self) -> usize {
+    self.pool.shared.inner.lock().unwrap().bound
+  }
+  
+  /// Returns the number of threads started and not yet exited.
+  pub fn threads(&self) -> usize {
+    self.pool.shared.inner.lock().unwrap().threads
+  }
+  
+  /// Returns the number of jobs waiting for a thread.
+  pub fn queued(&self) -> usize {
+    self.pool.shared.inner.lock().unwrap().queue.len()
+  }
+  
+  /// Blocks the current thread until no jobs are running or queued on
+  /// this threadpool. Returns immediately if already idle. Jobs spawned
+  /// from other threads while waiting extend the wait.
+  ///
+  /// # Example
+  /// ```
+  /// use std::sync::Arc;
+  /// use std::sync::atomic::{AtomicUsize, Ordering};
+  /// use smoke::async::threadpool::ThreadPool;
+  ///
+  /// let pool = ThreadPool::new(4);
+  /// let done = Arc::new(AtomicUsize::new(0));
+  /// for _ in 0..8 {
+  ///   let done = done.clone();
+  ///   pool.spawn(move || { done.fetch_add(1, Ordering::SeqCst); });
+  /// }
+  /// pool.wait_idle();
+  /// assert_eq!(done.load(Ordering::SeqCst), 8);
+  /// ```
+  pub fn wait_idle(&self) {
+    self.pool.shared.wait_idle()
+  }
+  
+  /// Returns a task that resolves once no jobs are running or queued 
+  /// on this threadpool, as with wait_idle. The task does not keep the
+  /// threadpool open.
+  ///
+  /// # Example
+  /// ```
+  /// use smoke::async::threadpool::ThreadPool;
+  ///
+  /// let pool = ThreadPool::new(4);
+  /// pool.spawn(|| println!("working"));
+  /// pool.idle().wait().unwrap();
+  /// ```
+  pub fn idle(&self) -> Task<()> {
+    let shared = self.pool.shared.clone();
+    Task::new(move |sender| {
+      shared.wait_idle();
+      sender.send(())
+    })
+  }
+  
+}
+
+/// Queues a job, signalling an idle thread or starting a new thread
+/// while below the bound.
+fn push(shared: &Arc<Shared>, inner: &mut Inner, job: Job) {
+  inner.queue.push_back(job);
+  if inner.idle >= inner.queue.len() {
+    shared.work.notify_one();
+  } else if inner.threads < inner.bound {
+    start(shared, inner);
+  }
+}
+
+/// Starts a thread running the jobs of the given threadpool.
+fn start(shared: &Arc<Shared>, inner: &mut Inner) {
+  inner.threads += 1;
+  let shared      = shared.clone();
+  let mut builder = thread::Builder::new();
+  if let Some(ref name) = inner.name {
+    builder = builder.name(name.clone());
+  }
+  if let Some(stack_size) = inner.stack_size {
+    builder = builder.stack_size(stack_size);
+  }
+  if let Err(_error) = builder.spawn(move || work(shared)) {
+    log_debug!("ThreadPool: unable to start thread: {}", _error);
+    inner.threads -= 1;
+  }
+}
+
+/// Runs the jobs of a threadpool until closed and no jobs remain, or
+/// until idle for the keep alive. A thread whose job panicked is replaced by a new thread, as the job
+/// may have left the thread's locals in an unknown state.
+fn work(shared: Arc<Shared>) {
+  let mut inner = shared.inner.lock().unwrap();
+  loop {
+    match inner.queue.pop_front() {
+      Some(job) => {
+        inner.active += 1;
+        drop(inner);
+        shared.room.notify_one();
+        let result = panic::catch_unwind(AssertUnwindSafe(move || job()));
+        if let Err(payload) = result {
+          log_debug!("ThreadPool: job panicked, replacing thread");
+          let on_error = shared.inner.lock().unwrap().on_error.clone();
+          if let Some(on_error) = on_error {
+            let error = Error::from_panic(payload);
+            if panic::catch_unwind(AssertUnwindSafe(|| on_error(error))).is_err() {
+              log_debug!("ThreadPool: error callback panicked");
+            }
+          }
+          let mut inner = shared.inner.lock().unwrap();
+          inner.active  -= 1;
+          inner.threads -= 1;
+          if inner.active == 0 && inner.queue.is_empty() {
+            shared.idle.notify_all();
+          }
+          if !inner.closed || !inner.queue.is_empty() {
+            start(&shared, &mut inner);
+          }
+          return;
+        }
+        inner = shared.inner.lock().unwrap();
+        inner.active -= 1;
+        if inner.active == 0 && inner.queue.is_empty() {
+          shared.idle.notify_all();
+        }
+      },
+      None if inner.closed => break,
+      None => match inner.keep_alive {
+        None => {
+          inner.idle += 1;
+          inner = shared.work.wait(inner).unwrap();
+          inner.idle -= 1;
+        },
+        Some(keep_alive) => {
+          inner.idle += 1;
+          let (guard, waited) = shared.work.wait_timeout(inner, keep_alive).unwrap();
+          inner = guard;
+          inner.idle -= 1;
+          if waited.timed_out() && inner.queue.is_empty() {
+            break;
+          }
+        }
+      }
+    }
+  }
+  inner.threads -= 1;
+}
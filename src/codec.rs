@@ -0,0 +1,137 @@
+/*--------------------------------------------------------------------------
+ smoke-rs
+
+ The MIT License (MIT)
+
+ Copyright (c) 2016 Haydn Paterson (sinclair) <haydn.developer@gmail.com>
+
+ Permission is hereby granted, free of charge, to any person obtaining a copy
+ of this software and associated documentation files (the "Software"), to deal
+ in the Software without restriction, including without limitation the rights
+ to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ copies of the Software, and to permit persons to whom the Software is
+ furnished to do so, subject to the following conditions:
+
+ The above copyright notice and this permission notice shall be included in
+ all copies or substantial portions of the Software.
+
+ THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+ THE SOFTWARE.
+---------------------------------------------------------------------------*/
+
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json;
+use super::async::{Stream, StreamSender};
+use super::io::ByteStream;
+use super::error::Error;
+
+/// Encodes a message as a line of JSON, including the newline.
+fn to_line<M: Serialize>(message: &M) -> Result<Vec<u8>, Error> {
+  let mut line = try!(serde_json::to_vec(message).map_err(|error| Error::Protocol(error.to_string())));
+  line.push(b'\n');
+  Ok(line)
+}
+
+/// Decodes the stream of bytes into a stream of messages, with each 
+/// message a line of JSON. Lines may span chunks of the byte stream.
+/// The stream fails if a message cannot be decoded.
+///
+/// # Example
+/// ```
+/// use smoke::codec;
+/// use smoke::async::Stream;
+///
+/// let bytes    = Stream::once(b"[1,\"a\"]\n[2,\"b\"]\n".to_vec());
+/// let messages = codec::decode::<(i32, String)>(bytes);
+/// assert_eq!(messages.read().iter().collect::<Vec<_>>(), vec![
+///   (1, "a".to_string()), 
+///   (2, "b".to_string())
+/// ]);
+/// ```
+pub fn decode<M>(bytes: Stream<Vec<u8>>) -> Stream<M> where M: DeserializeOwned + Send + 'static {
+  Stream::output(move |sender| {
+    let receiver = bytes.split(b'\n').read();
+    for line in receiver.iter() {
+      if line.iter().all(|byte| (*byte as char).is_whitespace()) {
+        continue;
+      }
+      match serde_json::from_slice(&line) {
+        Ok(message) => try!(sender.send(message)),
+        Err(error)  => return sender.fail(Error::Protocol(error.to_string()))
+      }
+    }
+    match receiver.error() {
+      Some(error) => sender.fail(error),
+      None        => Ok(())
+    }
+  })
+}
+
+/// Encodes the stream of messages into a stream of bytes, with each
+/// message a line of JSON. The stream fails if a message cannot be 
+/// encoded.
+///
+/// # Example
+/// ```
+/// use smoke::codec;
+/// use smoke::async::Stream;
+///
+/// let bytes = codec::encode(Stream::range(0, 3));
+/// assert_eq!(bytes.read().iter().collect::<Vec<_>>(), vec![
+///   b"0\n".to_vec(), 
+///   b"1\n".to_vec(), 
+///   b"2\n".to_vec()
+/// ]);
+/// ```
+pub fn encode<M>(messages: Stream<M>) -> Stream<Vec<u8>> where M: Serialize + Send + 'static {
+  Stream::output(move |sender| {
+    let receiver = messages.read();
+    for message in receiver.iter() {
+      match to_line(&message) {
+        Ok(line)   => try!(sender.send(line)),
+        Err(error) => return sender.fail(error)
+      }
+    }
+    match receiver.error() {
+      Some(error) => sender.fail(error),
+      None        => Ok(())
+    }
+  })
+}
+
+/// Creates a sender of messages, encoding each message sent as a line
+/// of JSON on the given sender of bytes. Messages that cannot be encoded
+/// fail the sender of bytes.
+///
+/// # Example
+/// ```
+/// use smoke::codec;
+/// use smoke::async::Stream;
+///
+/// let bytes = Stream::<Vec<u8>>::input(|receiver| {
+///   for line in receiver {
+///     // write line to a socket.
+///   }
+/// });
+/// let messages = codec::encoder(bytes);
+/// messages.send(("hello", 1)).unwrap();
+/// ```
+pub fn encoder<M>(bytes: StreamSender<Vec<u8>>) -> StreamSender<M> where M: Serialize + Send + 'static {
+  Stream::input(move |receiver| {
+    for message in receiver {
+      match to_line(&message) {
+        Ok(line)   => if bytes.send(line).is_err() { return; },
+        Err(error) => { 
+          let _ = bytes.fail(error);
+          return;
+        }
+      }
+    }
+  })
+}
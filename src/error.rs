@@ -0,0 +1,148 @@
+/*--------------------------------------------------------------------------
+ smoke-rs
+
+ The MIT License (MIT)
+
+ Copyright (c) 2016 Haydn Paterson (sinclair) <haydn.developer@gmail.com>
+
+ Permission is hereby granted, free of charge, to any person obtaining a copy
+ of this software and associated documentation files (the "Software"), to deal
+ in the Software without restriction, including without limitation the rights
+ to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ copies of the Software, and to permit persons to whom the Software is
+ furnished to do so, subject to the following conditions:
+
+ The above copyright notice and this permission notice shall be included in
+ all copies or substantial portions of the Software.
+
+ THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+ THE SOFTWARE.
+---------------------------------------------------------------------------*/
+
+use std::io;
+use std::any::Any;
+use std::fmt;
+use std::error;
+use std::sync::mpsc::{
+  SendError,
+  RecvError,
+  RecvTimeoutError
+};
+
+/// The error type for tasks, streams and io operations in smoke.
+#[derive(Debug)]
+pub enum Error {
+  /// A task panicked while running. Carries the panic message.
+  Panicked(String),
+  /// A task was cancelled before it could produce a result.
+  Cancelled,
+  /// A operation did not complete within its allotted time.
+  Timeout,
+  /// A underlying io operation failed.
+  Io(io::Error),
+  /// A value could not be sent as the receiving end was disconnected.
+  Send,
+  /// A value could not be received as the sending end was disconnected.
+  Recv,
+  /// A peer sent data that could not be understood.
+  Protocol(String),
+  /// A task completed without sending a value or error. Carries 
+  /// the name of the task, if named.
+  NoResult(Option<String>),
+  /// A task's deadline passed before it could complete.
+  DeadlineExceeded,
+  /// A task was rejected by a scheduler as its queue was full.
+  Rejected
+}
+
+impl Error {
+  /// Creates a panicked error from the payload of a caught panic.
+  pub(crate) fn from_panic(payload: Box<Any + Send>) -> Error {
+    let message = match payload.downcast::<String>() {
+      Ok(message)  => *message,
+      Err(payload) => match payload.downcast::<&'static str>() {
+        Ok(message) => message.to_string(),
+        Err(_)      => "unknown panic payload".to_string()
+      }
+    };
+    Error::Panicked(message)
+  }
+}
+
+impl Clone for Error {
+  /// Clones this error. As io::Error is not clonable, io
+  /// errors are cloned by their kind and message.
+  fn clone(&self) -> Error {
+    match *self {
+      Error::Panicked(ref message) => Error::Panicked(message.clone()),
+      Error::Cancelled             => Error::Cancelled,
+      Error::Timeout               => Error::Timeout,
+      Error::Io(ref error)         => Error::Io(io::Error::new(error.kind(), error.to_string())),
+      Error::Send                  => Error::Send,
+      Error::Recv                  => Error::Recv,
+      Error::Protocol(ref message) => Error::Protocol(message.clone()),
+      Error::NoResult(ref name)    => Error::NoResult(name.clone()),
+      Error::DeadlineExceeded      => Error::DeadlineExceeded,
+      Error::Rejected              => Error::Rejected
+    }
+  }
+}
+
+impl fmt::Display for Error {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match *self {
+      Error::Panicked(ref message)    => write!(f, "task panicked: {}", message),
+      Error::Cancelled                => write!(f, "task cancelled"),
+      Error::Timeout                  => write!(f, "operation timed out"),
+      Error::Io(ref error)            => write!(f, "io error: {}", error),
+      Error::Send                     => write!(f, "sending on a disconnected channel"),
+      Error::Recv                     => write!(f, "receiving on a disconnected channel"),
+      Error::Protocol(ref message)    => write!(f, "protocol error: {}", message),
+      Error::NoResult(Some(ref name)) => write!(f, "task '{}' completed without a result", name),
+      Error::NoResult(None)           => write!(f, "task completed without a result"),
+      Error::DeadlineExceeded         => write!(f, "deadline exceeded"),
+      Error::Rejected                 => write!(f, "task rejected by a full scheduler queue")
+    }
+  }
+}
+
+impl error::Error for Error {
+  fn source(&self) -> Option<&(error::Error + 'static)> {
+    match *self {
+      Error::Io(ref error) => Some(error),
+      _                    => None
+    }
+  }
+}
+
+impl From<io::Error> for Error {
+  fn from(error: io::Error) -> Error {
+    Error::Io(error)
+  }
+}
+
+impl<T> From<SendError<T>> for Error {
+  fn from(_: SendError<T>) -> Error {
+    Error::Send
+  }
+}
+
+impl From<RecvError> for Error {
+  fn from(_: RecvError) -> Error {
+    Error::Recv
+  }
+}
+
+impl From<RecvTimeoutError> for Error {
+  fn from(error: RecvTimeoutError) -> Error {
+    match error {
+      RecvTimeoutError::Timeout      => Error::Timeout,
+      RecvTimeoutError::Disconnected => Error::Recv
+    }
+  }
+}
@@ -0,0 +1,166 @@
+/*--------------------------------------------------------------------------
+ smoke-rs
+
+ The MIT License (MIT)
+
+ Copyright (c) 2016 Haydn Paterson (sinclair) <haydn.developer@gmail.com>
+
+ Permission is hereby granted, free of charge, to any person obtaining a copy
+ of this software and associated documentation files (the "Software"), to deal
+ in the Software without restriction, including without limitation the rights
+ to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ copies of the Software, and to permit persons to whom the Software is
+ furnished to do so, subject to the following conditions:
+
+ The above copyright notice and this permission notice shall be included in
+ all copies or substantial portions of the Software.
+
+ THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+ THE SOFTWARE.
+---------------------------------------------------------------------------*/
+
+use std::io;
+use std::mem;
+use super::super::async::Stream;
+use super::super::error::Error;
+
+/// Adds operations over streams of bytes, such as those read from 
+/// std::io::Read::to_stream(), reframing chunks of arbitrary size into
+/// records. Records spanning chunk boundaries are buffered until whole.
+pub trait ByteStream {
+  
+  /// Streams lines, split after each newline. The newline is kept, as
+  /// with to_line_stream(). A last line without a newline is emitted
+  /// when the stream ends. The stream fails if a line is not UTF-8.
+  ///
+  /// #Example
+  /// ```
+  /// use smoke::async::ToStream;
+  /// use smoke::io::ByteStream;
+  /// 
+  /// let chunks = vec![b"he".to_vec(), b"llo\nwor".to_vec(), b"ld".to_vec()].into_iter().to_stream();
+  /// let lines  = chunks.lines().read().iter().collect::<Vec<_>>();
+  /// assert_eq!(lines, vec!["hello\n", "world"]);
+  /// ```
+  fn lines(self) -> Stream<String>;
+  
+  /// Streams records separated by the given delimiter. The delimiter 
+  /// is not kept. A last record without a delimiter is emitted when 
+  /// the stream ends, if not empty.
+  ///
+  /// #Example
+  /// ```
+  /// use smoke::async::ToStream;
+  /// use smoke::io::ByteStream;
+  /// 
+  /// let chunks  = vec![vec![1, 0, 2], vec![3, 0]].into_iter().to_stream();
+  /// let records = chunks.split(0).read().iter().collect::<Vec<_>>();
+  /// assert_eq!(records, vec![vec![1], vec![2, 3]]);
+  /// ```
+  fn split(self, delimiter: u8) -> Stream<Vec<u8>>;
+  
+  /// Streams frames of the given size. A last frame shorter than the
+  /// size is emitted when the stream ends, if not empty.
+  ///
+  /// #Example
+  /// ```
+  /// use smoke::async::ToStream;
+  /// use smoke::io::ByteStream;
+  /// 
+  /// let chunks = vec![vec![1, 2, 3], vec![4, 5]].into_iter().to_stream();
+  /// let frames = chunks.frames(2).read().iter().collect::<Vec<_>>();
+  /// assert_eq!(frames, vec![vec![1, 2], vec![3, 4], vec![5]]);
+  /// ```
+  fn frames(self, size: usize) -> Stream<Vec<u8>>;
+}
+
+impl ByteStream for Stream<Vec<u8>> {
+  
+  /// Streams lines, split after each newline.
+  fn lines(self) -> Stream<String> {
+      Stream::output(move |sender| {
+        let mut buf = Vec::new();
+        let receiver = self.read();
+        for chunk in receiver.iter() {
+          let mut start = 0;
+          for index in 0..chunk.len() {
+            if chunk[index] == b'\n' {
+              buf.extend_from_slice(&chunk[start..index + 1]);
+              start = index + 1;
+              match String::from_utf8(mem::replace(&mut buf, Vec::new())) {
+                Ok(line)   => try!(sender.send(line)),
+                Err(error) => return sender.fail(Error::from(io::Error::new(io::ErrorKind::InvalidData, error)))
+              }
+            }
+          }
+          buf.extend_from_slice(&chunk[start..]);
+        }
+        // partial records are not emitted from a failed stream.
+        if let Some(error) = receiver.error() {
+          return sender.fail(error);
+        }
+        if buf.len() > 0 {
+          match String::from_utf8(buf) {
+            Ok(line)   => try!(sender.send(line)),
+            Err(error) => return sender.fail(Error::from(io::Error::new(io::ErrorKind::InvalidData, error)))
+          }
+        } Ok(())
+      })
+  }
+  
+  /// Streams records separated by the given delimiter.
+  fn split(self, delimiter: u8) -> Stream<Vec<u8>> {
+      Stream::output(move |sender| {
+        let mut buf = Vec::new();
+        let receiver = self.read();
+        for chunk in receiver.iter() {
+          let mut start = 0;
+          for index in 0..chunk.len() {
+            if chunk[index] == delimiter {
+              buf.extend_from_slice(&chunk[start..index]);
+              start = index + 1;
+              try!(sender.send(mem::replace(&mut buf, Vec::new())));
+            }
+          }
+          buf.extend_from_slice(&chunk[start..]);
+        }
+        if let Some(error) = receiver.error() {
+          return sender.fail(error);
+        }
+        if buf.len() > 0 {
+          try!(sender.send(buf));
+        } Ok(())
+      })
+  }
+  
+  /// Streams frames of the given size.
+  fn frames(self, size: usize) -> Stream<Vec<u8>> {
+      let size = size.max(1);
+      Stream::output(move |sender| {
+        let mut buf = Vec::with_capacity(size);
+        let receiver = self.read();
+        for chunk in receiver.iter() {
+          let mut chunk = &chunk[..];
+          while chunk.len() > 0 {
+            let take = (size - buf.len()).min(chunk.len());
+            buf.extend_from_slice(&chunk[..take]);
+            chunk = &chunk[take..];
+            if buf.len() == size {
+              try!(sender.send(mem::replace(&mut buf, Vec::with_capacity(size))));
+            }
+          }
+        }
+        if let Some(error) = receiver.error() {
+          return sender.fail(error);
+        }
+        if buf.len() > 0 {
+          try!(sender.send(buf));
+        } Ok(())
+      })
+  }
+}
@@ -25,5 +25,7 @@
 ---------------------------------------------------------------------------*/
 
 pub mod read;
+pub mod bytes;
 
-pub use self::read::Read;
\ No newline at end of file
+pub use self::read::Read;
+pub use self::bytes::ByteStream;
\ No newline at end of file
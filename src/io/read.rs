@@ -24,14 +24,19 @@
  THE SOFTWARE.
 ---------------------------------------------------------------------------*/
 
+use std::mem;
 use std::sync::Mutex;
-use std::io::{Read as StdRead, BufRead, BufReader};
-use super::super::async::Stream;
+use std::io::{Read as StdRead, BufRead, BufReader, ErrorKind};
+use super::super::async::{context, Stream};
+use super::super::error::Error;
+use super::super::metrics;
 
 /// Adds asynchronous operations over the std::io::Read trait.
 pub trait Read : StdRead {
   
-  /// Streams bytes until EOF.
+  /// Streams bytes until EOF. The stream fails if reading fails. 
+  /// Streams created within a task with a deadline fail once the 
+  /// deadline has passed.
   ///
   /// #Example
   /// ```
@@ -46,7 +51,9 @@ pub trait Read : StdRead {
   /// ```
   fn to_stream(self: Self, size: usize) -> Stream<Vec<u8>>;
   
-  /// Stream lines until EOF.
+  /// Stream lines until EOF. The stream fails if reading fails. 
+  /// Streams created within a task with a deadline fail once the 
+  /// deadline has passed.
   ///
   /// #Example
   /// ```
@@ -65,33 +72,69 @@ impl<R: StdRead + Send + 'static> Read for R {
   
   /// Stream bytes until EOF.
   fn to_stream(self: Self, bufsize: usize) -> Stream<Vec<u8>> {
-      let reader = Mutex::new(self);
+      let reader   = Mutex::new(self);
+      let deadline = context::deadline();
       Stream::output(move |sender| {
+        let _context   = context::enter(deadline);
         let mut reader = reader.lock().unwrap();
         let mut buf    = vec![0; bufsize];
+        log_debug!("Read: byte stream opened");
         loop {
-          let read = reader.read(&mut buf).unwrap();
+          if context::is_expired() {
+            log_debug!("Read: byte stream deadline exceeded");
+            return sender.fail(Error::DeadlineExceeded);
+          }
+          let read = match reader.read(&mut buf) {
+            Ok(read) => read,
+            Err(ref error) if error.kind() == ErrorKind::Interrupted => continue,
+            Err(error) => {
+              log_debug!("Read: byte stream failed: {}", error);
+              return sender.fail(Error::from(error));
+            }
+          };
           if read > 0 {
+            metrics::BYTES_READ.add(read);
+            log_trace!("Read: {} bytes read", read);
             try!(sender.send(buf[0..read].to_vec()));
           } else {
             break;
           }
-        } Ok(())    
+        } 
+        log_debug!("Read: byte stream reached EOF");
+        Ok(())    
       })
   }
   
   /// Stream lines until EOF.
   fn to_line_stream(self: Self) -> Stream<String> {
-      let reader = Mutex::new(Some(self));
+      let reader   = Mutex::new(Some(self));
+      let deadline = context::deadline();
       Stream::output(move |sender| {
+        let _context   = context::enter(deadline);
         let mut reader = reader.lock().unwrap();
         let reader     = reader.take();
         let mut reader = BufReader::new(reader.unwrap());
         let mut buf    = String::new();
-        while reader.read_line(&mut buf).unwrap() > 0 {
-            try!(sender.send(buf.clone()));
-            buf.clear();
-        } Ok(())    
+        log_debug!("Read: line stream opened");
+        loop {
+            match reader.read_line(&mut buf) {
+              Ok(0)      => break,
+              Ok(_)      => {},
+              Err(ref error) if error.kind() == ErrorKind::Interrupted => continue,
+              Err(error) => {
+                log_debug!("Read: line stream failed: {}", error);
+                return sender.fail(Error::from(error));
+              }
+            }
+            if context::is_expired() {
+              log_debug!("Read: line stream deadline exceeded");
+              return sender.fail(Error::DeadlineExceeded);
+            }
+            metrics::BYTES_READ.add(buf.len());
+            try!(sender.send(mem::replace(&mut buf, String::new())));
+        } 
+        log_debug!("Read: line stream reached EOF");
+        Ok(())    
       })
   }  
 }
\ No newline at end of file
@@ -24,9 +24,39 @@
  THE SOFTWARE.
 ---------------------------------------------------------------------------*/
 
+#[cfg(feature = "log")]
+#[macro_use]
+extern crate log;
+
+#[cfg(feature = "codec")]
+extern crate serde;
+
+#[cfg(feature = "codec")]
+extern crate serde_json;
+
+#[cfg(feature = "affinity")]
+extern crate libc;
+
+#[macro_use]
+mod macros;
+
+/// Provides the error type shared across smoke.
+pub mod error;
+
+pub use self::error::Error;
+
 /// Provides task, stream and scheduling primitives.
 pub mod async;
 
 
 /// Provides extension traits over IO.
-pub mod io;
\ No newline at end of file
+pub mod io;
+
+/// Provides encoding of typed messages over byte streams.
+#[cfg(feature = "codec")]
+pub mod codec;
+
+/// Provides counters gathered from schedulers, streams and io.
+pub mod metrics;
+
+pub use self::metrics::metrics;
\ No newline at end of file
@@ -0,0 +1,50 @@
+/*--------------------------------------------------------------------------
+ smoke-rs
+
+ The MIT License (MIT)
+
+ Copyright (c) 2016 Haydn Paterson (sinclair) <haydn.developer@gmail.com>
+
+ Permission is hereby granted, free of charge, to any person obtaining a copy
+ of this software and associated documentation files (the "Software"), to deal
+ in the Software without restriction, including without limitation the rights
+ to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ copies of the Software, and to permit persons to whom the Software is
+ furnished to do so, subject to the following conditions:
+
+ The above copyright notice and this permission notice shall be included in
+ all copies or substantial portions of the Software.
+
+ THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+ THE SOFTWARE.
+---------------------------------------------------------------------------*/
+
+// Internal logging macros. When the crate is built with the
+// `log` feature, these forward to the log crate using the
+// calling module as the target (e.g. `smoke::async::scheduling`).
+// Without the feature, they compile to nothing.
+
+#[cfg(feature = "log")]
+macro_rules! log_debug {
+  ($($arg:tt)*) => { debug!($($arg)*) }
+}
+
+#[cfg(not(feature = "log"))]
+macro_rules! log_debug {
+  ($($arg:tt)*) => { {} }
+}
+
+#[cfg(feature = "log")]
+macro_rules! log_trace {
+  ($($arg:tt)*) => { trace!($($arg)*) }
+}
+
+#[cfg(not(feature = "log"))]
+macro_rules! log_trace {
+  ($($arg:tt)*) => { {} }
+}
@@ -0,0 +1,236 @@
+/*--------------------------------------------------------------------------
+ smoke-rs
+
+ The MIT License (MIT)
+
+ Copyright (c) 2016 Haydn Paterson (sinclair) <haydn.developer@gmail.com>
+
+ Permission is hereby granted, free of charge, to any person obtaining a copy
+ of this software and associated documentation files (the "Software"), to deal
+ in the Software without restriction, including without limitation the rights
+ to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ copies of the Software, and to permit persons to whom the Software is
+ furnished to do so, subject to the following conditions:
+
+ The above copyright notice and this permission notice shall be included in
+ all copies or substantial portions of the Software.
+
+ THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+ THE SOFTWARE.
+---------------------------------------------------------------------------*/
+
+use std::thread;
+use std::time::Duration;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use super::async::Stream;
+
+/// A atomic counter used to gather metrics.
+pub(crate) struct Counter {
+  value: AtomicUsize
+}
+impl Counter {
+  /// Creates a new counter initialized to zero.
+  pub const fn new() -> Counter {
+    Counter { value: AtomicUsize::new(0) }
+  }
+  /// Increments this counter by one.
+  pub fn increment(&self) {
+    self.value.fetch_add(1, Ordering::Relaxed);
+  }
+  /// Decrements this counter by one.
+  pub fn decrement(&self) {
+    self.value.fetch_sub(1, Ordering::Relaxed);
+  }
+  /// Adds the given amount to this counter.
+  pub fn add(&self, amount: usize) {
+    self.value.fetch_add(amount, Ordering::Relaxed);
+  }
+  /// Gets the current value of this counter.
+  pub fn get(&self) -> usize {
+    self.value.load(Ordering::Relaxed)
+  }
+}
+
+pub(crate) static TASKS_SCHEDULED:   Counter = Counter::new();
+pub(crate) static TASKS_EXECUTED:    Counter = Counter::new();
+pub(crate) static TASKS_QUEUED:      Counter = Counter::new();
+pub(crate) static STREAMS_STARTED:   Counter = Counter::new();
+pub(crate) static STREAMS_COMPLETED: Counter = Counter::new();
+pub(crate) static DELAYS_STARTED:    Counter = Counter::new();
+pub(crate) static BYTES_READ:        Counter = Counter::new();
+
+/// A point in time snapshot of the counters gathered by smoke.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Metrics {
+  /// The number of tasks submitted to schedulers.
+  pub tasks_scheduled:   usize,
+  /// The number of tasks schedulers have finished executing.
+  pub tasks_executed:    usize,
+  /// The number of tasks waiting in threadpool scheduler queues.
+  pub tasks_queued:      usize,
+  /// The number of stream stages started.
+  pub streams_started:   usize,
+  /// The number of stream stages completed.
+  pub streams_completed: usize,
+  /// The number of delay timers started.
+  pub delays_started:    usize,
+  /// The number of bytes read by io streams.
+  pub bytes_read:        usize
+}
+
+/// Returns a snapshot of the metrics gathered so far.
+///
+/// # Example
+/// ```
+/// use std::time::Duration;
+/// use smoke::async::Task;
+///
+/// Task::delay_for(Duration::from_millis(1)).wait().unwrap();
+/// let metrics = smoke::metrics();
+/// assert!(metrics.tasks_executed > 0);
+/// ```
+pub fn metrics() -> Metrics {
+  Metrics {
+    tasks_scheduled:   TASKS_SCHEDULED.get(),
+    tasks_executed:    TASKS_EXECUTED.get(),
+    tasks_queued:      TASKS_QUEUED.get(),
+    streams_started:   STREAMS_STARTED.get(),
+    streams_completed: STREAMS_COMPLETED.get(),
+    delays_started:    DELAYS_STARTED.get(),
+    bytes_read:        BYTES_READ.get()
+  }
+}
+
+/// Counters gathered by a metered stream.
+pub(crate) struct Meter {
+  elements:     Counter,
+  bytes:        Counter,
+  recv_blocked: Counter,
+  send_blocked: Counter
+}
+impl Meter {
+  /// Records an element of the given size in bytes.
+  pub fn element(&self, bytes: usize) {
+    self.elements.increment();
+    self.bytes.add(bytes);
+  }
+  /// Records time spent waiting on the source stream.
+  pub fn recv_blocked(&self, duration: Duration) {
+    self.recv_blocked.add(micros(duration));
+  }
+  /// Records time spent waiting on the reader of the stream.
+  pub fn send_blocked(&self, duration: Duration) {
+    self.send_blocked.add(micros(duration));
+  }
+  /// Returns a snapshot of this meter.
+  fn snapshot(&self, name: &str) -> StreamMetrics {
+    StreamMetrics {
+      name:         name.to_string(),
+      elements:     self.elements.get(),
+      bytes:        self.bytes.get(),
+      recv_blocked: Duration::from_micros(self.recv_blocked.get() as u64),
+      send_blocked: Duration::from_micros(self.send_blocked.get() as u64)
+    }
+  }
+}
+
+/// Returns the duration in whole microseconds.
+fn micros(duration: Duration) -> usize {
+  duration.as_secs() as usize * 1_000_000 + duration.subsec_micros() as usize
+}
+
+/// The meters of metered streams, by name.
+static METERS: Mutex<Vec<(String, Arc<Meter>)>> = Mutex::new(Vec::new());
+
+/// Returns the meter with the given name, creating it if needed.
+pub(crate) fn meter(name: &str) -> Arc<Meter> {
+  let mut meters = METERS.lock().unwrap();
+  if let Some(&(_, ref meter)) = meters.iter().find(|&&(ref key, _)| key == name) {
+    return meter.clone();
+  }
+  let meter = Arc::new(Meter {
+    elements:     Counter::new(),
+    bytes:        Counter::new(),
+    recv_blocked: Counter::new(),
+    send_blocked: Counter::new()
+  });
+  meters.push((name.to_string(), meter.clone()));
+  meter
+}
+
+/// A point in time snapshot of the counters gathered by a metered 
+/// stream, created with Stream::metered(). Streams metered with the
+/// same name share counters. 
+///
+/// Time blocked on recv is time spent waiting for the source stream,
+/// and time blocked on send is time spent waiting for the reader, so
+/// the stage before a stream mostly blocked on recv is likely the 
+/// bottleneck of the pipeline.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StreamMetrics {
+  /// The name of the metered stream.
+  pub name:         String,
+  /// The number of elements emitted.
+  pub elements:     usize,
+  /// The number of bytes emitted, for streams metered with a size.
+  pub bytes:        usize,
+  /// The time spent waiting on the source stream.
+  pub recv_blocked: Duration,
+  /// The time spent waiting on the reader of the stream.
+  pub send_blocked: Duration
+}
+
+/// Returns a snapshot of the metrics of each metered stream.
+///
+/// # Example
+/// ```
+/// use smoke::async::Stream;
+///
+/// for _ in Stream::range(0, 4).metered("numbers").read() {}
+/// let numbers = smoke::metrics::streams().into_iter()
+///                                        .find(|stream| stream.name == "numbers")
+///                                        .unwrap();
+/// assert_eq!(numbers.elements, 4);
+/// ```
+pub fn streams() -> Vec<StreamMetrics> {
+  METERS.lock().unwrap()
+        .iter()
+        .map(|&(ref name, ref meter)| meter.snapshot(name))
+        .collect()
+}
+
+/// Returns a snapshot of the metrics of the metered stream with the
+/// given name, if any.
+pub fn stream(name: &str) -> Option<StreamMetrics> {
+  METERS.lock().unwrap()
+        .iter()
+        .find(|&&(ref key, _)| key == name)
+        .map(|&(ref name, ref meter)| meter.snapshot(name))
+}
+
+/// Creates a stream that emits a metrics snapshot immediately,
+/// and then again on each interval. The stream ends when the
+/// reader is dropped.
+///
+/// # Example
+/// ```
+/// use std::time::Duration;
+///
+/// let receiver = smoke::metrics::subscribe(Duration::from_millis(100)).read();
+/// let metrics  = receiver.recv().unwrap();
+/// println!("{:?}", metrics);
+/// ```
+pub fn subscribe(interval: Duration) -> Stream<Metrics> {
+  Stream::output(move |sender| {
+    loop {
+      try!(sender.send(metrics()));
+      thread::sleep(interval);
+    }
+  })
+}
@@ -0,0 +1,81 @@
+use smoke::async::{handle, Task, ThreadScheduler};
+use std::time::Duration;
+
+fn delayed(delay: u64, value: i32) -> Task<i32> {
+  Task::delay_for(Duration::from_millis(delay)).map(move |_| value)
+}
+
+#[test]
+fn select() {
+  let handles = vec![
+    delayed(100, 1).schedule(ThreadScheduler),
+    delayed(1,   2).schedule(ThreadScheduler),
+    delayed(100, 3).schedule(ThreadScheduler)
+  ];
+  let (index, result, remaining) = handle::select(handles);
+  assert_eq!(index, 1);
+  assert_eq!(result.unwrap(), 2);
+  let results = handle::wait_all(remaining).into_iter()
+                       .map(|result| result.unwrap())
+                       .collect::<Vec<_>>();
+  assert_eq!(results, vec![1, 3]);
+}
+
+#[test]
+fn select_remaining() {
+  use std::time::Instant;
+  let mut handles = vec![
+    delayed(60, 1).schedule(ThreadScheduler),
+    delayed(20, 2).schedule(ThreadScheduler),
+    delayed(40, 3).schedule(ThreadScheduler)
+  ];
+  let started    = Instant::now();
+  let mut values = Vec::new();
+  while handles.len() > 0 {
+    let (_, result, remaining) = handle::select(handles);
+    values.push(result.unwrap());
+    handles = remaining;
+  }
+  assert_eq!(values, vec![2, 3, 1]);
+  assert!(started.elapsed() < Duration::from_millis(100));
+}
+
+#[test]
+fn select_ready() {
+  let handles = vec![
+    delayed(1000, 1).schedule(ThreadScheduler),
+    Task::from_value(2).schedule(ThreadScheduler)
+  ];
+  let (index, result, remaining) = handle::select(handles);
+  assert_eq!((index, result.unwrap()), (1, 2));
+  assert!(remaining[0].try_wait().is_none());
+}
+
+#[test]
+fn select_failure() {
+  let handles = vec![
+    delayed(100, 1).schedule(ThreadScheduler),
+    Task::<i32>::new(|_| Ok(())).schedule(ThreadScheduler)
+  ];
+  let (index, result, _) = handle::select(handles);
+  assert_eq!(index, 1);
+  assert!(result.is_err());
+}
+
+#[test]
+#[should_panic]
+fn select_empty() {
+  let _ = handle::select::<i32, smoke::Error>(vec![]);
+}
+
+#[test]
+fn wait_all() {
+  let handles = vec![
+    delayed(20, 1).schedule(ThreadScheduler),
+    delayed(1,  2).schedule(ThreadScheduler)
+  ];
+  let results = handle::wait_all(handles).into_iter()
+                       .map(|result| result.unwrap())
+                       .collect::<Vec<_>>();
+  assert_eq!(results, vec![1, 2]);
+}
@@ -0,0 +1,55 @@
+use smoke::async::{Task, LocalTask, SyncScheduler};
+use std::rc::Rc;
+use std::cell::RefCell;
+
+#[test]
+fn wait() {
+  let task = LocalTask::new(|sender| sender.send(Rc::new(10)));
+  assert_eq!(*task.wait().unwrap(), 10);
+}
+
+#[test]
+fn map() {
+  let task = LocalTask::new(|sender| sender.send(Rc::new(10)))
+                       .map(|value| Rc::new(*value.unwrap() + 1));
+  assert_eq!(*task.wait().unwrap(), 11);
+}
+
+#[test]
+fn then() {
+  let log  = Rc::new(RefCell::new(Vec::new()));
+  let a    = log.clone();
+  let b    = log.clone();
+  let task = LocalTask::new(move |sender| {
+    a.borrow_mut().push(1);
+    sender.send(1)
+  }).then(move |n| LocalTask::new(move |sender| {
+    b.borrow_mut().push(2);
+    sender.send(n + 1)
+  }));
+  assert_eq!(task.wait().unwrap(), 2);
+  assert_eq!(*log.borrow(), vec![1, 2]);
+}
+
+#[test]
+fn panic() {
+  let task = LocalTask::<i32>::new(|_| panic!("boom"));
+  assert!(task.wait().is_err());
+  let handle = SyncScheduler.run_local(LocalTask::<i32>::new(|_| panic!("boom")));
+  match handle.wait() {
+    Err(smoke::Error::Panicked(message)) => assert_eq!(message, "boom"),
+    result => panic!("unexpected result: {:?}", result)
+  }
+}
+
+#[test]
+fn from_task() {
+  let task = LocalTask::from(Task::from_value(10));
+  assert_eq!(task.wait().unwrap(), 10);
+}
+
+#[test]
+fn run_local() {
+  let handle = SyncScheduler.run_local(LocalTask::new(|sender| sender.send(Rc::new(10))));
+  assert_eq!(*handle.wait().unwrap(), 10);
+}
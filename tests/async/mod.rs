@@ -1,3 +1,10 @@
 pub mod task;
 pub mod stream;
 pub mod scheduling;
+pub mod handle;
+pub mod retry;
+pub mod shared;
+pub mod local;
+pub mod observer;
+pub mod threadpool;
+pub mod scope;
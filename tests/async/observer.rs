@@ -0,0 +1,74 @@
+use smoke::async::{Task, TaskObserver, TaskInfo, TaskSpan, SyncScheduler, ThreadPoolScheduler};
+use smoke::async::observer;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+#[derive(Default)]
+struct Events {
+  scheduled: Vec<&'static str>,
+  started:   Vec<&'static str>,
+  completed: Vec<(&'static str, Duration)>,
+  named:     Vec<String>,
+  spans:     Vec<TaskSpan>
+}
+
+struct Recorder {
+  events: Arc<Mutex<Events>>
+}
+impl TaskObserver for Recorder {
+  fn on_scheduled(&self, info: &TaskInfo) {
+    let mut events = self.events.lock().unwrap();
+    events.scheduled.push(info.scheduler);
+    if let Some(ref name) = info.name {
+      events.named.push(format!("{} {}", name, info));
+    }
+  }
+  fn on_started(&self, info: &TaskInfo) {
+    self.events.lock().unwrap().started.push(info.scheduler);
+  }
+  fn on_completed(&self, info: &TaskInfo, duration: Duration) {
+    self.events.lock().unwrap().completed.push((info.scheduler, duration));
+  }
+  fn on_span(&self, span: &TaskSpan) {
+    self.events.lock().unwrap().spans.push(span.clone());
+  }
+}
+
+// the observer is global, so all observer 
+// assertions are made within a single test.
+#[test]
+fn lifecycle() {
+  let events = Arc::new(Mutex::new(Events::default()));
+  observer::set_observer(Recorder { events: events.clone() });
+  
+  Task::delay_for(Duration::from_millis(20)).schedule(SyncScheduler).wait().unwrap();
+  Task::delay_for(Duration::from_millis(20)).schedule(ThreadPoolScheduler::new(1)).wait().unwrap();
+  let task = Task::from_value(1).named("observed");
+  let id   = task.id();
+  task.wait().unwrap();
+  let task     = Task::from_value(2).named("deadline");
+  let deadline = task.id();
+  let task     = task.with_deadline(Instant::now() + Duration::from_secs(1));
+  assert_eq!(task.id(), deadline);
+  task.schedule(SyncScheduler).wait().unwrap();
+  observer::clear_observer();
+  
+  let events = events.lock().unwrap();
+  for scheduler in &["SyncScheduler", "ThreadPoolScheduler"] {
+    assert!(events.scheduled.contains(scheduler));
+    assert!(events.started.contains(scheduler));
+    assert!(events.completed.iter().any(|&(name, duration)| {
+      name == *scheduler && duration >= Duration::from_millis(20)
+    }));
+  }
+  assert!(events.named.contains(&format!("observed #{} 'observed'", id)));
+  let span = events.spans.iter().find(|span| span.id == id).unwrap();
+  assert_eq!(span.name, Some("observed".to_string()));
+  assert!(span.enqueued <= span.started && span.started <= span.finished);
+  // tasks keep their id once a deadline is attached.
+  assert!(events.named.contains(&format!("deadline #{} 'deadline'", deadline)));
+  assert!(events.spans.iter().any(|span| span.id == deadline && span.scheduler == "SyncScheduler"));
+  assert!(events.spans.iter().any(|span| {
+    span.scheduler == "ThreadPoolScheduler" && span.duration() >= Duration::from_millis(20)
+  }));
+}
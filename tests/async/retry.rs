@@ -0,0 +1,26 @@
+use smoke::async::{RetryPolicy, Backoff};
+use std::time::Duration;
+
+#[test]
+fn immediate() {
+  let policy = RetryPolicy::immediate(3);
+  assert_eq!(policy.backoff, Backoff::None);
+  assert_eq!(policy.delay(1), Duration::from_millis(0));
+}
+
+#[test]
+fn fixed() {
+  let policy = RetryPolicy::fixed(3, Duration::from_millis(10));
+  assert_eq!(policy.delay(1), Duration::from_millis(10));
+  assert_eq!(policy.delay(3), Duration::from_millis(10));
+}
+
+#[test]
+fn exponential() {
+  let policy = RetryPolicy::exponential(8, Duration::from_millis(10), Duration::from_millis(100));
+  assert_eq!(policy.delay(1), Duration::from_millis(10));
+  assert_eq!(policy.delay(2), Duration::from_millis(20));
+  assert_eq!(policy.delay(3), Duration::from_millis(40));
+  assert_eq!(policy.delay(5), Duration::from_millis(100));
+  assert_eq!(policy.delay(64), Duration::from_millis(100));
+}
@@ -4,13 +4,14 @@ use smoke::async::{
   SyncScheduler, 
   ThreadPoolScheduler
 };
+use std::time::Duration;
 /// creates a task that will pass.
 fn create_ok_task() -> Task<i32> {
-  Task::delay(1).map(|_| 1)
+  Task::delay_for(Duration::from_millis(1)).map(|_| 1)
 }
 /// creates a task that will panic.
 fn create_panic_task() -> Task<i32> {
-  Task::delay(1).map(|_| 1).then(|_| Task::new(|_| {
+  Task::delay_for(Duration::from_millis(1)).map(|_| 1).then(|_| Task::new(|_| {
     panic!("boom")
   }))
 }
@@ -117,4 +118,563 @@ fn thread_pool_scheduler_run_from_task() {
     Ok(result) => assert_eq!(1, result),
     Err(_) => {/* .. */}
   }
-}
\ No newline at end of file
+}
+#[test]
+fn thread_pool_scheduler_priority() {
+  use smoke::async::{Scheduler, Priority};
+  use std::sync::{Arc, Mutex};
+  fn record(order: &Arc<Mutex<Vec<&'static str>>>, name: &'static str, priority: Priority) -> Task<()> {
+    let order = order.clone();
+    Task::new(move |sender| {
+      order.lock().unwrap().push(name);
+      sender.send(())
+    }).with_priority(priority)
+  }
+  let scheduler = ThreadPoolScheduler::new(1);
+  let order     = Arc::new(Mutex::new(Vec::new()));
+  let blocker   = scheduler.run(Task::delay_for(Duration::from_millis(50)));
+  let handles   = vec![
+    scheduler.run(record(&order, "low",    Priority::Low)),
+    scheduler.run(record(&order, "normal", Priority::Normal)),
+    scheduler.run(record(&order, "high",   Priority::High))
+  ];
+  blocker.wait().unwrap();
+  for handle in handles {
+    handle.wait().unwrap();
+  }
+  assert_eq!(*order.lock().unwrap(), vec!["high", "normal", "low"]);
+}
+
+#[test]
+fn scheduler_by_reference() {
+  let scheduler = ThreadPoolScheduler::new(2);
+  let a = create_ok_task().schedule(&scheduler);
+  let b = create_ok_task().schedule(&scheduler);
+  assert_eq!(a.wait().unwrap() + b.wait().unwrap(), 2);
+}
+
+#[test]
+fn task_handle_try_wait() {
+  let handle = Task::delay_for(Duration::from_millis(50)).map(|_| 1).schedule(ThreadScheduler);
+  assert!(handle.try_wait().is_none());
+  let mut result = None;
+  while result.is_none() {
+    result = handle.try_wait();
+  }
+  assert_eq!(result.unwrap().unwrap(), 1);
+  assert!(handle.try_wait().unwrap().is_err());
+}
+
+#[test]
+fn panic_resolves_error() {
+  use smoke::Error;
+  fn boom() -> Task<i32> {
+    Task::new(|_| panic!("boom"))
+  }
+  for result in vec![
+    boom().schedule(SyncScheduler).wait(),
+    boom().schedule(ThreadScheduler).wait(),
+    boom().schedule(ThreadPoolScheduler::new(1)).wait()
+  ] {
+    match result {
+      Err(Error::Panicked(message)) => assert_eq!(message, "boom"),
+      result => panic!("unexpected result: {:?}", result)
+    }
+  }
+}
+
+#[test]
+fn panic_keeps_pool_thread() {
+  let scheduler = ThreadPoolScheduler::new(1);
+  let result    = Task::<i32>::new(|_| panic!("boom")).schedule(&scheduler).wait();
+  assert!(result.is_err());
+  assert_eq!(create_ok_task().schedule(&scheduler).wait().unwrap(), 1);
+}
+
+#[test]
+fn thread_pool_scheduler_blocking_tasks() {
+  use std::time::Instant;
+  let scheduler = ThreadPoolScheduler::new(1);
+  let started   = Instant::now();
+  let blocking  = (0..4).map(|_| {
+    Task::delay_for(Duration::from_millis(100)).blocking().schedule(&scheduler)
+  }).collect::<Vec<_>>();
+  // the single compute thread is not held by the blocking tasks.
+  create_ok_task().schedule(&scheduler).wait().unwrap();
+  assert!(started.elapsed() < Duration::from_millis(100));
+  for handle in blocking {
+    handle.wait().unwrap();
+  }
+  assert!(started.elapsed() < Duration::from_millis(400));
+}
+
+#[test]
+fn default_scheduler() {
+  use smoke::Error;
+  use smoke::async::{Scheduler, TaskHandle, Stream, set_default_scheduler};
+  use std::sync::Arc;
+  use std::sync::atomic::{AtomicUsize, Ordering};
+  struct Counting {
+    count: Arc<AtomicUsize>
+  }
+  impl Scheduler for Counting {
+    fn run<T, E>(&self, task: Task<T, E>) -> TaskHandle<T, E> where T: Send + 'static, E: From<Error> + Send + 'static {
+      self.count.fetch_add(1, Ordering::SeqCst);
+      ThreadScheduler.run(task)
+    }
+  }
+  let count = Arc::new(AtomicUsize::new(0));
+  set_default_scheduler(Counting { count: count.clone() });
+  assert_eq!(create_ok_task().named("spawned").spawn().wait().unwrap(), 1);
+  assert_eq!(Stream::range(0, 4).spawn().iter().collect::<Vec<_>>(), vec![0, 1, 2, 3]);
+  assert!(count.load(Ordering::SeqCst) >= 2);
+}
+
+#[test]
+fn thread_pool_scheduler_builder() {
+  use std::thread;
+  fn name() -> Task<Option<String>> {
+    Task::new(|sender| sender.send(thread::current().name().map(|name| name.to_string())))
+  }
+  let scheduler = ThreadPoolScheduler::builder()
+                    .threads(2)
+                    .name_prefix("smoke-test")
+                    .stack_size(4 * 1024 * 1024)
+                    .build();
+  assert_eq!(name().schedule(&scheduler).wait().unwrap(), Some("smoke-test-worker".to_string()));
+  assert_eq!(name().blocking().schedule(&scheduler).wait().unwrap(), Some("smoke-test-blocking".to_string()));
+  // deep recursion needs the larger stack.
+  fn depth(n: usize) -> usize {
+    let buffer = [n as u8; 1024];
+    if n == 0 { buffer[0] as usize } else { depth(n - 1) + buffer[1] as usize / 255 }
+  }
+  let recursed = Task::new(|sender| sender.send(depth(2048))).schedule(&scheduler);
+  assert!(recursed.wait().is_ok());
+}
+
+#[test]
+fn task_error_does_not_panic() {
+  use smoke::Error;
+  use std::sync::mpsc::SendError;
+  fn unsent() -> Task<i32> {
+    Task::new(|_| Err(SendError(1)))
+  }
+  // the sync scheduler runs the task on this thread.
+  match unsent().schedule(SyncScheduler).wait() {
+    Err(Error::NoResult(_)) => (),
+    result => panic!("unexpected result: {:?}", result)
+  }
+  let scheduler = ThreadPoolScheduler::new(1);
+  for _ in 0 .. 4 {
+    assert!(unsent().schedule(&scheduler).wait().is_err());
+  }
+  assert_eq!(create_ok_task().schedule(&scheduler).wait().unwrap(), 1);
+}
+
+#[test]
+fn priority_scheduler() {
+  use smoke::async::{Scheduler, Priority, PriorityScheduler};
+  use std::sync::{Arc, Mutex};
+  use std::sync::mpsc::channel;
+  use std::thread;
+  fn order(scheduler: PriorityScheduler) -> Vec<&'static str> {
+    let order   = Arc::new(Mutex::new(Vec::new()));
+    let (started, running) = channel();
+    let blocker = scheduler.run(Task::new(move |sender| {
+      started.send(()).unwrap();
+      thread::sleep(Duration::from_millis(50));
+      sender.send(())
+    }));
+    running.recv().unwrap();
+    let handles = (0 .. 3).flat_map(|_| vec![("low", Priority::Low), ("normal", Priority::Normal), ("high", Priority::High)])
+      .map(|(name, priority)| {
+        let order = order.clone();
+        scheduler.run(Task::new(move |sender| {
+          order.lock().unwrap().push(name);
+          sender.send(())
+        }).with_priority(priority))
+      }).collect::<Vec<_>>();
+    blocker.wait().unwrap();
+    for handle in handles {
+      handle.wait().unwrap();
+    }
+    let order = order.lock().unwrap().clone();
+    order
+  }
+  assert_eq!(order(PriorityScheduler::strict(1)), vec![
+    "high", "high", "high", "normal", "normal", "normal", "low", "low", "low"
+  ]);
+  assert_eq!(order(PriorityScheduler::weighted(1, 2, 1, 1)), vec![
+    "high", "high", "normal", "low", "high", "normal", "low", "normal", "low"
+  ]);
+}
+
+#[test]
+fn fair_scheduler() {
+  use smoke::async::FairScheduler;
+  use std::sync::{Arc, Mutex};
+  use std::sync::mpsc::channel;
+  use std::thread;
+  let scheduler = FairScheduler::new(1);
+  let order     = Arc::new(Mutex::new(Vec::new()));
+  let (started, running) = channel();
+  let blocker = Task::new(move |sender| {
+    started.send(()).unwrap();
+    thread::sleep(Duration::from_millis(50));
+    sender.send(())
+  }).schedule(&scheduler);
+  running.recv().unwrap();
+  let groups  = vec![("a", scheduler.group("a")), ("b", scheduler.group("b"))];
+  let handles = vec![0, 0, 0, 0, 0, 0, 1, 1].into_iter().map(|index: usize| {
+    let (name, ref group) = groups[index];
+    let order = order.clone();
+    Task::new(move |sender| {
+      order.lock().unwrap().push(name);
+      sender.send(())
+    }).schedule(group)
+  }).collect::<Vec<_>>();
+  blocker.wait().unwrap();
+  for handle in handles {
+    handle.wait().unwrap();
+  }
+  assert_eq!(groups[1].1.name(), "b");
+  assert_eq!(*order.lock().unwrap(), vec!["a", "b", "a", "b", "a", "a", "a", "a"]);
+}
+
+#[test]
+fn dual_pool_scheduler() {
+  use smoke::async::DualPoolScheduler;
+  use std::time::Instant;
+  let scheduler = DualPoolScheduler::new(1, 2);
+  let started   = Instant::now();
+  let io = (0..4).map(|_| {
+    Task::delay_for(Duration::from_millis(100)).blocking().schedule(&scheduler)
+  }).collect::<Vec<_>>();
+  // the compute thread is not held by the io tasks.
+  create_ok_task().schedule(&scheduler).wait().unwrap();
+  assert!(started.elapsed() < Duration::from_millis(100));
+  for handle in io {
+    handle.wait().unwrap();
+  }
+  // io tasks are bounded to 2 threads, so run in two rounds.
+  assert!(started.elapsed() >= Duration::from_millis(200));
+  // a zero io bound still runs blocking tasks.
+  let scheduler = DualPoolScheduler::new(1, 0);
+  assert_eq!(create_ok_task().blocking().schedule(&scheduler).wait().unwrap(), 1);
+}
+
+#[test]
+fn event_loop_scheduler() {
+  use smoke::async::{Stream, EventLoopScheduler};
+  use std::sync::{Arc, Mutex};
+  use std::thread;
+  let event_loop = EventLoopScheduler::new();
+  let order      = Arc::new(Mutex::new(Vec::new()));
+  let threads    = Arc::new(Mutex::new(Vec::new()));
+  let record     = |name: &'static str| {
+    let order   = order.clone();
+    let threads = threads.clone();
+    move || {
+      order.lock().unwrap().push(name);
+      threads.lock().unwrap().push(thread::current().id());
+    }
+  };
+  event_loop.set_timeout(Duration::from_millis(60), record("timeout 60"));
+  event_loop.set_timeout(Duration::from_millis(20), || panic!("boom"));
+  event_loop.set_timeout(Duration::from_millis(30), record("timeout 30"));
+  let task = record("task");
+  Task::new(move |sender| { task(); sender.send(()) }).schedule(&event_loop).wait().unwrap();
+  let each = record("element");
+  event_loop.for_each(Stream::range(0, 2), move |_| each()).wait().unwrap();
+  thread::sleep(Duration::from_millis(100));
+  assert_eq!(*order.lock().unwrap(), vec!["task", "element", "element", "timeout 30", "timeout 60"]);
+  let threads = threads.lock().unwrap();
+  assert!(threads.iter().all(|id| *id == threads[0]));
+  assert!(threads[0] != thread::current().id());
+}
+
+#[test]
+fn event_loop_scheduler_stop() {
+  use smoke::async::{Stream, EventLoopScheduler};
+  let event_loop = EventLoopScheduler::new();
+  let blocker    = Task::delay_for(Duration::from_millis(50)).schedule(&event_loop);
+  let waiting    = Task::from_value(1).schedule(&event_loop);
+  let stream     = event_loop.for_each(Stream::range(0, 10), |_| ());
+  event_loop.stop();
+  assert!(event_loop.is_stopped());
+  // the blocker may have started before the event loop was stopped.
+  let _ = blocker.wait();
+  assert!(waiting.wait().is_err());
+  assert!(stream.wait().is_err());
+  assert!(Task::from_value(1).schedule(&event_loop).wait().is_err());
+}
+
+#[test]
+fn thread_pool_scheduler_queue_bound() {
+  use smoke::Error;
+  use smoke::async::QueuePolicy;
+  use std::sync::mpsc::channel;
+  use std::thread;
+  use std::time::Instant;
+  fn blocked(policy: QueuePolicy) -> (ThreadPoolScheduler, smoke::async::TaskHandle<()>) {
+    let scheduler = ThreadPoolScheduler::builder().threads(1).queue_bound(2, policy).build();
+    let (started, running) = channel();
+    let blocker = Task::new(move |sender| {
+      started.send(()).unwrap();
+      thread::sleep(Duration::from_millis(50));
+      sender.send(())
+    }).schedule(&scheduler);
+    running.recv().unwrap();
+    (scheduler, blocker)
+  }
+  fn rejected(result: Result<i32, Error>) -> bool {
+    match result { Err(Error::Rejected) => true, _ => false }
+  }
+  // reject
+  let (scheduler, blocker) = blocked(QueuePolicy::Reject);
+  let handles = (0..3).map(|n| Task::from_value(n).schedule(&scheduler)).collect::<Vec<_>>();
+  blocker.wait().unwrap();
+  let results = handles.into_iter().map(|handle| handle.wait()).collect::<Vec<_>>();
+  assert_eq!(results[0].as_ref().unwrap(), &0);
+  assert_eq!(results[1].as_ref().unwrap(), &1);
+  assert!(rejected(results.into_iter().nth(2).unwrap()));
+  // drop oldest
+  let (scheduler, blocker) = blocked(QueuePolicy::DropOldest);
+  let handles = (0..3).map(|n| Task::from_value(n).schedule(&scheduler)).collect::<Vec<_>>();
+  blocker.wait().unwrap();
+  let mut results = handles.into_iter().map(|handle| handle.wait());
+  assert!(rejected(results.next().unwrap()));
+  assert_eq!(results.map(|result| result.unwrap()).collect::<Vec<_>>(), vec![1, 2]);
+  // block
+  let (scheduler, blocker) = blocked(QueuePolicy::Block);
+  let started = Instant::now();
+  let handles = (0..3).map(|n| Task::from_value(n).schedule(&scheduler)).collect::<Vec<_>>();
+  assert!(started.elapsed() >= Duration::from_millis(30));
+  blocker.wait().unwrap();
+  assert_eq!(handles.into_iter().map(|handle| handle.wait().unwrap()).collect::<Vec<_>>(), vec![0, 1, 2]);
+  // tasks scheduled by a worker onto its own full queue run on the worker.
+  let scheduler = ThreadPoolScheduler::builder().threads(1).queue_bound(1, QueuePolicy::Block).build();
+  let inner     = scheduler.clone();
+  let outer     = Task::new(move |sender| {
+    sender.send((0..3).map(|n| Task::from_value(n).schedule(&inner)).collect::<Vec<_>>())
+  }).schedule(&scheduler);
+  let (done, finished) = channel();
+  thread::spawn(move || done.send(outer.wait().unwrap()).unwrap());
+  let handles = finished.recv_timeout(Duration::from_secs(2)).unwrap();
+  assert_eq!(handles.into_iter().map(|handle| handle.wait().unwrap()).collect::<Vec<_>>(), vec![0, 1, 2]);
+}
+
+#[test]
+fn affinity_scheduler() {
+  use smoke::async::AffinityScheduler;
+  use std::thread;
+  fn name() -> Task<String> {
+    Task::new(|sender| sender.send(thread::current().name().unwrap().to_string()))
+  }
+  let scheduler = AffinityScheduler::new(2);
+  assert_eq!(scheduler.threads(), 2);
+  for _ in 0..4 {
+    assert_eq!(name().schedule(&scheduler.worker(1)).wait().unwrap(), "smoke-affinity-1");
+  }
+  let names = (0..4).map(|_| name().schedule(&scheduler).wait().unwrap()).collect::<Vec<_>>();
+  assert_eq!(names, vec!["smoke-affinity-0", "smoke-affinity-1", "smoke-affinity-0", "smoke-affinity-1"]);
+  let worker = scheduler.worker(3);
+  assert_eq!(worker.index(), 1);
+  assert_eq!(worker.is_pinned(), Some(cfg!(all(feature = "affinity", target_os = "linux"))));
+}
+
+#[test]
+fn affinity_scheduler_zero_threads() {
+  use smoke::async::AffinityScheduler;
+  let scheduler = AffinityScheduler::new(0);
+  assert_eq!(scheduler.threads(), 1);
+  assert_eq!(create_ok_task().schedule(&scheduler).wait().unwrap(), 1);
+  assert_eq!(create_ok_task().schedule(&scheduler.worker(5)).wait().unwrap(), 1);
+}
+
+#[test]
+fn run_after() {
+  use smoke::async::Scheduler;
+  use std::sync::{Arc, Mutex};
+  use std::time::Instant;
+  let scheduler = ThreadPoolScheduler::new(1);
+  let order     = Arc::new(Mutex::new(Vec::new()));
+  let started   = Instant::now();
+  let handles   = vec![60, 20, 40].into_iter().map(|millis| {
+    let order = order.clone();
+    scheduler.run_after(Duration::from_millis(millis), Task::new(move |sender| {
+      order.lock().unwrap().push(millis);
+      sender.send(millis)
+    }))
+  }).collect::<Vec<_>>();
+  assert!(started.elapsed() < Duration::from_millis(20));
+  let results = handles.into_iter().map(|handle| handle.wait().unwrap()).collect::<Vec<_>>();
+  assert!(started.elapsed() >= Duration::from_millis(60));
+  assert_eq!(results, vec![60, 20, 40]);
+  assert_eq!(*order.lock().unwrap(), vec![20, 40, 60]);
+  assert_eq!(SyncScheduler.run_after(Duration::from_millis(1), create_ok_task()).wait().unwrap(), 1);
+}
+
+#[test]
+fn run_after_slow_sync_scheduler() {
+  use smoke::async::Scheduler;
+  use std::thread;
+  use std::time::Instant;
+  let started = Instant::now();
+  let slow    = SyncScheduler.run_after(Duration::from_millis(10), Task::new(|sender| {
+    thread::sleep(Duration::from_millis(300));
+    sender.send(())
+  }));
+  let fast    = SyncScheduler.run_after(Duration::from_millis(20), Task::from_value(1));
+  assert_eq!(fast.wait().unwrap(), 1);
+  assert!(started.elapsed() < Duration::from_millis(200));
+  slow.wait().unwrap();
+  assert!(started.elapsed() >= Duration::from_millis(300));
+}
+
+#[test]
+fn run_at_fixed_rate() {
+  use smoke::async::Scheduler;
+  use std::sync::{Arc, Mutex};
+  use std::thread;
+  use std::time::Instant;
+  let scheduler = ThreadPoolScheduler::new(2);
+  let runs      = Arc::new(Mutex::new(Vec::new()));
+  let started   = Instant::now();
+  let recorded  = runs.clone();
+  let recurring = scheduler.run_at_fixed_rate(Duration::from_millis(20), move || {
+    let recorded = recorded.clone();
+    Task::new(move |sender| {
+      recorded.lock().unwrap().push(started.elapsed());
+      sender.send(())
+    })
+  });
+  thread::sleep(Duration::from_millis(110));
+  recurring.cancel();
+  assert!(recurring.is_cancelled());
+  let count = recurring.runs();
+  assert!(count >= 4 && count <= 6);
+  thread::sleep(Duration::from_millis(60));
+  assert_eq!(recurring.runs(), count);
+  let runs = runs.lock().unwrap();
+  assert_eq!(runs.len(), count);
+  assert!(runs[0] >= Duration::from_millis(20));
+}
+
+#[test]
+fn thread_pool_scheduler_set_threads() {
+  use std::time::Instant;
+  let scheduler = ThreadPoolScheduler::new(1);
+  let delayed   = || Task::delay_for(Duration::from_millis(50)).schedule(&scheduler);
+  let started   = Instant::now();
+  let handles   = (0..4).map(|_| delayed()).collect::<Vec<_>>();
+  scheduler.set_threads(4);
+  assert_eq!(scheduler.threads(), 4);
+  for handle in handles {
+    handle.wait().unwrap();
+  }
+  // the tasks waiting were run on the threads added.
+  assert!(started.elapsed() < Duration::from_millis(150));
+  scheduler.set_threads(0);
+  assert_eq!(scheduler.threads(), 1);
+  let started = Instant::now();
+  let handles = (0..2).map(|_| delayed()).collect::<Vec<_>>();
+  for handle in handles {
+    handle.wait().unwrap();
+  }
+  assert!(started.elapsed() >= Duration::from_millis(100));
+}
+
+#[test]
+fn thread_pool_scheduler_hooks() {
+  use std::sync::{Arc, Mutex};
+  use std::sync::atomic::{AtomicUsize, Ordering};
+  use std::sync::mpsc::channel;
+  use std::thread;
+  let scheduler = ThreadPoolScheduler::new(1);
+  let saturated = Arc::new(Mutex::new(Vec::new()));
+  let idle      = Arc::new(AtomicUsize::new(0));
+  let (queued, counted) = (saturated.clone(), idle.clone());
+  scheduler.on_saturated(move |n| queued.lock().unwrap().push(n));
+  scheduler.on_idle(move || { counted.fetch_add(1, Ordering::SeqCst); });
+  let (started, running) = channel();
+  let blocker = Task::new(move |sender| {
+    started.send(()).unwrap();
+    thread::sleep(Duration::from_millis(50));
+    sender.send(())
+  }).schedule(&scheduler);
+  running.recv().unwrap();
+  let handles = (0..3).map(|_| create_ok_task().schedule(&scheduler)).collect::<Vec<_>>();
+  assert_eq!(*saturated.lock().unwrap(), vec![1, 2, 3]);
+  assert_eq!(idle.load(Ordering::SeqCst), 0);
+  blocker.wait().unwrap();
+  for handle in handles {
+    handle.wait().unwrap();
+  }
+  thread::sleep(Duration::from_millis(20));
+  assert_eq!(idle.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn thread_pool_scheduler_on_panic() {
+  use std::sync::{Arc, Mutex};
+  let scheduler = ThreadPoolScheduler::new(1);
+  let panics    = Arc::new(Mutex::new(Vec::new()));
+  let recorded  = panics.clone();
+  scheduler.on_panic(move |name, payload| {
+    let message = payload.downcast_ref::<&str>().map(|message| message.to_string());
+    recorded.lock().unwrap().push((name.map(|name| name.to_string()), message));
+  });
+  let task: Task<i32> = Task::new(|_| panic!("boom"));
+  assert!(task.named("exploding").schedule(&scheduler).wait().is_err());
+  assert_eq!(create_ok_task().schedule(&scheduler).wait().unwrap(), 1);
+  assert_eq!(*panics.lock().unwrap(), vec![(Some("exploding".to_string()), Some("boom".to_string()))]);
+}
+
+#[test]
+fn thread_pool_scheduler_shutdown_now() {
+  use std::sync::mpsc::channel;
+  use std::thread;
+  let scheduler = ThreadPoolScheduler::new(1);
+  let (started, running) = channel();
+  let blocker = Task::new(move |sender| {
+    started.send(()).unwrap();
+    thread::sleep(Duration::from_millis(50));
+    sender.send(0)
+  }).schedule(&scheduler);
+  running.recv().unwrap();
+  let handles   = (0..3).map(|_| create_ok_task().schedule(&scheduler)).collect::<Vec<_>>();
+  let unstarted = scheduler.shutdown_now();
+  assert_eq!(unstarted.len(), 3);
+  assert!(create_ok_task().schedule(&scheduler).wait().is_err());
+  assert_eq!(blocker.wait().unwrap(), 0);
+  for task in unstarted {
+    task.schedule(SyncScheduler).wait().unwrap();
+  }
+  for handle in handles {
+    assert_eq!(handle.wait().unwrap(), 1);
+  }
+}
+
+#[test]
+fn thread_pool_scheduler_shutdown_graceful() {
+  let scheduler = ThreadPoolScheduler::new(1);
+  let handles   = (0..3).map(|_| create_ok_task().schedule(&scheduler)).collect::<Vec<_>>();
+  assert_eq!(scheduler.shutdown_graceful(Duration::from_secs(5)).len(), 0);
+  for handle in handles {
+    assert_eq!(handle.wait().unwrap(), 1);
+  }
+  assert!(create_ok_task().schedule(&scheduler).wait().is_err());
+}
+
+#[test]
+fn thread_pool_scheduler_shutdown_graceful_timeout() {
+  let scheduler = ThreadPoolScheduler::new(1);
+  let delayed   = Task::delay_for(Duration::from_millis(100)).schedule(&scheduler);
+  let waiting   = create_ok_task().schedule(&scheduler);
+  let unstarted = scheduler.shutdown_graceful(Duration::from_millis(10));
+  assert_eq!(unstarted.len(), 1);
+  delayed.wait().unwrap();
+  drop(unstarted);
+  assert!(waiting.wait().is_err());
+}
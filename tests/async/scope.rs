@@ -0,0 +1,94 @@
+use smoke::Error;
+use smoke::async::{Task, TaskScope};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+#[test]
+fn joins_children() {
+  let count   = Arc::new(AtomicUsize::new(0));
+  let counter = count.clone();
+  let task    = Task::scope(move |scope| {
+    for _ in 0..4 {
+      let counter = counter.clone();
+      scope.run(Task::delay_for(Duration::from_millis(20)).map(move |_| {
+        counter.fetch_add(1, Ordering::SeqCst);
+      }));
+    }
+    Task::from_value(())
+  });
+  task.wait().unwrap();
+  assert_eq!(count.load(Ordering::SeqCst), 4);
+}
+
+#[test]
+fn joins_children_on_failure() {
+  let count   = Arc::new(AtomicUsize::new(0));
+  let counter = count.clone();
+  let task    = Task::<()>::scope(move |scope| {
+    scope.run(Task::delay_for(Duration::from_millis(20)).map(move |_| {
+      counter.fetch_add(1, Ordering::SeqCst);
+    }));
+    Task::err(Error::Timeout)
+  });
+  assert!(task.wait().is_err());
+  assert_eq!(count.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn cancelled_children_do_not_start() {
+  let scope = TaskScope::new();
+  scope.cancel();
+  match scope.run(Task::from_value(10)).wait() {
+    Err(Error::Cancelled) => {},
+    result => panic!("unexpected result: {:?}", result)
+  }
+}
+
+#[test]
+fn children_observe_cancellation() {
+  let task = Task::scope(|scope| {
+    let child = scope.clone();
+    scope.run(Task::new(move |sender| {
+      while !child.is_cancelled() {
+        std::thread::sleep(Duration::from_millis(1));
+      }
+      sender.send(())
+    }));
+    Task::from_value(10)
+  });
+  assert_eq!(task.wait().unwrap(), 10);
+}
+
+#[test]
+fn scheduler_scope() {
+  use smoke::async::{Scheduler, ThreadPoolScheduler, SyncScheduler};
+  use std::sync::Mutex;
+  use std::thread;
+  let scheduler = ThreadPoolScheduler::new(2);
+  let words     = vec!["smoke".to_string(), "on".to_string(), "the".to_string(), "water".to_string()];
+  let completed = Mutex::new(Vec::new());
+  let lengths   = scheduler.scope(|scope| {
+    let handles = words.iter().map(|word| {
+      let completed = &completed;
+      scope.run(move || {
+        thread::sleep(Duration::from_millis(10));
+        completed.lock().unwrap().push(word.as_str());
+        word.len()
+      })
+    }).collect::<Vec<_>>();
+    // not waited on, but completes before the scope returns.
+    scope.run(|| {
+      thread::sleep(Duration::from_millis(50));
+      completed.lock().unwrap().push("last");
+    });
+    handles.into_iter().map(|handle| handle.wait().unwrap()).collect::<Vec<_>>()
+  });
+  assert_eq!(lengths, vec![5, 2, 3, 5]);
+  assert_eq!(completed.lock().unwrap().len(), 5);
+  let result = SyncScheduler.scope(|scope| scope.run(|| -> i32 { panic!("boom") }).wait());
+  match result {
+    Err(Error::Panicked(message)) => assert_eq!(message, "boom"),
+    result => panic!("unexpected result: {:?}", result)
+  }
+}
@@ -0,0 +1,45 @@
+use smoke::async::Task;
+use std::thread;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+#[test]
+fn wait() {
+  let shared = Task::new(|sender| sender.send(10)).shared();
+  assert_eq!(shared.wait().unwrap(), 10);
+  assert_eq!(shared.wait().unwrap(), 10);
+}
+
+#[test]
+fn single_execution() {
+  let count   = Arc::new(AtomicUsize::new(0));
+  let counter = count.clone();
+  let shared  = Task::delay_for(Duration::from_millis(10)).map(move |_| {
+    counter.fetch_add(1, Ordering::SeqCst);
+    10
+  }).shared();
+  let handles = (0..4).map(|_| {
+    let shared = shared.clone();
+    thread::spawn(move || shared.wait().unwrap())
+  }).collect::<Vec<_>>();
+  for handle in handles {
+    assert_eq!(handle.join().unwrap(), 10);
+  }
+  assert_eq!(count.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn shared_error() {
+  let shared = Task::<i32>::new(|_| panic!("boom")).shared();
+  assert!(shared.wait().is_err());
+  assert!(shared.clone().wait().is_err());
+}
+
+#[test]
+fn task() {
+  let shared = Task::new(|sender| sender.send(10)).shared();
+  let task   = shared.task().map(|n| n.unwrap() + 1);
+  assert_eq!(task.wait().unwrap(), 11);
+  assert_eq!(shared.wait().unwrap(), 10);
+}
@@ -70,6 +70,27 @@ fn filter() {
   } assert_eq!(2, acc);
 }
 
+#[test]
+fn fused_stages() {
+  use std::sync::{Arc, Mutex};
+  use std::thread;
+  let threads = Arc::new(Mutex::new(Vec::new()));
+  let (mapped, filtered) = (threads.clone(), threads.clone());
+  let receiver = Stream::output(|sender| {
+                   try!(sender.send(1));
+                   try!(sender.send(2));
+                   sender.fail(smoke::Error::Timeout)
+                 })
+                 .map(move |n| { mapped.lock().unwrap().push(thread::current().id()); n * 10 })
+                 .filter(move |_| { filtered.lock().unwrap().push(thread::current().id()); true })
+                 .read();
+  assert_eq!(receiver.iter().collect::<Vec<_>>(), vec![10, 20]);
+  assert!(receiver.error().is_some());
+  let threads = threads.lock().unwrap();
+  assert_eq!(threads.len(), 4);
+  assert!(threads.iter().all(|id| *id == threads[0]));
+}
+
 #[test]
 fn fold() {
   fn stream() -> Stream<i32> {
@@ -89,4 +110,796 @@ fn to_stream() {
   for n in (0 .. 10).to_stream().read() {
     assert_eq!(n, idx); idx += 1;
   }
-}
\ No newline at end of file
+}
+#[test]
+fn from_task() {
+  use smoke::async::Task;
+  let stream = Stream::from_task(Task::from_value(vec![1, 2, 3]));
+  assert_eq!(stream.read().iter().collect::<Vec<_>>(), vec![1, 2, 3]);
+  let receiver = Stream::from_task(Task::<Vec<i32>>::err(smoke::Error::Cancelled)).read();
+  assert_eq!(receiver.iter().count(), 0);
+  match receiver.error() {
+    Some(smoke::Error::Cancelled) => {/* ok */},
+    error => panic!("unexpected error: {:?}", error)
+  }
+}
+
+#[test]
+fn task_into_stream() {
+  use smoke::async::Task;
+  let stream = Task::from_value(10).into_stream();
+  assert_eq!(stream.read().iter().collect::<Vec<_>>(), vec![10]);
+  let receiver = Task::<i32>::err(smoke::Error::Cancelled).into_stream().read();
+  assert_eq!(receiver.iter().count(), 0);
+  match receiver.error() {
+    Some(smoke::Error::Cancelled) => {/* ok */},
+    error => panic!("unexpected error: {:?}", error)
+  }
+}
+
+#[test]
+fn take() {
+  assert_eq!(Stream::range(0, 100).take(3).read().iter().collect::<Vec<_>>(), vec![0, 1, 2]);
+  assert_eq!(Stream::range(0, 2).take(3).read().iter().collect::<Vec<_>>(), vec![0, 1]);
+  assert_eq!(Stream::range(0, 2).take(0).read().iter().count(), 0);
+}
+
+#[test]
+fn take_ends_producer() {
+  use std::sync::Arc;
+  use std::sync::atomic::{AtomicUsize, Ordering};
+  let produced = Arc::new(AtomicUsize::new(0));
+  let counter  = produced.clone();
+  let (tx, rx) = std::sync::mpsc::channel();
+  let stream   = Stream::output(move |sender| {
+    let result = (0..).map(|n| {
+      counter.fetch_add(1, Ordering::SeqCst);
+      sender.send(n)
+    }).find(|result| result.is_err()).unwrap();
+    tx.send(()).unwrap();
+    result
+  });
+  assert_eq!(stream.map(|n| n * 2).take(3).read().iter().collect::<Vec<_>>(), vec![0, 2, 4]);
+  rx.recv().unwrap();
+  assert!(produced.load(Ordering::SeqCst) < 10);
+}
+
+#[test]
+fn skip() {
+  assert_eq!(Stream::range(0, 5).skip(3).read().iter().collect::<Vec<_>>(), vec![3, 4]);
+  assert_eq!(Stream::range(0, 2).skip(3).read().iter().count(), 0);
+}
+
+#[test]
+fn filter_none() {
+  assert_eq!(Stream::range(0, 10).filter(|_| false).read().iter().count(), 0);
+}
+
+#[test]
+fn take_while() {
+  let stream = Stream::range(0, 100).take_while(|n| *n < 3);
+  assert_eq!(stream.read().iter().collect::<Vec<_>>(), vec![0, 1, 2]);
+}
+
+#[test]
+fn skip_while() {
+  let stream = Stream::range(0, 6).skip_while(|n| *n < 3).filter(|n| *n != 4);
+  assert_eq!(stream.read().iter().collect::<Vec<_>>(), vec![3, 5]);
+}
+
+#[test]
+fn zip() {
+  let pairs = Stream::range(0, 3).zip(Stream::range(10, 100)).read().iter().collect::<Vec<_>>();
+  assert_eq!(pairs, vec![(0, 10), (1, 11), (2, 12)]);
+  let pairs = Stream::range(0, 100).zip(Stream::range(10, 12)).read().iter().collect::<Vec<_>>();
+  assert_eq!(pairs, vec![(0, 10), (1, 11)]);
+}
+
+#[test]
+fn buffer_time() {
+  use std::thread;
+  use std::time::Duration;
+  let stream = Stream::output(|sender| {
+    try!(sender.send(1));
+    try!(sender.send(2));
+    thread::sleep(Duration::from_millis(100));
+    try!(sender.send(3));
+    Ok(())
+  });
+  let batches = stream.buffer_time(Duration::from_millis(50)).read().iter().collect::<Vec<_>>();
+  assert_eq!(batches, vec![vec![1, 2], vec![3]]);
+}
+
+#[test]
+fn scan() {
+  let averages = Stream::range(1, 5).scan((0, 0), |state, n| {
+    state.0 += n;
+    state.1 += 1;
+    state.0 as f32 / state.1 as f32
+  });
+  assert_eq!(averages.read().iter().collect::<Vec<_>>(), vec![1.0, 1.5, 2.0, 2.5]);
+}
+
+#[test]
+fn dedup() {
+  let words = vec!["a", "a", "b", "a", "a"].into_iter().to_stream().dedup();
+  assert_eq!(words.read().iter().collect::<Vec<_>>(), vec!["a", "b", "a"]);
+}
+
+#[test]
+fn distinct() {
+  let words = vec!["a", "a", "b", "a", "c"].into_iter().to_stream().distinct();
+  assert_eq!(words.read().iter().collect::<Vec<_>>(), vec!["a", "b", "c"]);
+}
+
+#[test]
+fn group_by() {
+  use std::collections::HashMap;
+  let words  = vec!["apple", "bean", "avocado", "beet", "carrot"].into_iter().to_stream();
+  let groups = words.group_by(|word| word.chars().next().unwrap())
+                    .read()
+                    .iter()
+                    .collect::<Vec<_>>();
+  assert_eq!(groups.iter().map(|group| group.0).collect::<Vec<_>>(), vec!['a', 'b', 'c']);
+  let groups = groups.into_iter()
+                     .map(|(key, group)| (key, group.read().iter().collect::<Vec<_>>()))
+                     .collect::<HashMap<_, _>>();
+  assert_eq!(groups[&'a'], vec!["apple", "avocado"]);
+  assert_eq!(groups[&'b'], vec!["bean", "beet"]);
+  assert_eq!(groups[&'c'], vec!["carrot"]);
+}
+
+#[test]
+fn concat() {
+  use std::thread;
+  use std::time::Duration;
+  let slow = Stream::output(|sender| {
+    thread::sleep(Duration::from_millis(20));
+    try!(sender.send(0));
+    sender.send(1)
+  });
+  let stream = Stream::concat(vec![slow, Stream::range(2, 4), Stream::range(4, 4)]);
+  assert_eq!(stream.read().iter().collect::<Vec<_>>(), vec![0, 1, 2, 3]);
+}
+
+#[test]
+fn interleave() {
+  let fast   = Stream::range(0, 100);
+  let slow   = Stream::output(|sender| {
+    std::thread::sleep(std::time::Duration::from_millis(10));
+    try!(sender.send(1000));
+    sender.send(1001)
+  });
+  let stream = Stream::interleave(vec![fast, slow]).take(5);
+  assert_eq!(stream.read().iter().collect::<Vec<_>>(), vec![0, 1000, 1, 1001, 2]);
+}
+
+#[test]
+fn throttle() {
+  use std::time::{Duration, Instant};
+  let started = Instant::now();
+  let values  = Stream::range(0, 4).throttle(Duration::from_millis(20)).read().iter().collect::<Vec<_>>();
+  assert_eq!(values, vec![0, 1, 2, 3]);
+  assert!(started.elapsed() >= Duration::from_millis(60));
+}
+
+#[test]
+fn sample() {
+  use std::thread;
+  use std::time::Duration;
+  let stream = Stream::output(|sender| {
+    for n in 0..3 {
+      try!(sender.send(n));
+    }
+    // lands midway through the second interval.
+    thread::sleep(Duration::from_millis(75));
+    for n in 3..6 {
+      try!(sender.send(n));
+    } Ok(())
+  });
+  let samples = stream.sample(Duration::from_millis(50)).read().iter().collect::<Vec<_>>();
+  assert_eq!(samples, vec![2, 5]);
+}
+
+#[test]
+fn timeout() {
+  use std::time::Duration;
+  let values = Stream::range(0, 3).timeout(Duration::from_millis(100)).read().iter().collect::<Vec<_>>();
+  assert_eq!(values, vec![0, 1, 2]);
+  let stalled = Stream::output(|sender| {
+    try!(sender.send(0));
+    std::thread::sleep(Duration::from_millis(200));
+    sender.send(1)
+  });
+  let receiver = stalled.timeout(Duration::from_millis(20)).read();
+  assert_eq!(receiver.iter().collect::<Vec<_>>(), vec![0]);
+  match receiver.error() {
+    Some(smoke::Error::Timeout) => {/* ok */},
+    error => panic!("unexpected error: {:?}", error)
+  }
+}
+
+#[test]
+fn empty_once_repeat() {
+  assert_eq!(Stream::<i32>::empty().read().iter().count(), 0);
+  assert_eq!(Stream::once(1).read().iter().collect::<Vec<_>>(), vec![1]);
+  assert_eq!(Stream::repeat(1).take(4).fold(0, |p, c| p + c).wait().unwrap(), 4);
+}
+
+#[test]
+fn tee() {
+  use std::thread;
+  let branches = Stream::range(0, 100).tee(3);
+  let handles  = branches.into_iter().map(|branch| {
+    thread::spawn(move || branch.fold(0, |p, c| p + c).wait().unwrap())
+  }).collect::<Vec<_>>();
+  for handle in handles {
+    assert_eq!(handle.join().unwrap(), 4950);
+  }
+}
+
+#[test]
+fn tee_dropped_branch() {
+  let mut branches = Stream::range(0, 100).tee(2);
+  drop(branches.remove(1));
+  assert_eq!(branches.remove(0).read().iter().count(), 100);
+}
+
+#[test]
+fn publish() {
+  use std::thread;
+  let publisher = Stream::range(0, 100).publish();
+  let handles   = (0..3).map(|_| publisher.subscribe()).map(|numbers| {
+    thread::spawn(move || numbers.fold(0, |p, c| p + c).wait().unwrap())
+  }).collect::<Vec<_>>();
+  for handle in handles {
+    assert_eq!(handle.join().unwrap(), 4950);
+  }
+  assert_eq!(publisher.subscribe().read().iter().count(), 0);
+}
+
+#[test]
+fn publish_ends_without_subscribers() {
+  use std::sync::Arc;
+  use std::sync::atomic::{AtomicUsize, Ordering};
+  let count     = Arc::new(AtomicUsize::new(0));
+  let counter   = count.clone();
+  let publisher = Stream::range(0, 1000).map(move |n| { counter.fetch_add(1, Ordering::SeqCst); n }).publish();
+  let taken     = publisher.subscribe().take(2).read().iter().count();
+  assert_eq!(taken, 2);
+  assert!(count.load(Ordering::SeqCst) < 1000);
+}
+
+#[test]
+fn on_backpressure() {
+  use std::thread;
+  use std::time::Duration;
+  use smoke::async::Backpressure;
+  fn slow_read(numbers: Stream<i32>) -> Vec<i32> {
+    let receiver = numbers.read();
+    thread::sleep(Duration::from_millis(50));
+    receiver.iter().collect()
+  }
+  let block  = slow_read(Stream::range(0, 1000).on_backpressure(Backpressure::Block));
+  assert_eq!(block.len(), 1000);
+  let latest = slow_read(Stream::range(0, 1000).on_backpressure(Backpressure::Latest));
+  assert!(latest.len() < 1000);
+  assert_eq!(*latest.last().unwrap(), 999);
+  let oldest = slow_read(Stream::range(0, 1000).on_backpressure(Backpressure::DropOldest(10)));
+  assert!(oldest.len() < 1000);
+  assert_eq!(&oldest[oldest.len() - 10..], &(990..1000).collect::<Vec<_>>()[..]);
+  let newest = slow_read(Stream::range(0, 1000).on_backpressure(Backpressure::DropNewest(10)));
+  assert!(newest.len() < 1000);
+  assert_eq!(newest[0], 0);
+}
+
+#[test]
+fn fail() {
+  use smoke::Error;
+  let failing = || Stream::output(|sender| {
+    try!(sender.send(1));
+    try!(sender.fail(Error::Timeout));
+    sender.send(2)
+  });
+  let receiver = failing().read();
+  assert_eq!(receiver.iter().collect::<Vec<_>>(), vec![1]);
+  assert!(receiver.error().is_some());
+  let receiver = Stream::range(0, 2).read();
+  assert_eq!(receiver.iter().count(), 2);
+  assert!(receiver.error().is_none());
+  // errors are forwarded through operators.
+  let receiver = failing().map(|n| n * 2).filter(|_| true).read();
+  assert_eq!(receiver.iter().collect::<Vec<_>>(), vec![2]);
+  assert!(receiver.error().is_some());
+  assert!(failing().fold(0, |p, c| p + c).wait().is_err());
+  assert!(Stream::merge(vec![failing(), Stream::range(0, 2)]).fold(0, |p, c| p + c).wait().is_err());
+}
+
+#[test]
+fn fail_forwarded() {
+  use std::time::Duration;
+  use smoke::Error;
+  let failing = || Stream::output(|sender| {
+    try!(sender.send(1));
+    try!(sender.send(2));
+    sender.fail(Error::Timeout)
+  });
+  let failed = |stream: Stream<i32>| {
+    let receiver = stream.read();
+    let _ = receiver.iter().count();
+    receiver.error().is_some()
+  };
+  assert!(failed(failing().skip(1)));
+  assert!(failed(failing().take(3)));
+  assert!(failed(failing().take_last(1)));
+  assert!(failed(failing().skip_last(1)));
+  assert!(failed(failing().skip_while(|n| *n < 2)));
+  assert!(failed(failing().scan(0, |p, c| { *p += c; *p })));
+  assert!(failed(failing().dedup()));
+  assert!(failed(failing().distinct()));
+  assert!(failed(failing().zip(Stream::range(0, 10)).map(|(n, _)| n)));
+  assert!(failed(failing().batch(4, Duration::from_millis(10)).map(|batch| batch.len() as i32)));
+  assert!(failed(Stream::interleave(vec![failing(), Stream::range(0, 10)])));
+  assert!(failed(Stream::merge_sorted(vec![failing(), Stream::range(0, 10)])));
+  // stages that stop reading early end without failing.
+  assert!(!failed(failing().take(2)));
+  assert!(!failed(failing().take_while(|n| *n < 2)));
+}
+
+#[test]
+fn concat_fail() {
+  use std::sync::Arc;
+  use std::sync::atomic::{AtomicBool, Ordering};
+  use smoke::Error;
+  let started = Arc::new(AtomicBool::new(false));
+  let flag    = started.clone();
+  let second  = Stream::output(move |sender| {
+    flag.store(true, Ordering::SeqCst);
+    sender.send(10)
+  });
+  let first = Stream::output(|sender| {
+    try!(sender.send(1));
+    sender.fail(Error::Timeout)
+  });
+  let receiver = Stream::concat(vec![first, second]).read();
+  assert_eq!(receiver.iter().collect::<Vec<_>>(), vec![1]);
+  match receiver.error() {
+    Some(Error::Timeout) => {/* ok */},
+    error => panic!("unexpected error: {:?}", error)
+  }
+  assert!(!started.load(Ordering::SeqCst));
+}
+
+#[test]
+fn fail_inspected() {
+  use smoke::Error;
+  let recovered = Stream::output(|sender| {
+    let receiver = Stream::<i32>::output(|sender| sender.fail(Error::Timeout)).read();
+    for n in receiver.iter() {
+      try!(sender.send(n));
+    }
+    assert!(receiver.error().is_some());
+    sender.send(10)
+  });
+  let receiver = recovered.read();
+  assert_eq!(receiver.iter().collect::<Vec<_>>(), vec![10]);
+  assert!(receiver.error().is_none());
+}
+
+#[test]
+fn fail_on_panic() {
+  let receiver = Stream::<i32>::output(|_| panic!("boom")).read();
+  assert_eq!(receiver.iter().count(), 0);
+  match receiver.error() {
+    Some(smoke::Error::Panicked(message)) => assert_eq!(message, "boom"),
+    error => panic!("unexpected error: {:?}", error)
+  }
+}
+
+#[test]
+fn first_last_count() {
+  assert_eq!(Stream::range(0, 4).first().wait().unwrap(), Some(0));
+  assert_eq!(Stream::range(0, 4).last().wait().unwrap(), Some(3));
+  assert_eq!(Stream::range(0, 4).count().wait().unwrap(), 4);
+  assert_eq!(Stream::<i32>::empty().first().wait().unwrap(), None);
+  assert_eq!(Stream::<i32>::empty().last().wait().unwrap(), None);
+  assert_eq!(Stream::<i32>::empty().count().wait().unwrap(), 0);
+}
+
+#[test]
+fn first_ends_producer() {
+  use std::sync::Arc;
+  use std::sync::mpsc::channel;
+  use std::sync::atomic::{AtomicUsize, Ordering};
+  let produced = Arc::new(AtomicUsize::new(0));
+  let counter  = produced.clone();
+  let (tx, rx) = channel();
+  let stream   = Stream::output(move |sender| {
+    let result = (0..).map(|n| {
+      counter.fetch_add(1, Ordering::SeqCst);
+      sender.send(n)
+    }).find(|result| result.is_err()).unwrap();
+    tx.send(()).unwrap();
+    result
+  });
+  assert_eq!(stream.first().wait().unwrap(), Some(0));
+  rx.recv().unwrap();
+  assert!(produced.load(Ordering::SeqCst) < 10);
+}
+
+#[test]
+fn any_all_find() {
+  assert_eq!(Stream::range(0, 4).any(|n| *n == 3).wait().unwrap(), true);
+  assert_eq!(Stream::range(0, 4).any(|n| *n == 4).wait().unwrap(), false);
+  assert_eq!(Stream::range(0, 4).all(|n| *n < 4).wait().unwrap(), true);
+  assert_eq!(Stream::range(0, 4).all(|n| *n < 3).wait().unwrap(), false);
+  assert_eq!(Stream::range(0, 4).find(|n| *n > 1).wait().unwrap(), Some(2));
+  assert_eq!(Stream::range(0, 4).find(|n| *n > 4).wait().unwrap(), None);
+  // short circuits on infinite streams.
+  assert_eq!(Stream::repeat(1).any(|n| *n == 1).wait().unwrap(), true);
+  assert_eq!(Stream::repeat(1).all(|n| *n == 2).wait().unwrap(), false);
+  assert_eq!(Stream::repeat(1).find(|n| *n == 1).wait().unwrap(), Some(1));
+}
+
+#[test]
+fn fold_accumulator() {
+  let lines = vec!["a", "bc", "def"].into_iter().map(|s| s.to_string()).to_stream();
+  assert_eq!(lines.fold(0, |p, c| p + c.len()).wait().unwrap(), 6);
+  let numbers = Stream::range(0, 3).fold(Vec::new(), |mut p, c| { p.push(c); p });
+  assert_eq!(numbers.wait().unwrap(), vec![0, 1, 2]);
+}
+
+#[test]
+fn via() {
+  use smoke::Error;
+  use smoke::async::{Task, TaskHandle, Scheduler, ThreadPoolScheduler};
+  use std::sync::Arc;
+  use std::sync::atomic::{AtomicUsize, Ordering};
+  struct Counting {
+    count:     Arc<AtomicUsize>,
+    scheduler: ThreadPoolScheduler
+  }
+  impl Scheduler for Counting {
+    fn run<T, E>(&self, task: Task<T, E>) -> TaskHandle<T, E> where T: Send + 'static, E: From<Error> + Send + 'static {
+      self.count.fetch_add(1, Ordering::SeqCst);
+      self.scheduler.run(task)
+    }
+  }
+  let count     = Arc::new(AtomicUsize::new(0));
+  let scheduler = Counting { count: count.clone(), scheduler: ThreadPoolScheduler::new(8) };
+  let numbers   = Stream::merge(vec![Stream::range(0, 2), Stream::range(2, 4)])
+                    .map(|n| n * 2)
+                    .filter(|n| *n > 2)
+                    .via(scheduler);
+  let mut values = numbers.read().iter().collect::<Vec<_>>();
+  values.sort();
+  assert_eq!(values, vec![4, 6]);
+  // the fused filter and map, merge, its two readers and the two merged streams.
+  assert_eq!(count.load(Ordering::SeqCst), 6);
+}
+
+#[test]
+fn map_concurrent() {
+  use std::thread;
+  use std::time::{Duration, Instant};
+  let started = Instant::now();
+  let values  = Stream::range(0, 8).map_concurrent(4, |n| {
+    // later elements complete first.
+    thread::sleep(Duration::from_millis(50 - n as u64 * 5));
+    n
+  }).read().iter().collect::<Vec<_>>();
+  assert_eq!(values, (0..8).collect::<Vec<_>>());
+  assert!(started.elapsed() < Duration::from_millis(300));
+  let receiver = Stream::range(0, 4).map_concurrent(2, |n| if n == 2 { panic!("boom") } else { n }).read();
+  assert_eq!(receiver.iter().collect::<Vec<_>>(), vec![0, 1]);
+  assert!(receiver.error().is_some());
+}
+
+#[test]
+fn inspect() {
+  use std::sync::{Arc, Mutex};
+  let seen     = Arc::new(Mutex::new(Vec::new()));
+  let recorder = seen.clone();
+  let values   = Stream::range(0, 4).inspect(move |n| recorder.lock().unwrap().push(*n))
+                                    .map(|n| n * 10)
+                                    .read().iter().collect::<Vec<_>>();
+  assert_eq!(values, vec![0, 10, 20, 30]);
+  assert_eq!(*seen.lock().unwrap(), vec![0, 1, 2, 3]);
+}
+
+#[test]
+fn rate_limit() {
+  use std::time::{Duration, Instant};
+  let started  = Instant::now();
+  let receiver = Stream::range(0, 15).rate_limit(10, Duration::from_millis(100)).read();
+  let burst    = (0..10).map(|_| receiver.recv().unwrap()).collect::<Vec<_>>();
+  assert_eq!(burst, (0..10).collect::<Vec<_>>());
+  assert!(started.elapsed() < Duration::from_millis(40));
+  assert_eq!(receiver.iter().count(), 5);
+  assert!(started.elapsed() >= Duration::from_millis(40));
+}
+
+#[test]
+fn merge_sorted() {
+  let a = vec![1, 5, 9].into_iter().to_stream();
+  let b = vec![2, 2, 10].into_iter().to_stream();
+  let c = Stream::<i32>::empty();
+  let d = vec![0, 6].into_iter().to_stream();
+  let merged = Stream::merge_sorted(vec![a, b, c, d]).read().iter().collect::<Vec<_>>();
+  assert_eq!(merged, vec![0, 1, 2, 2, 5, 6, 9, 10]);
+}
+
+#[test]
+fn merge_sorted_fail() {
+  use smoke::Error;
+  let failed   = Stream::<i32>::output(|sender| sender.fail(Error::Timeout));
+  let receiver = Stream::merge_sorted(vec![failed, Stream::repeat(1)]).read();
+  let _ = receiver.iter().take(1000).count();
+  match receiver.error() {
+    Some(Error::Timeout) => {/* ok */},
+    error => panic!("unexpected error: {:?}", error)
+  }
+  let failing = Stream::output(|sender| {
+    try!(sender.send(0));
+    sender.fail(Error::Timeout)
+  });
+  let receiver = Stream::merge_sorted(vec![failing, Stream::repeat(1)]).read();
+  assert!(receiver.iter().take(1000).count() < 1000);
+  assert!(receiver.error().is_some());
+}
+
+#[test]
+fn retry() {
+  use smoke::Error;
+  use std::sync::Arc;
+  use std::sync::atomic::{AtomicUsize, Ordering};
+  let attempts = Arc::new(AtomicUsize::new(0));
+  let counter  = attempts.clone();
+  let numbers  = Stream::retry(3, move || {
+    let attempt = counter.fetch_add(1, Ordering::SeqCst);
+    Stream::output(move |sender| {
+      try!(sender.send(attempt));
+      if attempt < 2 { sender.fail(Error::Timeout) } else { Ok(()) }
+    })
+  });
+  let receiver = numbers.read();
+  assert_eq!(receiver.iter().collect::<Vec<_>>(), vec![0, 1, 2]);
+  assert!(receiver.error().is_none());
+  let failing  = Stream::<i32>::retry(2, || Stream::output(|sender| sender.fail(Error::Timeout)));
+  let receiver = failing.read();
+  assert_eq!(receiver.iter().count(), 0);
+  assert!(receiver.error().is_some());
+}
+
+#[test]
+fn batch() {
+  use std::time::Duration;
+  let slow = Stream::output(|sender| {
+    try!(sender.send(0));
+    try!(sender.send(1));
+    std::thread::sleep(Duration::from_millis(100));
+    try!(sender.send(2));
+    try!(sender.send(3));
+    try!(sender.send(4));
+    sender.send(5)
+  });
+  let batches = slow.batch(3, Duration::from_millis(20)).read().iter().collect::<Vec<_>>();
+  assert_eq!(batches, vec![vec![0, 1], vec![2, 3, 4], vec![5]]);
+}
+
+#[test]
+fn read_controlled() {
+  use std::time::Duration;
+  use std::sync::mpsc::TryRecvError;
+  let (receiver, control) = Stream::range(0, 100).read_controlled();
+  assert_eq!(receiver.recv().unwrap(), 0);
+  control.pause();
+  assert!(control.is_paused());
+  // only elements already in flight are emitted while paused.
+  for _ in 0..2 {
+    std::thread::sleep(Duration::from_millis(20));
+    while receiver.try_recv().is_ok() {}
+  }
+  std::thread::sleep(Duration::from_millis(20));
+  match receiver.try_recv() {
+    Err(TryRecvError::Empty) => {/* ok */},
+    result => panic!("unexpected result: {:?}", result)
+  }
+  control.resume();
+  assert!(receiver.recv().is_ok());
+  control.stop();
+  assert!(control.is_stopped());
+  assert!(receiver.iter().count() < 100);
+}
+
+#[test]
+fn unzip() {
+  use std::thread;
+  let (evens, odds) = Stream::range(0, 5).map(|n| (n * 2, n * 2 + 1)).unzip();
+  let evens = thread::spawn(move || evens.read().iter().collect::<Vec<_>>());
+  let odds  = thread::spawn(move || odds.read().iter().collect::<Vec<_>>());
+  assert_eq!(evens.join().unwrap(), vec![0, 2, 4, 6, 8]);
+  assert_eq!(odds.join().unwrap(), vec![1, 3, 5, 7, 9]);
+  let (keys, values) = Stream::range(0, 5).map(|n| (n, n)).unzip();
+  drop(values);
+  assert_eq!(keys.read().iter().count(), 5);
+}
+
+#[test]
+fn join_by_key() {
+  use std::time::Duration;
+  let requests  = Stream::range(0, 10).map(|n| (n, format!("request {}", n)));
+  let responses = Stream::output(|sender| {
+    for n in (0..10).rev() {
+      if n != 5 {
+        try!(sender.send((n, n * 100)));
+      }
+    } Ok(())
+  });
+  let mut pairs = requests.join_by_key(responses, |r| r.0, |r| r.0, Duration::from_secs(5))
+                          .read()
+                          .iter()
+                          .map(|(request, response)| (request.0, response.1))
+                          .collect::<Vec<_>>();
+  pairs.sort();
+  assert_eq!(pairs, (0..10).filter(|n| *n != 5).map(|n| (n, n * 100)).collect::<Vec<_>>());
+  // elements not matched within the window are discarded.
+  let late = Stream::output(|sender| {
+    std::thread::sleep(Duration::from_millis(100));
+    sender.send(1)
+  });
+  let joined = Stream::once(1).join_by_key(late, |n| *n, |n| *n, Duration::from_millis(10));
+  assert_eq!(joined.read().iter().count(), 0);
+}
+
+#[test]
+fn range_types() {
+  assert_eq!(Stream::range(1u64 << 40, (1u64 << 40) + 2).read().iter().collect::<Vec<_>>(), vec![1u64 << 40, (1u64 << 40) + 1]);
+  assert_eq!(Stream::range(0usize, 3).read().iter().collect::<Vec<_>>(), vec![0, 1, 2]);
+  assert_eq!(Stream::range_step(0i64, 10, 5).read().iter().collect::<Vec<_>>(), vec![0, 5]);
+  assert_eq!(Stream::range_step(0, 3, 0).read().iter().collect::<Vec<_>>(), vec![0, 1, 2]);
+  assert_eq!(Stream::from_range(-2..1).read().iter().collect::<Vec<_>>(), vec![-2, -1, 0]);
+}
+
+#[test]
+fn take_last_skip_last() {
+  assert_eq!(Stream::range(0, 10).take_last(3).read().iter().collect::<Vec<_>>(), vec![7, 8, 9]);
+  assert_eq!(Stream::range(0, 2).take_last(3).read().iter().collect::<Vec<_>>(), vec![0, 1]);
+  assert_eq!(Stream::range(0, 10).take_last(0).read().iter().count(), 0);
+  assert_eq!(Stream::range(0, 10).skip_last(7).read().iter().collect::<Vec<_>>(), vec![0, 1, 2]);
+  assert_eq!(Stream::range(0, 2).skip_last(3).read().iter().count(), 0);
+  assert_eq!(Stream::range(0, 3).skip_last(0).read().iter().count(), 3);
+}
+
+#[test]
+fn delay_elements() {
+  use std::time::{Duration, Instant};
+  let started  = Instant::now();
+  let receiver = Stream::range(0, 4).delay_elements(Duration::from_millis(50)).read();
+  assert_eq!(receiver.recv().unwrap(), 0);
+  assert!(started.elapsed() >= Duration::from_millis(50));
+  assert_eq!(receiver.iter().collect::<Vec<_>>(), vec![1, 2, 3]);
+  // elements are shifted, not delayed cumulatively.
+  assert!(started.elapsed() < Duration::from_millis(150));
+  let started = Instant::now();
+  let values  = Stream::range(0, 3).delay_by(|n| Duration::from_millis(40 - *n as u64 * 20)).read().iter().collect::<Vec<_>>();
+  assert_eq!(values, vec![0, 1, 2]);
+  assert!(started.elapsed() >= Duration::from_millis(40));
+}
+
+#[test]
+fn reduce() {
+  assert_eq!(Stream::range(1, 5).reduce(|p, c| p * c).wait().unwrap(), Some(24));
+  assert_eq!(Stream::once(7).reduce(|p, c| p + c).wait().unwrap(), Some(7));
+  assert_eq!(Stream::<i32>::empty().reduce(|p, c| p + c).wait().unwrap(), None);
+}
+
+#[test]
+fn max_min() {
+  let values = vec![(1, 'a'), (3, 'b'), (2, 'c'), (3, 'd'), (1, 'e')];
+  assert_eq!(values.clone().into_iter().to_stream().max_by(|a, b| a.0.cmp(&b.0)).wait().unwrap(), Some((3, 'd')));
+  assert_eq!(values.clone().into_iter().to_stream().min_by(|a, b| a.0.cmp(&b.0)).wait().unwrap(), Some((1, 'a')));
+  assert_eq!(values.clone().into_iter().to_stream().max_by_key(|v| v.0).wait().unwrap(), Some((3, 'd')));
+  assert_eq!(values.clone().into_iter().to_stream().min_by_key(|v| v.0).wait().unwrap(), Some((1, 'a')));
+  assert_eq!(Stream::<i32>::empty().max_by_key(|v| *v).wait().unwrap(), None);
+}
+
+#[test]
+fn on_error() {
+  use std::sync::{Arc, Mutex};
+  let errors  = Arc::new(Mutex::new(Vec::new()));
+  let handler = errors.clone();
+  let stream  = (0 .. 6).to_stream()
+    .map(|n| if n % 2 == 0 { Ok(n) } else { Err(format!("odd {}", n)) })
+    .on_error(move |error| handler.lock().unwrap().push(error));
+  assert_eq!(stream.read().iter().collect::<Vec<_>>(), vec![0, 2, 4]);
+  assert_eq!(*errors.lock().unwrap(), vec!["odd 1", "odd 3", "odd 5"]);
+}
+
+#[test]
+fn switch() {
+  use std::sync::mpsc::channel;
+  use std::thread;
+  use std::time::Duration;
+  let (streams, receiver) = channel();
+  let outer = Stream::output(move |sender| {
+    for stream in receiver {
+      try!( sender.send(stream) );
+    } Ok(())
+  });
+  let first = Stream::output(|sender| {
+    try!( sender.send(1) );
+    thread::sleep(Duration::from_millis(100));
+    loop { try!( sender.send(2) ); }
+  });
+  let switched = outer.switch().read();
+  streams.send(first).unwrap();
+  assert_eq!(switched.recv().unwrap(), 1);
+  streams.send(Stream::range(10, 13)).unwrap();
+  drop(streams);
+  assert_eq!(switched.iter().collect::<Vec<_>>(), vec![10, 11, 12]);
+  assert!(switched.error().is_none());
+}
+
+#[test]
+fn switch_fails() {
+  use smoke::Error;
+  let streams = Stream::range(0, 2).map(|n| Stream::output(move |sender| {
+    try!( sender.send(n) );
+    sender.fail(Error::Timeout)
+  }));
+  let receiver = streams.switch().read();
+  let _ = receiver.iter().count();
+  match receiver.error() {
+    Some(Error::Timeout) => (),
+    error => panic!("unexpected error: {:?}", error)
+  }
+}
+
+#[test]
+fn stage() {
+  use std::sync::Arc;
+  use std::time::Duration;
+  use std::sync::atomic::{AtomicUsize, Ordering};
+  use std::thread;
+  let produced = Arc::new(AtomicUsize::new(0));
+  let counter  = produced.clone();
+  let numbers  = Stream::range(0, 100).inspect(move |_| { counter.fetch_add(1, Ordering::SeqCst); }).stage(32);
+  let receiver = numbers.read();
+  assert_eq!(receiver.recv().unwrap(), 0);
+  thread::sleep(Duration::from_millis(100));
+  // the source ran ahead of the reader by the bound of the stage.
+  assert!(produced.load(Ordering::SeqCst) >= 32);
+  assert!(produced.load(Ordering::SeqCst) < 40);
+  assert_eq!(receiver.iter().collect::<Vec<_>>(), (1 .. 100).collect::<Vec<_>>());
+}
+
+#[test]
+fn record_replay() {
+  use smoke::Error;
+  use std::time::{Duration, Instant};
+  let source = Stream::output(|sender| {
+    try!( sender.send(1) );
+    std::thread::sleep(Duration::from_millis(50));
+    try!( sender.send(2) );
+    sender.fail(Error::Timeout)
+  });
+  let (stream, recording) = source.record();
+  assert!(!recording.is_completed());
+  let receiver = stream.read();
+  assert_eq!(receiver.iter().collect::<Vec<_>>(), vec![1, 2]);
+  assert!(receiver.error().is_some());
+  assert!(recording.is_completed());
+  let elements = recording.elements();
+  assert!(elements[1].0 - elements[0].0 >= Duration::from_millis(50));
+  for _ in 0 .. 2 {
+    let started  = Instant::now();
+    let replayed = recording.replay().read();
+    assert_eq!(replayed.iter().collect::<Vec<_>>(), vec![1, 2]);
+    assert!(started.elapsed() >= Duration::from_millis(50));
+    match replayed.error() {
+      Some(Error::Timeout) => (),
+      error => panic!("unexpected error: {:?}", error)
+    }
+  }
+}
@@ -1,5 +1,5 @@
-
 use smoke::async::Task;
+use std::time::Duration;
 
 
 
@@ -24,7 +24,7 @@ fn await() {
 #[test]
 fn wait_then() {
   fn increment(value: i32) -> Task<i32> {
-    Task::delay(10).map(move |_| value + 1) 
+    Task::delay_for(Duration::from_millis(10)).map(move |_| value + 1) 
   }
   assert_eq!(4, increment(0)
                 .then(increment)
@@ -37,7 +37,7 @@ fn wait_then() {
 #[test]
 fn async_then() {
   fn increment(value: i32) -> Task<i32> {
-    Task::delay(1).map(move |_| value + 1) 
+    Task::delay_for(Duration::from_millis(1)).map(move |_| value + 1) 
   }
   let result = increment(0)
       .then(increment)
@@ -53,7 +53,7 @@ fn async_then() {
 #[test]
 fn then_result_with_panic() {
   fn increment(value: i32) -> Task<i32> {
-    Task::delay(1).map(move |_| value + 1) 
+    Task::delay_for(Duration::from_millis(1)).map(move |_| value + 1) 
   }
   fn boom(_: i32) -> Task<i32> {
     Task::new(|_| {
@@ -81,4 +81,590 @@ fn wait_no_result_match() {
 fn wait_no_result_unwrap() {
   let task = Task::<i32>::new(|_| { Ok(()) });
   task.wait().unwrap();
-}
\ No newline at end of file
+}
+#[test]
+fn race() {
+  fn query(delay: u64, value: i32) -> Task<i32> {
+    Task::delay_for(Duration::from_millis(delay)).map(move |_| value)
+  }
+  let task = Task::race(vec![
+    query(100, 1),
+    query(1,   2),
+    query(100, 3)
+  ]);
+  assert_eq!(task.wait().unwrap(), 2);
+}
+
+#[test]
+fn race_with_panic() {
+  fn boom() -> Task<i32> {
+    Task::new(|_| {
+      panic!("boom")
+    })
+  }
+  let task = Task::race(vec![
+    boom(),
+    Task::delay_for(Duration::from_millis(10)).map(|_| 1)
+  ]);
+  assert_eq!(task.wait().unwrap(), 1);
+}
+
+#[test]
+fn race_empty() {
+  let task = Task::<i32>::race(vec![]);
+  assert!(task.wait().is_err());
+}
+
+#[test]
+fn retry() {
+  use std::sync::Arc;
+  use std::sync::atomic::{AtomicUsize, Ordering};
+  let attempts = Arc::new(AtomicUsize::new(0));
+  let counter  = attempts.clone();
+  let task = Task::retry(3, move || {
+    let attempt = counter.fetch_add(1, Ordering::SeqCst);
+    Task::new(move |sender| {
+      if attempt < 2 { Ok(()) } else { sender.send(attempt) }
+    })
+  });
+  assert_eq!(task.wait().unwrap(), 2);
+  assert_eq!(attempts.load(Ordering::SeqCst), 3);
+}
+
+#[test]
+fn retry_exhausted() {
+  use std::sync::Arc;
+  use std::sync::atomic::{AtomicUsize, Ordering};
+  let attempts = Arc::new(AtomicUsize::new(0));
+  let counter  = attempts.clone();
+  let task = Task::<i32>::retry(2, move || {
+    counter.fetch_add(1, Ordering::SeqCst);
+    Task::new(|_| Ok(()))
+  });
+  assert!(task.wait().is_err());
+  assert_eq!(attempts.load(Ordering::SeqCst), 3);
+}
+
+#[test]
+fn retry_with() {
+  use std::time::{Duration, Instant};
+  use smoke::async::RetryPolicy;
+  let start  = Instant::now();
+  let policy = RetryPolicy::fixed(2, Duration::from_millis(20));
+  let task   = Task::<i32>::retry_with(policy, || Task::new(|_| Ok(())));
+  assert!(task.wait().is_err());
+  assert!(start.elapsed() >= Duration::from_millis(40));
+}
+
+#[test]
+fn fail() {
+  use smoke::Error;
+  let task = Task::<i32>::new(|sender| sender.fail(Error::Protocol("bad".to_string())));
+  match task.wait() {
+    Err(Error::Protocol(message)) => assert_eq!(message, "bad"),
+    _ => panic!("unexpected result")
+  }
+}
+
+#[derive(Debug, PartialEq)]
+enum CustomError {
+  NotFound,
+  Internal
+}
+impl From<smoke::Error> for CustomError {
+  fn from(_: smoke::Error) -> CustomError {
+    CustomError::Internal
+  }
+}
+
+#[test]
+fn fallible() {
+  let task = Task::<i32, CustomError>::fallible(|sender| sender.fail(CustomError::NotFound));
+  assert_eq!(task.wait(), Err(CustomError::NotFound));
+}
+
+#[test]
+fn fallible_no_result() {
+  let task = Task::<i32, CustomError>::fallible(|_| Ok(()));
+  assert_eq!(task.wait(), Err(CustomError::Internal));
+}
+
+#[test]
+fn then_forwards_error() {
+  let task = Task::<i32, CustomError>::fallible(|sender| sender.send(1))
+    .then(|_| Task::fallible(|sender| sender.fail(CustomError::NotFound)))
+    .then(|n| Task::fallible(move |sender| sender.send(n + 1)));
+  assert_eq!(task.wait(), Err(CustomError::NotFound));
+}
+
+#[test]
+fn all_forwards_error() {
+  let task = Task::all(2, vec![
+    Task::<i32, CustomError>::fallible(|sender| sender.send(1)),
+    Task::<i32, CustomError>::fallible(|sender| sender.fail(CustomError::NotFound))
+  ]);
+  assert_eq!(task.wait(), Err(CustomError::NotFound));
+}
+
+#[test]
+fn map_err() {
+  let task = Task::<i32, CustomError>::fallible(|sender| sender.fail(CustomError::NotFound))
+    .map_err(|error| smoke::Error::Protocol(format!("{:?}", error)));
+  match task.wait() {
+    Err(smoke::Error::Protocol(message)) => assert_eq!(message, "NotFound"),
+    _ => panic!("unexpected result")
+  }
+}
+
+#[test]
+fn map_err_ok() {
+  let task = Task::<i32, CustomError>::fallible(|sender| sender.send(1))
+    .map_err(|_| smoke::Error::Cancelled);
+  assert_eq!(task.wait().unwrap(), 1);
+}
+
+#[test]
+fn recover() {
+  let task = Task::<i32, CustomError>::fallible(|sender| sender.fail(CustomError::NotFound))
+    .recover(|error| {
+      assert_eq!(error, CustomError::NotFound);
+      Task::fallible(|sender| sender.send(10))
+    });
+  assert_eq!(task.wait(), Ok(10));
+}
+
+#[test]
+fn recover_with_panic() {
+  let task = Task::<i32>::new(|_| panic!("boom"))
+    .recover(|_| Task::new(|sender| sender.send(10)));
+  assert_eq!(task.wait().unwrap(), 10);
+}
+
+#[test]
+fn all_stream() {
+  fn delayed(delay: u64, value: i32) -> Task<i32> {
+    Task::delay_for(Duration::from_millis(delay)).map(move |_| value)
+  }
+  let stream = Task::all_stream(4, vec![
+    delayed(60, 1),
+    delayed(1,  2),
+    delayed(30, 3)
+  ]);
+  let results = stream.read().into_iter().collect::<Vec<_>>();
+  assert_eq!(results, vec![2, 3, 1]);
+}
+
+#[test]
+fn all_stream_with_failure() {
+  let stream = Task::all_stream(2, vec![
+    Task::new(|sender| sender.send(1)),
+    Task::new(|sender| sender.fail(smoke::Error::Cancelled)),
+    Task::delay_for(Duration::from_millis(100)).map(|_| 3)
+  ]);
+  let receiver = stream.read();
+  assert!(!receiver.iter().any(|n| n == 3));
+  match receiver.error() {
+    Some(smoke::Error::Cancelled) => {/* ok */},
+    error => panic!("unexpected error: {:?}", error)
+  }
+}
+
+#[test]
+fn all_with_panic() {
+  let task = Task::all(2, vec![
+    Task::new(|sender| sender.send(1)),
+    Task::new(|_| panic!("boom"))
+  ]);
+  assert!(task.wait().is_err());
+}
+
+#[test]
+fn try_all() {
+  let task = Task::try_all(2, vec![
+    Task::<i32>::new(|sender| sender.send(1)),
+    Task::<i32>::new(|sender| sender.send(2))
+  ]);
+  assert_eq!(task.wait().unwrap(), vec![1, 2]);
+}
+
+#[test]
+fn try_all_with_failure() {
+  let task = Task::try_all(2, vec![
+    Task::<i32, CustomError>::fallible(|sender| sender.send(1)),
+    Task::fallible(|_| panic!("boom")),
+    Task::fallible(|sender| sender.fail(CustomError::NotFound))
+  ]);
+  match task.wait() {
+    Err(error) => {
+      assert_eq!(error.index, Some(1));
+      assert_eq!(error.error, CustomError::Internal);
+    },
+    Ok(_) => panic!("unexpected result")
+  }
+}
+
+#[test]
+fn map_after_panic() {
+  let task: Task<i32> = Task::new(|_| panic!("boom"));
+  assert_eq!(task.map(|result| result.is_err()).wait().unwrap(), true);
+}
+
+#[test]
+fn chained_stages() {
+  let task = Task::from_value(1).then(|n| Task::from_value(n + 1))
+                                .map(|n| n.unwrap() * 10)
+                                .map_err(|error| error)
+                                .then(|n| Task::<i32>::err(smoke::Error::Timeout).recover(move |_| Task::from_value(n + 1)));
+  assert_eq!(task.wait().unwrap(), 21);
+}
+
+#[test]
+fn join2() {
+  let task = Task::join2(
+    Task::delay_for(Duration::from_millis(10)).map(|_| 1),
+    Task::delay_for(Duration::from_millis(1)).map(|_| "a")
+  );
+  assert_eq!(task.wait().unwrap(), (1, "a"));
+}
+
+#[test]
+fn join2_with_failure() {
+  let task = Task::join2(
+    Task::new(|sender| sender.send(1)),
+    Task::<i32>::new(|sender| sender.fail(smoke::Error::Timeout))
+  );
+  assert!(task.wait().is_err());
+}
+
+#[test]
+fn join2_on_shared_threads() {
+  use std::thread;
+  let name = || Task::new(|sender| sender.send(thread::current().name().map(|name| name.to_string())));
+  let (a, _) = Task::join2(name(), name()).wait().unwrap();
+  assert_eq!(a, Some("smoke-shared".to_string()));
+}
+
+#[test]
+fn join3() {
+  let task = Task::join3(
+    Task::new(|sender| sender.send(1)),
+    Task::new(|sender| sender.send("a")),
+    Task::new(|sender| sender.send(true))
+  );
+  assert_eq!(task.wait().unwrap(), (1, "a", true));
+}
+
+#[test]
+fn join4() {
+  let task = Task::join4(
+    Task::new(|sender| sender.send(1)),
+    Task::new(|sender| sender.send("a")),
+    Task::new(|sender| sender.send(true)),
+    Task::<i32>::new(|_| panic!("boom"))
+  );
+  assert!(task.wait().is_err());
+}
+
+#[test]
+fn from_value() {
+  assert_eq!(Task::from_value(10).wait().unwrap(), 10);
+}
+
+#[test]
+fn err() {
+  let task = Task::<i32, CustomError>::err(CustomError::NotFound);
+  assert_eq!(task.wait(), Err(CustomError::NotFound));
+}
+
+#[test]
+fn never() {
+  let task = Task::race(vec![
+    Task::never(),
+    Task::delay_for(Duration::from_millis(10)).map(|_| 1)
+  ]);
+  assert_eq!(task.wait().unwrap(), 1);
+}
+
+#[test]
+fn all_settled() {
+  let task = Task::all_settled(2, vec![
+    Task::<i32, CustomError>::fallible(|sender| sender.send(1)),
+    Task::fallible(|sender| sender.fail(CustomError::NotFound)),
+    Task::fallible(|_| panic!("boom")),
+    Task::fallible(|sender| sender.send(4))
+  ]);
+  let results = task.wait().unwrap();
+  assert_eq!(results.len(), 4);
+  assert_eq!(*results[0].as_ref().unwrap(), 1);
+  match results[1] {
+    Err(ref error) => {
+      assert_eq!(error.index, Some(1));
+      assert_eq!(error.error, CustomError::NotFound);
+    },
+    Ok(_) => panic!("unexpected result")
+  }
+  match results[2] {
+    Err(ref error) => assert_eq!(error.index, Some(2)),
+    Ok(_) => panic!("unexpected result")
+  }
+  assert_eq!(*results[3].as_ref().unwrap(), 4);
+}
+
+#[test]
+#[allow(deprecated)]
+fn delay() {
+  use std::time::Instant;
+  let start = Instant::now();
+  Task::delay(10).wait().unwrap();
+  assert!(start.elapsed() >= Duration::from_millis(10));
+}
+
+#[test]
+fn delay_for() {
+  use std::time::Instant;
+  let start = Instant::now();
+  Task::delay_for(Duration::from_millis(10)).wait().unwrap();
+  assert!(start.elapsed() >= Duration::from_millis(10));
+}
+
+#[test]
+fn delay_until() {
+  use std::time::Instant;
+  let deadline = Instant::now() + Duration::from_millis(10);
+  Task::delay_until(deadline).wait().unwrap();
+  assert!(Instant::now() >= deadline);
+}
+
+#[test]
+fn delay_until_passed() {
+  use std::time::Instant;
+  let deadline = Instant::now();
+  Task::delay_until(deadline).wait().unwrap();
+}
+
+#[test]
+fn then_runs_on_resolving_thread() {
+  use std::thread;
+  fn current(_: thread::ThreadId) -> Task<thread::ThreadId> {
+    Task::new(|sender| sender.send(thread::current().id()))
+  }
+  let (first, last) = Task::new(|sender| sender.send(thread::current().id()))
+    .then(current)
+    .then(current)
+    .map(|id| (id.unwrap(), thread::current().id()))
+    .wait()
+    .unwrap();
+  assert_eq!(first, thread::current().id());
+  assert_eq!(last,  thread::current().id());
+}
+
+#[test]
+fn async_on() {
+  use smoke::async::{SyncScheduler, ThreadPoolScheduler};
+  let handle = Task::from_value(10).async_on(SyncScheduler, |result| result.unwrap() + 1);
+  assert_eq!(handle.wait().unwrap(), 11);
+  let scheduler = ThreadPoolScheduler::new(2);
+  let handles   = (0..4).map(|n| Task::from_value(n).async_on(&scheduler, |result| result.unwrap() * 2))
+                        .collect::<Vec<_>>();
+  let results   = handles.into_iter().map(|handle| handle.wait().unwrap()).collect::<Vec<_>>();
+  assert_eq!(results, vec![0, 2, 4, 6]);
+}
+
+#[test]
+fn named() {
+  let task = Task::from_value(10).named("fetch-user");
+  assert_eq!(task.name(), Some("fetch-user"));
+  assert_eq!(task.wait().unwrap(), 10);
+  assert_eq!(Task::from_value(10).name(), None);
+}
+
+#[test]
+fn unique_ids() {
+  let a = Task::from_value(1);
+  let b = Task::from_value(2);
+  assert!(a.id() != b.id());
+  let id = a.id();
+  assert_eq!(a.named("a").id(), id);
+}
+
+#[test]
+fn repeat_until() {
+  use std::sync::Arc;
+  use std::sync::atomic::{AtomicUsize, Ordering};
+  let attempts = Arc::new(AtomicUsize::new(0));
+  let counter  = attempts.clone();
+  let task     = Task::repeat_until(move || {
+    let counter = counter.clone();
+    Task::new(move |sender| sender.send(counter.fetch_add(1, Ordering::SeqCst)))
+  }, |value| *value == 4);
+  assert_eq!(task.wait().unwrap(), 4);
+  assert_eq!(attempts.load(Ordering::SeqCst), 5);
+}
+
+#[test]
+fn repeat_until_forwards_error() {
+  let task = Task::<i32>::repeat_until(|| Task::err(smoke::Error::Cancelled), |_| true);
+  match task.wait() {
+    Err(smoke::Error::Cancelled) => {},
+    result => panic!("unexpected result: {:?}", result)
+  }
+}
+
+#[test]
+fn sequence_runs_in_order() {
+  use std::sync::{Arc, Mutex};
+  let order = Arc::new(Mutex::new(Vec::new()));
+  let tasks = (0..4).map(|n| {
+    let order = order.clone();
+    // earlier tasks take longer, so would finish last if run in parallel.
+    Task::delay_for(Duration::from_millis(20 - n * 5)).map(move |_| {
+      order.lock().unwrap().push(n);
+      n
+    })
+  }).collect();
+  assert_eq!(Task::sequence(tasks).wait().unwrap(), vec![0, 1, 2, 3]);
+  assert_eq!(*order.lock().unwrap(), vec![0, 1, 2, 3]);
+}
+
+#[test]
+fn sequence_stops_on_error() {
+  use std::sync::Arc;
+  use std::sync::atomic::{AtomicUsize, Ordering};
+  let count   = Arc::new(AtomicUsize::new(0));
+  let counter = count.clone();
+  let task    = Task::sequence(vec![
+    Task::from_value(1),
+    Task::err(smoke::Error::Cancelled),
+    Task::new(move |sender| {
+      counter.fetch_add(1, Ordering::SeqCst);
+      sender.send(3)
+    })
+  ]);
+  assert!(task.wait().is_err());
+  assert_eq!(count.load(Ordering::SeqCst), 0);
+}
+
+#[test]
+fn select_returns_remaining() {
+  let task = Task::select(vec![
+    Task::delay_for(Duration::from_millis(50)).map(|_| 1),
+    Task::delay_for(Duration::from_millis(1)).map(|_| 2),
+    Task::delay_for(Duration::from_millis(50)).map(|_| 3)
+  ]);
+  let (value, remaining) = task.wait().unwrap();
+  assert_eq!(value, 2);
+  let values = remaining.into_iter().map(|handle| handle.wait().unwrap()).collect::<Vec<_>>();
+  assert_eq!(values, vec![1, 3]);
+}
+
+#[test]
+fn select_skips_failures() {
+  let task = Task::select(vec![
+    Task::delay_for(Duration::from_millis(20)).map(|_| 1),
+    Task::err(smoke::Error::Cancelled)
+  ]);
+  let (value, remaining) = task.wait().unwrap();
+  assert_eq!(value, 1);
+  assert_eq!(remaining.len(), 0);
+  
+  let task = Task::<i32>::select(vec![Task::err(smoke::Error::Cancelled)]);
+  assert!(task.wait().is_err());
+}
+
+#[test]
+fn unwrap_or() {
+  assert_eq!(Task::<i32>::err(smoke::Error::Timeout).unwrap_or(10).wait().unwrap(), 10);
+  assert_eq!(Task::from_value(1).unwrap_or(10).wait().unwrap(), 1);
+}
+
+#[test]
+fn or_else() {
+  let task = Task::<i32>::err(smoke::Error::Timeout).or_else(|| Task::from_value(10));
+  assert_eq!(task.wait().unwrap(), 10);
+  let task = Task::from_value(1).or_else(|| panic!("fallback should not run"));
+  assert_eq!(task.wait().unwrap(), 1);
+}
+
+#[test]
+fn all_on_shared_scheduler() {
+  use std::sync::Arc;
+  use smoke::async::ThreadPoolScheduler;
+  let scheduler = Arc::new(ThreadPoolScheduler::new(2));
+  for n in 0..4 {
+    let tasks = (0..4).map(|m| Task::from_value(n * m)).collect();
+    let sum: i32 = Task::all_on(scheduler.clone(), tasks).wait().unwrap().iter().sum();
+    assert_eq!(sum, n * 6);
+  }
+}
+
+#[test]
+fn all_nested() {
+  let task = Task::all(2, (0..4).map(|n| {
+    Task::all(2, vec![Task::from_value(n), Task::from_value(n)]).map(|values| {
+      values.unwrap().iter().sum::<i32>()
+    })
+  }).collect());
+  assert_eq!(task.wait().unwrap(), vec![0, 2, 4, 6]);
+}
+
+#[test]
+fn deadline_exceeded_in_chain() {
+  use std::time::Instant;
+  use std::sync::Arc;
+  use std::sync::atomic::{AtomicBool, Ordering};
+  let ran     = Arc::new(AtomicBool::new(false));
+  let flag    = ran.clone();
+  let task    = Task::delay_for(Duration::from_millis(30))
+                  .then(move |_| Task::new(move |sender| {
+                    flag.store(true, Ordering::SeqCst);
+                    sender.send(())
+                  }))
+                  .with_deadline(Instant::now() + Duration::from_millis(10));
+  match task.wait() {
+    Err(smoke::Error::DeadlineExceeded) => {},
+    result => panic!("unexpected result: {:?}", result)
+  }
+  assert!(!ran.load(Ordering::SeqCst));
+}
+
+#[test]
+fn deadline_propagates_to_schedulers() {
+  use std::time::Instant;
+  use smoke::async::{context, ThreadScheduler};
+  let deadline = Instant::now() + Duration::from_secs(10);
+  let task     = Task::new(|sender| {
+    let handle = Task::new(|sender| sender.send(context::deadline())).schedule(ThreadScheduler);
+    sender.send(handle.wait().unwrap())
+  });
+  assert_eq!(task.with_deadline(deadline).wait().unwrap(), Some(deadline));
+  assert_eq!(context::deadline(), None);
+}
+
+#[test]
+fn deadline_keeps_earliest() {
+  use std::time::Instant;
+  use smoke::async::context;
+  let early = Instant::now() + Duration::from_secs(1);
+  let late  = early + Duration::from_secs(1);
+  let task  = Task::new(|sender| sender.send(context::deadline()))
+                .with_deadline(late)
+                .with_deadline(early);
+  assert_eq!(task.wait().unwrap(), Some(early));
+}
+
+#[test]
+fn deadline_met() {
+  use std::time::Instant;
+  let task = Task::delay_for(Duration::from_millis(1))
+               .map(|_| 10)
+               .with_deadline(Instant::now() + Duration::from_secs(10));
+  assert_eq!(task.wait().unwrap(), 10);
+}
+
+#[test]
+fn timed() {
+  let (value, elapsed) = Task::delay_for(Duration::from_millis(20)).map(|_| 1).timed().wait().unwrap();
+  assert_eq!(value, 1);
+  assert!(elapsed >= Duration::from_millis(20));
+  assert!(Task::<i32>::err(smoke::Error::Timeout).timed().wait().is_err());
+}
@@ -0,0 +1,163 @@
+use smoke::async::threadpool::ThreadPool;
+use std::sync::{Arc, Mutex};
+use std::sync::mpsc::channel;
+use std::thread;
+use std::time::Duration;
+
+#[test]
+fn spawn() {
+  let pool     = ThreadPool::new(2);
+  let (tx, rx) = channel();
+  for n in 0..10 {
+    let tx = tx.clone();
+    pool.spawn(move || tx.send(n).unwrap());
+  }
+  assert_eq!(rx.iter().take(10).sum::<i32>(), 45);
+}
+
+#[test]
+fn bound() {
+  let pool     = ThreadPool::new(2);
+  let running  = Arc::new(Mutex::new((0, 0)));
+  let (tx, rx) = channel();
+  for _ in 0..6 {
+    let running = running.clone();
+    let tx      = tx.clone();
+    pool.spawn(move || {
+      {
+        let mut running = running.lock().unwrap();
+        running.0 += 1;
+        running.1  = if running.0 > running.1 { running.0 } else { running.1 };
+      }
+      thread::sleep(Duration::from_millis(10));
+      running.lock().unwrap().0 -= 1;
+      tx.send(()).unwrap();
+    });
+  }
+  assert!(pool.queued() > 0);
+  assert_eq!(rx.iter().take(6).count(), 6);
+  assert_eq!(running.lock().unwrap().1, 2);
+}
+
+#[test]
+fn zero_bound() {
+  let pool     = ThreadPool::new(0);
+  let (tx, rx) = channel();
+  pool.spawn(move || tx.send(1).unwrap());
+  assert_eq!(rx.recv_timeout(Duration::from_secs(1)).unwrap(), 1);
+  assert_eq!(pool.bound(), 1);
+}
+
+#[test]
+fn reuse() {
+  let pool     = ThreadPool::new(1);
+  let (tx, rx) = channel();
+  for _ in 0..3 {
+    let tx = tx.clone();
+    pool.spawn(move || tx.send(thread::current().id()).unwrap());
+  }
+  let ids = rx.iter().take(3).collect::<Vec<_>>();
+  assert!(ids.iter().all(|id| *id == ids[0]));
+}
+
+#[test]
+fn wait_idle() {
+  let pool = ThreadPool::new(2);
+  let done = Arc::new(Mutex::new(0));
+  for _ in 0..6 {
+    let done = done.clone();
+    pool.spawn(move || {
+      thread::sleep(Duration::from_millis(10));
+      *done.lock().unwrap() += 1;
+    });
+  }
+  pool.wait_idle();
+  assert_eq!(*done.lock().unwrap(), 6);
+  assert_eq!(pool.active(), 0);
+  pool.wait_idle();
+}
+
+#[test]
+fn idle() {
+  let pool = ThreadPool::new(2);
+  let done = Arc::new(Mutex::new(0));
+  for _ in 0..4 {
+    let done = done.clone();
+    pool.spawn(move || {
+      thread::sleep(Duration::from_millis(10));
+      *done.lock().unwrap() += 1;
+    });
+  }
+  pool.idle().wait().unwrap();
+  assert_eq!(*done.lock().unwrap(), 4);
+}
+
+#[test]
+fn panic_restarts_thread() {
+  let pool   = ThreadPool::new(1);
+  let errors = Arc::new(Mutex::new(Vec::new()));
+  let failed = errors.clone();
+  pool.on_error(move |error| failed.lock().unwrap().push(error.to_string()));
+  let (tx, rx) = channel();
+  pool.spawn(|| panic!("boom"));
+  for n in 0..3 {
+    let tx = tx.clone();
+    pool.spawn(move || tx.send(n).unwrap());
+  }
+  assert_eq!(rx.iter().take(3).sum::<i32>(), 3);
+  pool.wait_idle();
+  assert_eq!(errors.lock().unwrap().len(), 1);
+  assert!(errors.lock().unwrap()[0].contains("boom"));
+}
+
+#[test]
+fn try_spawn() {
+  let pool     = ThreadPool::bounded(1, 2);
+  let (started, running) = channel();
+  let (release, blocked) = channel::<()>();
+  pool.try_spawn(move || {
+    started.send(()).unwrap();
+    blocked.recv().unwrap();
+  }).unwrap();
+  running.recv().unwrap();
+  pool.try_spawn(|| {}).unwrap();
+  pool.try_spawn(|| {}).unwrap();
+  match pool.try_spawn(|| {}) {
+    Err(smoke::Error::Rejected) => {},
+    result                      => panic!("expected rejected, found {:?}", result)
+  }
+  release.send(()).unwrap();
+  pool.wait_idle();
+  assert!(pool.try_spawn(|| {}).is_ok());
+}
+
+#[test]
+fn spawn_blocks_while_full() {
+  let pool     = ThreadPool::bounded(1, 1);
+  let (tx, rx) = channel();
+  for n in 0..4 {
+    let tx = tx.clone();
+    pool.spawn(move || {
+      thread::sleep(Duration::from_millis(5));
+      tx.send(n).unwrap();
+    });
+    assert!(pool.queued() <= 1);
+  }
+  assert_eq!(rx.iter().take(4).collect::<Vec<_>>(), vec![0, 1, 2, 3]);
+}
+
+#[test]
+fn keep_alive() {
+  let pool = ThreadPool::new(4);
+  pool.keep_alive(Duration::from_millis(20));
+  for _ in 0..4 {
+    pool.spawn(|| thread::sleep(Duration::from_millis(10)));
+  }
+  assert_eq!(pool.threads(), 4);
+  pool.wait_idle();
+  thread::sleep(Duration::from_millis(100));
+  assert_eq!(pool.threads(), 0);
+  let (tx, rx) = channel();
+  pool.spawn(move || tx.send(1).unwrap());
+  assert_eq!(rx.recv_timeout(Duration::from_secs(1)).unwrap(), 1);
+}
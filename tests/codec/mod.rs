@@ -0,0 +1,41 @@
+use smoke::codec;
+use smoke::async::{Stream, ToStream};
+
+#[test]
+fn round_trip() {
+  let messages = vec![(1, "a".to_string()), (2, "b".to_string())];
+  let bytes    = codec::encode(messages.clone().into_iter().to_stream());
+  let decoded  = codec::decode::<(i32, String)>(bytes);
+  assert_eq!(decoded.read().iter().collect::<Vec<_>>(), messages);
+}
+
+#[test]
+fn decode_across_chunks() {
+  let chunks  = vec![b"[1,".to_vec(), b"2]\n[3".to_vec(), b",4]\n".to_vec()].into_iter().to_stream();
+  let decoded = codec::decode::<Vec<i32>>(chunks);
+  assert_eq!(decoded.read().iter().collect::<Vec<_>>(), vec![vec![1, 2], vec![3, 4]]);
+}
+
+#[test]
+fn decode_error() {
+  let receiver = codec::decode::<i32>(Stream::once(b"1\nnot json\n2\n".to_vec())).read();
+  assert_eq!(receiver.iter().collect::<Vec<_>>(), vec![1]);
+  match receiver.error() {
+    Some(smoke::Error::Protocol(_)) => {/* ok */},
+    error => panic!("unexpected error: {:?}", error)
+  }
+}
+
+#[test]
+fn encoder() {
+  use std::sync::mpsc::channel;
+  let (tx, rx) = channel();
+  let bytes    = Stream::<Vec<u8>>::input(move |receiver| {
+    for line in receiver {
+      tx.send(line).unwrap();
+    }
+  });
+  let messages = codec::encoder(bytes);
+  messages.send(vec!["a", "b"]).unwrap();
+  assert_eq!(rx.recv().unwrap(), b"[\"a\",\"b\"]\n".to_vec());
+}
@@ -0,0 +1,76 @@
+use smoke::Error;
+use smoke::async::{Task, ThreadScheduler};
+use std::io;
+use std::error::Error as StdError;
+use std::sync::mpsc::{SendError, RecvError, RecvTimeoutError};
+
+#[test]
+fn display() {
+  assert_eq!(format!("{}", Error::Panicked("boom".to_string())), "task panicked: boom");
+  assert_eq!(format!("{}", Error::Timeout), "operation timed out");
+  assert_eq!(format!("{}", Error::NoResult(None)), "task completed without a result");
+}
+
+#[test]
+fn from_io() {
+  let error = Error::from(io::Error::new(io::ErrorKind::Other, "io"));
+  match error {
+    Error::Io(_) => assert!(error.source().is_some()),
+    _ => panic!("unexpected error")
+  }
+}
+
+#[test]
+fn from_send() {
+  match Error::from(SendError(1)) {
+    Error::Send => {/* ok */},
+    _ => panic!("unexpected error")
+  }
+}
+
+#[test]
+fn from_recv() {
+  match Error::from(RecvError) {
+    Error::Recv => {/* ok */},
+    _ => panic!("unexpected error")
+  }
+}
+
+#[test]
+fn from_recv_timeout() {
+  match Error::from(RecvTimeoutError::Timeout) {
+    Error::Timeout => {/* ok */},
+    _ => panic!("unexpected error")
+  }
+}
+
+#[test]
+fn task_without_result() {
+  let task = Task::<i32>::new(|_| Ok(()));
+  match task.wait() {
+    Err(Error::NoResult(None)) => {/* ok */},
+    _ => panic!("unexpected result")
+  }
+}
+
+#[test]
+fn named_task_without_result() {
+  let task = Task::<i32>::new(|_| Ok(())).named("fetch-user");
+  match task.schedule(ThreadScheduler).wait() {
+    Err(error) => assert_eq!(error.to_string(), "task 'fetch-user' completed without a result"),
+    Ok(_)      => panic!("unexpected result")
+  }
+}
+
+#[test]
+fn clone() {
+  match Error::Io(io::Error::new(io::ErrorKind::NotFound, "missing")).clone() {
+    Error::Io(error) => assert_eq!(error.kind(), io::ErrorKind::NotFound),
+    _ => panic!("unexpected error")
+  }
+}
+
+#[test]
+fn rejected() {
+  assert_eq!(format!("{}", Error::Rejected.clone()), "task rejected by a full scheduler queue");
+}
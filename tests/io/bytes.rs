@@ -0,0 +1,48 @@
+use smoke::async::{Stream, ToStream};
+use smoke::io::ByteStream;
+
+fn chunks(chunks: Vec<&'static [u8]>) -> Stream<Vec<u8>> {
+  chunks.into_iter().map(|chunk| chunk.to_vec()).to_stream()
+}
+
+#[test]
+fn lines() {
+  let lines = chunks(vec![b"a\nb", b"c\n", b"\n", b"d"]).lines();
+  assert_eq!(lines.read().iter().collect::<Vec<_>>(), vec!["a\n", "bc\n", "\n", "d"]);
+}
+
+#[test]
+fn lines_invalid_utf8() {
+  let receiver = chunks(vec![b"a\n", &[0xff, b'\n']]).lines().read();
+  assert_eq!(receiver.iter().collect::<Vec<_>>(), vec!["a\n"]);
+  assert!(receiver.error().is_some());
+}
+
+#[test]
+fn split() {
+  let records = chunks(vec![b"a,b", b"c,", b",d"]).split(b',');
+  assert_eq!(records.read().iter().collect::<Vec<_>>(), vec![b"a".to_vec(), b"bc".to_vec(), vec![], b"d".to_vec()]);
+}
+
+#[test]
+fn frames() {
+  let frames = chunks(vec![b"abcde", b"f", b"ghijklm"]).frames(4);
+  assert_eq!(frames.read().iter().collect::<Vec<_>>(), vec![b"abcd".to_vec(), b"efgh".to_vec(), b"ijkl".to_vec(), b"m".to_vec()]);
+}
+
+#[test]
+fn failed_source() {
+  let failing = || Stream::output(|sender| {
+    try!(sender.send(b"a\nb".to_vec()));
+    sender.fail(smoke::Error::Timeout)
+  });
+  let receiver = failing().lines().read();
+  assert_eq!(receiver.iter().collect::<Vec<_>>(), vec!["a\n"]);
+  assert!(receiver.error().is_some());
+  let receiver = failing().split(b'\n').read();
+  assert_eq!(receiver.iter().collect::<Vec<_>>(), vec![b"a".to_vec()]);
+  assert!(receiver.error().is_some());
+  let receiver = failing().frames(2).read();
+  assert_eq!(receiver.iter().collect::<Vec<_>>(), vec![b"a\n".to_vec()]);
+  assert!(receiver.error().is_some());
+}
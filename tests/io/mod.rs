@@ -1 +1,2 @@
-pub mod read;
\ No newline at end of file
+pub mod read;
+pub mod bytes;
@@ -13,4 +13,41 @@ fn to_line_stream() {
   let read = empty();
   let stream = read.to_line_stream();
   for _ in stream.read() {}
-}
\ No newline at end of file
+}
+#[test]
+fn line_stream_header() {
+  let reader = std::io::Cursor::new("a: 1\nb: 2\n\nbody\n".as_bytes().to_vec());
+  let header = reader.to_line_stream().take_while(|line| line.trim().len() > 0);
+  assert_eq!(header.read().iter().collect::<Vec<_>>(), vec!["a: 1\n", "b: 2\n"]);
+}
+
+#[test]
+fn line_numbers() {
+  let reader = std::io::Cursor::new("a\nb\n".as_bytes().to_vec());
+  let lines  = reader.to_line_stream().enumerate().map(|(index, line)| format!("{}: {}", index + 1, line));
+  assert_eq!(lines.read().iter().collect::<Vec<_>>(), vec!["1: a\n", "2: b\n"]);
+}
+
+#[test]
+fn to_stream_error() {
+  use std::io;
+  use smoke::Error;
+  struct Failing { reads: usize }
+  impl io::Read for Failing {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+      self.reads += 1;
+      match self.reads {
+        1 => { buf[0] = 1; Ok(1) },
+        _ => Err(io::Error::new(io::ErrorKind::Other, "disconnected"))
+      }
+    }
+  }
+  let receiver = Failing { reads: 0 }.to_stream(16).read();
+  assert_eq!(receiver.iter().collect::<Vec<_>>(), vec![vec![1]]);
+  match receiver.error() {
+    Some(Error::Io(error)) => assert_eq!(error.to_string(), "disconnected"),
+    error => panic!("unexpected error: {:?}", error)
+  }
+  let total = Failing { reads: 0 }.to_stream(16).map(|bytes| bytes.len()).fold(0, |p, c| p + c);
+  assert!(total.wait().is_err());
+}
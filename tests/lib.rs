@@ -1,4 +1,8 @@
 extern crate smoke;
 
 mod async;
-mod io;
\ No newline at end of file
+#[cfg(feature = "codec")]
+mod codec;
+mod error;
+mod io;
+mod metrics;
\ No newline at end of file
@@ -0,0 +1,67 @@
+use smoke::async::{Task, Stream};
+use smoke::io::Read;
+use std::time::Duration;
+
+#[test]
+fn snapshot() {
+  let _ = smoke::metrics();
+}
+
+#[test]
+fn tasks_executed() {
+  let before = smoke::metrics();
+  Task::new(|sender| sender.send(1)).wait().unwrap();
+  let after = smoke::metrics();
+  assert!(after.tasks_scheduled > before.tasks_scheduled);
+  assert!(after.tasks_executed  > before.tasks_executed);
+}
+
+#[test]
+fn delays_started() {
+  let before = smoke::metrics();
+  Task::delay_for(Duration::from_millis(1)).wait().unwrap();
+  let after = smoke::metrics();
+  assert!(after.delays_started > before.delays_started);
+}
+
+#[test]
+fn streams_started() {
+  let before = smoke::metrics();
+  for _ in Stream::range(0, 4).read() {}
+  let after = smoke::metrics();
+  assert!(after.streams_started > before.streams_started);
+}
+
+#[test]
+fn bytes_read() {
+  let before = smoke::metrics();
+  let bytes  = std::io::Cursor::new(vec![0; 16]);
+  for _ in bytes.to_stream(4).read() {}
+  let after = smoke::metrics();
+  assert!(after.bytes_read >= before.bytes_read + 16);
+}
+
+#[test]
+fn subscribe() {
+  let receiver = smoke::metrics::subscribe(Duration::from_millis(1)).read();
+  let first    = receiver.recv().unwrap();
+  let second   = receiver.recv().unwrap();
+  assert!(second.tasks_scheduled >= first.tasks_scheduled);
+}
+
+#[test]
+fn metered_streams() {
+  let slow = Stream::range(0, 4).map(|n| {
+    std::thread::sleep(Duration::from_millis(10));
+    n
+  }).metered("slow_source");
+  let total = slow.metered_by("slow_source_bytes", |_| 4).fold(0, |p, c| p + c).wait().unwrap();
+  assert_eq!(total, 6);
+  let source = smoke::metrics::stream("slow_source").unwrap();
+  assert_eq!(source.elements, 4);
+  assert!(source.recv_blocked >= Duration::from_millis(30));
+  let bytes  = smoke::metrics::stream("slow_source_bytes").unwrap();
+  assert_eq!(bytes.bytes, 16);
+  assert!(smoke::metrics::streams().iter().any(|stream| stream.name == "slow_source"));
+  assert!(smoke::metrics::stream("unknown").is_none());
+}